@@ -1,65 +1,169 @@
 use crate::environment::Environment;
-use crate::error::LoxError;
+use crate::error::{ErrorKind, LoxError};
 use crate::expr::variable::Variable;
 use crate::function::lox_function::LoxFunction;
+use crate::function::{Arity, LoxCallable};
+use crate::heap::Tracer;
 use crate::instance::LoxInstance;
 use crate::interpreter::Interpreter;
 use crate::object::Object;
+use crate::symbol::{self, Symbol};
+use crate::token::Token;
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
+use std::rc::Rc;
 
-#[derive(Clone, Debug)]
+/// classes are immutable once declared, they are shared through `Rc` and compared by identity
+#[derive(Debug)]
 pub(crate) struct LoxClass {
-    pub name: String,
-    superclass: Option<Box<LoxClass>>,
-    methods: HashMap<String, LoxFunction>,
+    pub name: Symbol,
+    superclass: Option<Rc<LoxClass>>,
+    methods: HashMap<Symbol, LoxFunction>,
+    /// holds the static methods, a class object is the instance of its metaclass
+    metaclass: Option<Rc<LoxClass>>,
+    /// class-level constants, such as PI in class Math { PI = 3.14159; }
+    fields: HashMap<Symbol, Option<Object>>,
 }
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub(crate) enum ClassType {
     NONE,
     CLASS,
     SUBCLASS,
+    TRAIT,
 }
 
 impl LoxClass {
     pub fn new(
-        name: String,
-        superclass: Option<LoxClass>,
-        methods: HashMap<String, LoxFunction>,
+        name: Symbol,
+        superclass: Option<Rc<LoxClass>>,
+        methods: HashMap<Symbol, LoxFunction>,
+        metaclass: Option<Rc<LoxClass>>,
+        fields: HashMap<Symbol, Option<Object>>,
     ) -> Self {
         LoxClass {
             name,
-            superclass: superclass.map(|c| Box::new(c)),
+            superclass,
             methods,
+            metaclass,
+            fields,
         }
     }
 
-    pub(crate) fn find_method(&self, name: &str) -> Option<LoxFunction> {
+    pub(crate) fn find_method(&self, name: &Symbol) -> Option<LoxFunction> {
+        if let Some(method) = self.methods.get(name) {
+            return Some(method.clone());
+        }
         if let Some(superclass) = &self.superclass {
             return superclass.find_method(name);
         }
-        self.methods.get(name).map(|v| v.clone())
+        None
+    }
+
+    /// whether this class is `class` or one of its subclasses
+    pub(crate) fn inherits(&self, class: &LoxClass) -> bool {
+        std::ptr::eq(self, class)
+            || self
+                .superclass
+                .as_ref()
+                .is_some_and(|superclass| superclass.inherits(class))
+    }
+
+    pub(crate) fn metaclass(&self) -> Option<Rc<LoxClass>> {
+        self.metaclass.clone()
+    }
+
+    /// the environments the methods close over, the superclass, metaclass and class constants
+    pub(crate) fn trace(&self, tracer: &mut Tracer) {
+        if let Some(superclass) = &self.superclass {
+            tracer.edge(superclass);
+        }
+        if let Some(metaclass) = &self.metaclass {
+            tracer.edge(metaclass);
+        }
+        self.methods
+            .values()
+            .for_each(|method| tracer.edge(&method.closure));
+        self.fields
+            .values()
+            .for_each(|field| tracer.optional(field));
+    }
+
+    pub(crate) fn size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.methods.capacity()
+                * (std::mem::size_of::<Symbol>() + std::mem::size_of::<LoxFunction>())
+            + self.fields.capacity()
+                * (std::mem::size_of::<Symbol>() + std::mem::size_of::<Option<Object>>())
+    }
+
+    /// property access on the class object, class constants first, then static methods bound to the class
+    pub fn get(class: &Rc<LoxClass>, name: &Token) -> Result<Object, LoxError> {
+        if let Some(field) = class.find_field(&name.lexeme) {
+            return Ok(field.unwrap_or(Object::Void));
+        }
+        let method = class
+            .metaclass
+            .as_ref()
+            .and_then(|metaclass| metaclass.find_method(&name.lexeme));
+        if let Some(method) = method {
+            return Ok(Object::Function(Box::new(LoxCallable::LoxFunction(
+                method.bind(Object::Class(class.clone())),
+            ))));
+        }
+        Err(LoxError::new_runtime_error(
+            name.clone(),
+            ErrorKind::PropertyError,
+            format!("Undefined property '{}'.", name.lexeme),
+        ))
+    }
+
+    /// class constants are inherited by subclasses
+    pub(crate) fn find_field(&self, name: &Symbol) -> Option<Option<Object>> {
+        if let Some(field) = self.fields.get(name) {
+            return Some(field.clone());
+        }
+        self.superclass
+            .as_ref()
+            .and_then(|superclass| superclass.find_field(name))
     }
 
     pub fn call(
-        &self,
+        self: &Rc<Self>,
         interpreter: &mut Interpreter,
         arguments: Vec<Option<Object>>,
     ) -> Result<Option<Object>, LoxError> {
-        let instance = LoxInstance::new(self.clone());
-        let initializer = self.find_method("init");
+        let instance = LoxInstance::new(self.clone()).object();
+        let initializer = symbol::INIT.with(|init| self.find_method(init));
         if let Some(initializer) = initializer {
             initializer
                 .bind(instance.clone())
                 .call(interpreter, arguments)?;
         }
-        Ok(Some(Object::Instance(instance)))
+        Ok(Some(instance))
+    }
+
+    /// call with named arguments, they are passed on to the initializer
+    pub fn call_named(
+        self: &Rc<Self>,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Option<Object>>,
+        named: Vec<(Token, Option<Object>)>,
+        paren: &Token,
+    ) -> Result<Option<Object>, LoxError> {
+        let instance = LoxInstance::new(self.clone()).object();
+        let initializer = symbol::INIT.with(|init| self.find_method(init));
+        if let Some(initializer) = initializer {
+            initializer
+                .bind(instance.clone())
+                .call_named(interpreter, arguments, named, paren)?;
+        }
+        Ok(Some(instance))
     }
 
-    pub fn arity(&self) -> usize {
-        let initializer = self.find_method("init");
+    pub fn arity(&self) -> Arity {
+        let initializer = symbol::INIT.with(|init| self.find_method(init));
         match initializer {
-            None => 0,
+            None => Arity::exact(0),
             Some(initializer) => initializer.arity(),
         }
     }
@@ -73,6 +177,6 @@ impl Display for LoxClass {
 
 impl PartialEq for LoxClass {
     fn eq(&self, other: &Self) -> bool {
-        self.name.eq(&other.name)
+        std::ptr::eq(self, other)
     }
 }
@@ -0,0 +1,109 @@
+use crate::error::{ErrorKind, LoxError};
+use crate::expr::variable::Variable;
+use crate::instance::LoxInstance;
+use crate::list::LoxList;
+use crate::map::MapKey;
+use crate::object::Object;
+use crate::token::Token;
+
+/// names bound by a destructuring declaration or assignment
+#[derive(Debug, Clone)]
+pub(crate) enum Target {
+    /// such as [a, b, ...rest], takes the elements of a list in order
+    List {
+        bracket: Token,
+        names: Vec<Variable>,
+        rest: Option<Variable>,
+    },
+    /// such as {x, y}, reads instance fields or map keys with the same names
+    Fields { brace: Token, names: Vec<Variable> },
+}
+
+impl Target {
+    /// every name bound by the target, the rest name comes last
+    pub fn names(&self) -> Vec<&Variable> {
+        match self {
+            Target::List { names, rest, .. } => names.iter().chain(rest).collect(),
+            Target::Fields { names, .. } => names.iter().collect(),
+        }
+    }
+
+    /// pair each name with its part of `value`, the shape of `value` must match the target
+    pub fn unpack(
+        &self,
+        value: Option<Object>,
+    ) -> Result<Vec<(&Variable, Option<Object>)>, LoxError> {
+        match self {
+            Target::List {
+                bracket,
+                names,
+                rest,
+            } => {
+                let Some(Object::List(list)) = value else {
+                    return Err(LoxError::new_runtime_error(
+                        bracket.clone(),
+                        ErrorKind::TypeError,
+                        "Can only destructure a list with '[...]'.".into(),
+                    ));
+                };
+                let elements = list.borrow().elements.clone();
+                let matches = match rest {
+                    Some(_) => elements.len() >= names.len(),
+                    None => elements.len() == names.len(),
+                };
+                if !matches {
+                    let expected = match rest {
+                        Some(_) => format!("at least {}", names.len()),
+                        None => names.len().to_string(),
+                    };
+                    return Err(LoxError::new_runtime_error(
+                        bracket.clone(),
+                        ErrorKind::IndexError,
+                        format!(
+                            "Expected {} elements to destructure but got {}.",
+                            expected,
+                            elements.len()
+                        ),
+                    ));
+                }
+                let mut elements = elements.into_iter();
+                let mut values: Vec<(&Variable, Option<Object>)> =
+                    names.iter().zip(elements.by_ref()).collect();
+                if let Some(rest) = rest {
+                    values.push((rest, Some(LoxList::object(elements.collect()))));
+                }
+                Ok(values)
+            }
+            Target::Fields { brace, names } => {
+                let mut values = vec![];
+                for name in names {
+                    let field = match &value {
+                        Some(Object::Instance(instance)) => {
+                            LoxInstance::get(instance, name.name.clone())?
+                        }
+                        Some(Object::Map(map)) => {
+                            let key = MapKey::Str(name.name.lexeme.text());
+                            let Some(field) = map.borrow().get(&key) else {
+                                return Err(LoxError::new_runtime_error(
+                                    name.name.clone(),
+                                    ErrorKind::PropertyError,
+                                    format!("Map has no key '{}'.", name.name.lexeme),
+                                ));
+                            };
+                            field
+                        }
+                        _ => {
+                            return Err(LoxError::new_runtime_error(
+                                brace.clone(),
+                                ErrorKind::TypeError,
+                                "Can only destructure instances and maps with '{...}'.".into(),
+                            ))
+                        }
+                    };
+                    values.push((name, field));
+                }
+                Ok(values)
+            }
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use crate::error::{ErrorKind, LoxError};
+use crate::function::native_function::NativeFunction;
+use crate::function::{Arity, LoxCallable};
+use crate::heap::{self, Managed, Tracer};
+use crate::interpreter::Interpreter;
+use crate::object::{repr, Object};
+use crate::symbol::Symbol;
+use crate::token::Token;
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+
+/// an enum declaration, such as enum Shape { Circle(radius), Rect(w, h), Empty }
+#[derive(Debug)]
+pub(crate) struct LoxEnum {
+    pub name: Symbol,
+    /// name and field names of every variant, in declaration order
+    variants: Vec<(Symbol, Vec<Symbol>)>,
+}
+
+impl LoxEnum {
+    pub fn new(name: Symbol, variants: Vec<(Symbol, Vec<Symbol>)>) -> Self {
+        LoxEnum { name, variants }
+    }
+
+    /// `Shape.Circle` is a constructor, `Shape.Empty` is the variant itself since it has no fields
+    pub fn get(lox_enum: &Rc<LoxEnum>, name: &Token) -> Result<Object, LoxError> {
+        let Some(index) = lox_enum
+            .variants
+            .iter()
+            .position(|(v, _)| *v == name.lexeme)
+        else {
+            return Err(LoxError::new_runtime_error(
+                name.clone(),
+                ErrorKind::PropertyError,
+                format!("Enum '{}' has no variant '{}'.", lox_enum.name, name.lexeme),
+            ));
+        };
+        if lox_enum.variants[index].1.is_empty() {
+            return Ok(Object::Variant(heap::track(LoxVariant {
+                lox_enum: lox_enum.clone(),
+                index,
+                values: vec![],
+            })));
+        }
+        Ok(Object::Function(Box::new(LoxCallable::NativeFunction(
+            NativeFunction::VariantConstructor(VariantConstructor {
+                lox_enum: lox_enum.clone(),
+                index,
+            }),
+        ))))
+    }
+}
+
+impl PartialEq for LoxEnum {
+    fn eq(&self, other: &Self) -> bool {
+        self.name.eq(&other.name)
+    }
+}
+
+impl Display for LoxEnum {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<enum {}>", self.name)
+    }
+}
+
+/// a value of an enum, such as Shape.Circle(2)
+#[derive(Debug)]
+pub(crate) struct LoxVariant {
+    pub lox_enum: Rc<LoxEnum>,
+    pub index: usize,
+    pub values: Vec<Option<Object>>,
+}
+
+impl LoxVariant {
+    pub fn name(&self) -> &str {
+        &self.lox_enum.variants[self.index].0
+    }
+
+    fn fields(&self) -> &[Symbol] {
+        &self.lox_enum.variants[self.index].1
+    }
+
+    /// variants are equal when they are the same variant of the same enum with equal values
+    pub fn is_equal(&self, other: &LoxVariant) -> bool {
+        Rc::ptr_eq(&self.lox_enum, &other.lox_enum)
+            && self.index == other.index
+            && self
+                .values
+                .iter()
+                .zip(&other.values)
+                .all(|pair| match pair {
+                    (None, None) => true,
+                    (Some(a), Some(b)) => a.is_equal(b),
+                    _ => false,
+                })
+    }
+
+    /// fields by name, `variant` is the variant name and `toString()` its printed form
+    pub fn get(variant: &Rc<LoxVariant>, name: &Token) -> Result<Option<Object>, LoxError> {
+        if let Some(position) = variant.fields().iter().position(|f| *f == name.lexeme) {
+            return Ok(variant.values[position].clone());
+        }
+        match name.lexeme.as_str() {
+            "variant" => Ok(Some(Object::Str(variant.name().into()))),
+            "toString" => Ok(Some(Object::Function(Box::new(
+                LoxCallable::NativeFunction(NativeFunction::VariantToString(variant.clone())),
+            )))),
+            _ => Err(LoxError::new_runtime_error(
+                name.clone(),
+                ErrorKind::PropertyError,
+                format!(
+                    "Variant '{}.{}' has no field '{}'.",
+                    variant.lox_enum.name,
+                    variant.name(),
+                    name.lexeme
+                ),
+            )),
+        }
+    }
+}
+
+/// variants are immutable, a cycle through one goes through the list, map or instance it holds too
+impl Managed for LoxVariant {
+    fn trace(&self, tracer: &mut Tracer) -> bool {
+        self.values.iter().for_each(|value| tracer.optional(value));
+        true
+    }
+
+    fn clear(&self) {}
+
+    fn size(&self) -> usize {
+        std::mem::size_of::<Self>() + self.values.capacity() * std::mem::size_of::<Option<Object>>()
+    }
+}
+
+impl PartialEq for LoxVariant {
+    fn eq(&self, other: &Self) -> bool {
+        self.is_equal(other)
+    }
+}
+
+impl Display for LoxVariant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.lox_enum.name, self.name())?;
+        if !self.values.is_empty() {
+            let values = self.values.iter().map(repr).collect::<Vec<String>>();
+            write!(f, "({})", values.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// constructor of a variant with fields, such as Shape.Circle
+#[derive(Debug, Clone)]
+pub struct VariantConstructor {
+    pub(crate) lox_enum: Rc<LoxEnum>,
+    pub(crate) index: usize,
+}
+
+impl VariantConstructor {
+    pub fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: Vec<Option<Object>>,
+    ) -> Result<Option<Object>, LoxError> {
+        Ok(Some(Object::Variant(heap::track(LoxVariant {
+            lox_enum: self.lox_enum.clone(),
+            index: self.index,
+            values: arguments,
+        }))))
+    }
+
+    pub fn arity(&self) -> Arity {
+        Arity::exact(self.lox_enum.variants[self.index].1.len())
+    }
+
+    pub fn to_string(&self) -> String {
+        format!(
+            "<variant {}.{}>",
+            self.lox_enum.name, self.lox_enum.variants[self.index].0
+        )
+    }
+}
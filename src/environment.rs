@@ -1,90 +1,175 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::HashSet;
 use std::rc::Rc;
 
-use crate::error::{LoxError, ParseError};
+use crate::error::{ErrorKind, LoxError};
+use crate::heap::{self, Trace, Tracer};
 use crate::object::Object;
+use crate::symbol::Symbol;
 use crate::token::Token;
 
+/// where the resolver found a local variable, `depth` scopes out from the current one at `index` in that scope
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Slot {
+    pub depth: usize,
+    pub index: usize,
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct Environment {
     /// parent scope's env
     pub(crate) enclosing: Option<Rc<RefCell<Environment>>>,
-    /// current scope's env
-    values: HashMap<String, Option<Object>>,
+    /// current scope's values, in the order the names were defined
+    values: Vec<Option<Object>>,
+    /// name of each value, globals and names the resolver doesn't know about are looked up by name
+    names: Vec<Symbol>,
+    /// names declared with const in this scope
+    constants: HashSet<Symbol>,
 }
 
 impl Environment {
     pub fn new() -> Self {
         Environment {
             enclosing: None,
-            values: HashMap::new(),
+            values: vec![],
+            names: vec![],
+            constants: HashSet::new(),
         }
     }
 
     pub fn new_from_enclosing(enclosing: Rc<RefCell<Environment>>) -> Self {
         Environment {
             enclosing: Some(enclosing),
-            values: HashMap::new(),
+            values: vec![],
+            names: vec![],
+            constants: HashSet::new(),
         }
     }
 
+    /// index of a name in this scope
+    fn index_of(&self, name: &Symbol) -> Option<usize> {
+        self.names.iter().rposition(|n| n == name)
+    }
+
     pub fn get(&self, name: &Token) -> Result<Option<Object>, LoxError> {
-        let value = self.values.get(&name.lexeme);
-        match value {
-            Some(v) => Ok(v.clone()),
+        match self.index_of(&name.lexeme) {
+            Some(index) => Ok(self.values[index].clone()),
             None => {
-                if let Some(enclosing) = self.enclosing.clone() {
-                    return Ok(enclosing.borrow_mut().get(name)?.clone());
+                if let Some(enclosing) = &self.enclosing {
+                    return enclosing.borrow().get(name);
                 }
-                Err(LoxError::new_parse_error(
+                Err(LoxError::new_runtime_error(
                     name.clone(),
+                    ErrorKind::NameError,
                     format!("Undefined variable '{}'.", name.lexeme),
                 ))
             }
         }
     }
 
-    pub fn define(&mut self, name: String, value: Option<Object>) {
-        self.values.insert(name, value);
+    /// the value of a name in this scope only, `None` when it isn't defined here
+    pub fn get_here(&self, name: &Symbol) -> Option<Option<Object>> {
+        self.index_of(name).map(|index| self.values[index].clone())
+    }
+
+    /// define a name in the next slot, redefining a name reuses its slot, the resolver only allows that for globals
+    pub fn define(&mut self, name: Symbol, value: Option<Object>) {
+        match self.index_of(&name) {
+            Some(index) => self.values[index] = value,
+            None => {
+                self.names.push(name);
+                self.values.push(value);
+            }
+        }
+    }
+
+    pub fn define_constant(&mut self, name: Symbol, value: Option<Object>) {
+        self.define(name.clone(), value);
+        self.constants.insert(name);
+    }
+
+    /// whether a name of this scope was declared with const
+    pub fn is_constant(&self, name: &Symbol) -> bool {
+        self.constants.contains(name)
     }
 
     pub fn assign(&mut self, name: &Token, value: Option<Object>) -> Result<(), LoxError> {
-        if (self.values.contains_key(&name.lexeme)) {
-            self.values.insert(name.lexeme.clone(), value);
+        if self.constants.contains(&name.lexeme) {
+            return Err(LoxError::new_runtime_error(
+                name.clone(),
+                ErrorKind::TypeError,
+                format!("Can't assign to constant '{}'.", name.lexeme),
+            ));
+        }
+        if let Some(index) = self.index_of(&name.lexeme) {
+            self.values[index] = value;
             return Ok(());
         }
 
-        if let Some(enclosing) = &mut self.enclosing {
+        if let Some(enclosing) = &self.enclosing {
             return enclosing.borrow_mut().assign(name, value);
         }
 
-        return Err(LoxError::new_parse_error(
+        Err(LoxError::new_runtime_error(
             name.clone(),
+            ErrorKind::NameError,
             format!("Undefined variable '{}'.", name.lexeme),
-        ));
+        ))
     }
 
-    pub fn get_at(&self, distance: usize, name: &str) -> Option<Option<Object>> {
-        match self.ancestor(distance) {
-            None => Some(None),
-            Some(ancestor) => ancestor.borrow().values.get(name).map(|v| v.clone()),
+    pub fn get_at(&self, slot: Slot) -> Option<Object> {
+        if slot.depth == 0 {
+            return self.values[slot.index].clone();
         }
+        self.ancestor(slot.depth).borrow().values[slot.index].clone()
     }
 
-    pub fn assign_at(&mut self, distance: usize, name: &Token, value: Option<Object>) {
-        self.ancestor(distance)
-            .map(|env| env.borrow_mut().values.insert(name.lexeme.clone(), value));
+    /// the resolver already rejected assignments to local constants
+    pub fn assign_at(&mut self, slot: Slot, value: Option<Object>) {
+        if slot.depth == 0 {
+            self.values[slot.index] = value;
+            return;
+        }
+        self.ancestor(slot.depth).borrow_mut().values[slot.index] = value;
     }
 
-    fn ancestor(&self, distance: usize) -> Option<Rc<RefCell<Environment>>> {
-        let mut environment: Option<Rc<RefCell<Environment>>> =
-            Some(Rc::new(RefCell::new(self.clone())));
-        for i in 0..distance {
-            if let Some(env) = environment {
-                environment = env.borrow().enclosing.clone();
-            }
+    /// the scope `distance` levels out, the resolver guarantees it exists
+    fn ancestor(&self, distance: usize) -> Rc<RefCell<Environment>> {
+        let mut environment = self.enclosing.clone().expect("resolved scope");
+        for _ in 1..distance {
+            let enclosing = environment
+                .borrow()
+                .enclosing
+                .clone()
+                .expect("resolved scope");
+            environment = enclosing;
+        }
+        environment
+    }
+}
+
+impl Trace for Environment {
+    fn trace(&self, tracer: &mut Tracer) {
+        if let Some(enclosing) = &self.enclosing {
+            tracer.edge(enclosing);
         }
-        return environment;
+        self.values.iter().for_each(|value| tracer.optional(value));
+    }
+
+    fn clear(&mut self) {
+        self.enclosing = None;
+        self.values.iter_mut().for_each(|value| *value = None);
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.values.capacity() * std::mem::size_of::<Option<Object>>()
+            + self.names.capacity() * std::mem::size_of::<Symbol>()
+            + self
+                .values
+                .iter()
+                .flatten()
+                .map(heap::payload)
+                .sum::<usize>()
     }
 }
@@ -1,29 +1,101 @@
+use crate::function::lox_function::TailCall;
+use crate::limits::Limit;
 use crate::object::Object;
 use crate::token::Token;
 use std::fmt::{Display, Formatter};
 use thiserror::Error;
 
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum LoxError {
     ParseError(ParseError),
     ReturnError(Return),
+    /// `return f(x);` in tail position, the function being returned from makes the call
+    TailCall(Box<TailCall>),
+    /// error raised by the interpreter itself, such as a type error, catchable by `try`
+    RuntimeError(RuntimeError),
+    /// value thrown by a `throw` statement
+    ThrowError(Throw),
+    /// the run went over one of its budgets, not catchable by `try`
+    LimitError(Limit),
 }
 
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub(crate) struct ParseError {
     pub token: Token,
     pub message: String,
 }
 
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub struct Return {
     pub(crate) value: Option<Object>,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum ErrorKind {
+    Error,
+    TypeError,
+    NameError,
+    PropertyError,
+    ArityError,
+    ArithmeticError,
+    IndexError,
+    ImportError,
+    MatchError,
+    RecursionError,
+}
+
+#[derive(Debug, Clone, Error)]
+pub(crate) struct RuntimeError {
+    pub token: Token,
+    pub kind: ErrorKind,
+    pub message: String,
+    /// call stack at the point the error was raised, innermost frame first, filled in by the interpreter
+    pub trace: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Error)]
+pub(crate) struct Throw {
+    /// the `throw` keyword
+    pub keyword: Token,
+    pub value: Option<Object>,
+    /// call stack at the point the value was thrown, innermost frame first, filled in by the interpreter
+    pub trace: Option<Vec<String>>,
+}
+
 impl LoxError {
     pub fn new_parse_error(token: Token, message: String) -> Self {
         LoxError::ParseError(ParseError { token, message })
     }
+
+    pub fn new_runtime_error(token: Token, kind: ErrorKind, message: String) -> Self {
+        LoxError::RuntimeError(RuntimeError {
+            token,
+            kind,
+            message,
+            trace: None,
+        })
+    }
+
+    /// stack trace of a runtime error or thrown value, `None` for other errors
+    pub fn trace_mut(&mut self) -> Option<&mut Option<Vec<String>>> {
+        match self {
+            LoxError::RuntimeError(e) => Some(&mut e.trace),
+            LoxError::ThrowError(e) => Some(&mut e.trace),
+            _ => None,
+        }
+    }
+
+    /// line where a runtime error was raised or a value was thrown
+    pub fn line(&self) -> usize {
+        match self {
+            LoxError::ParseError(e) => e.token.line,
+            LoxError::ReturnError(_) => 0,
+            LoxError::TailCall(e) => e.paren.line,
+            LoxError::RuntimeError(e) => e.token.line,
+            LoxError::ThrowError(e) => e.keyword.line,
+            LoxError::LimitError(_) => 0,
+        }
+    }
 }
 
 impl ParseError {
@@ -44,11 +116,36 @@ impl Display for Return {
     }
 }
 
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.kind, self.message)
+    }
+}
+
+impl Display for Throw {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "Uncaught exception: {}", value),
+            None => write!(f, "Uncaught exception: nil"),
+        }
+    }
+}
+
 impl Display for LoxError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             LoxError::ParseError(e) => e.fmt(f),
             LoxError::ReturnError(e) => e.fmt(f),
+            LoxError::TailCall(e) => write!(f, "tail call to {}", e.function.to_string()),
+            LoxError::RuntimeError(e) => e.fmt(f),
+            LoxError::ThrowError(e) => e.fmt(f),
+            LoxError::LimitError(e) => e.fmt(f),
         }
     }
 }
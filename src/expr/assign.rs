@@ -1,4 +1,4 @@
-use crate::expr::Expr;
+use crate::expr::{Expr, NodeId};
 use crate::token::Token;
 
 /// assign is also an expr, the expr's value is assign.value, for example a = 33, the result will be 33.
@@ -7,7 +7,7 @@ use crate::token::Token;
 pub(crate) struct Assign {
     pub name: Token,
     pub value: Expr,
-    pub distance: Option<usize>,
+    pub id: NodeId,
 }
 
 impl Assign {}
@@ -0,0 +1,9 @@
+use crate::destructure::Target;
+use crate::expr::Expr;
+
+/// assign several variables at once, such as [a, b] = [b, a], the value of the expression is the right side
+#[derive(Debug, Clone)]
+pub(crate) struct AssignDestructure {
+    pub target: Target,
+    pub value: Expr,
+}
@@ -0,0 +1,9 @@
+use crate::expr::Expr;
+use crate::token::Token;
+
+/// await value, suspends the running async function until the awaited promise settles
+#[derive(Debug, Clone)]
+pub(crate) struct Await {
+    pub keyword: Token,
+    pub value: Expr,
+}
@@ -1,4 +1,4 @@
-use crate::expr::Expr;
+use crate::expr::{Expr, NodeId};
 use crate::token::Token;
 
 #[derive(Debug, Clone)]
@@ -8,4 +8,8 @@ pub(crate) struct Call {
     /// right paren, use when a runtime error occur caused by a function call,  report function’s location
     pub paren: Token,
     pub arguments: Vec<Expr>,
+    /// named arguments, such as greeting: "Hi" in greet("Bob", greeting: "Hi")
+    pub named: Vec<(Token, Expr)>,
+    /// the resolver marks calls in tail position by id
+    pub id: NodeId,
 }
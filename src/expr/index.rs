@@ -0,0 +1,11 @@
+use crate::expr::Expr;
+use crate::token::Token;
+
+/// subscript read, such as m[key]
+#[derive(Debug, Clone)]
+pub(crate) struct Index {
+    pub object: Expr,
+    /// right bracket, use to report errors
+    pub bracket: Token,
+    pub index: Expr,
+}
@@ -0,0 +1,7 @@
+use crate::expr::Expr;
+
+/// list literal, such as [1, 2, 3]
+#[derive(Debug, Clone)]
+pub(crate) struct List {
+    pub elements: Vec<Expr>,
+}
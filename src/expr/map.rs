@@ -0,0 +1,10 @@
+use crate::expr::Expr;
+use crate::token::Token;
+
+/// map literal, such as {"a": 1, "b": 2}
+#[derive(Debug, Clone)]
+pub(crate) struct Map {
+    /// left brace, use to report errors such as an unhashable key
+    pub brace: Token,
+    pub entries: Vec<(Expr, Expr)>,
+}
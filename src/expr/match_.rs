@@ -0,0 +1,11 @@
+use crate::expr::Expr;
+use crate::pattern::Arm;
+use crate::token::Token;
+
+/// match (value) { pattern => expression, ... }, evaluates the body of the first matching arm
+#[derive(Debug, Clone)]
+pub(crate) struct Match {
+    pub keyword: Token,
+    pub subject: Expr,
+    pub arms: Vec<Arm>,
+}
@@ -1,25 +1,50 @@
 use std::fmt::{Debug, Display};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
+use crate::destructure::Target;
 use crate::error::LoxError;
 use crate::expr::Expr::{
-    Assign, Binary, Call, Get, Grouping, Literal, Logical, Set, Super, This, Unary, Variable,
+    Assign, AssignDestructure, Await, Binary, Call, Get, Grouping, Index, List, Literal, Logical,
+    Map, Match, Set, SetIndex, Super, This, Unary, Variable, Yield,
 };
 use crate::object::Object;
+use crate::pattern::Arm;
 use crate::token::Token;
 
 pub mod assign;
+pub(crate) mod assign_destructure;
 pub mod ast_printer;
+pub(crate) mod await_;
 pub mod binary;
 pub mod call;
 pub(crate) mod get;
 pub mod grouping;
+pub(crate) mod index;
+pub(crate) mod list;
 pub mod literal;
 pub mod logical;
+pub(crate) mod map;
+pub(crate) mod match_;
 pub(crate) mod set;
+pub(crate) mod set_index;
 pub(crate) mod super_;
 pub(crate) mod this;
 pub mod unary;
 pub(crate) mod variable;
+pub(crate) mod yield_;
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// identifies an expression the resolver binds to a declaration, copies of a node keep its id
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct NodeId(usize);
+
+impl NodeId {
+    /// ids are unique across every parsed file, so one side table serves the script and its modules
+    pub fn next() -> Self {
+        NodeId(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum Expr {
@@ -35,21 +60,22 @@ pub enum Expr {
     Set(Box<set::Set>),
     This(this::This),
     Super(super_::Super),
+    List(Box<list::List>),
+    Map(Box<map::Map>),
+    Index(Box<index::Index>),
+    SetIndex(Box<set_index::SetIndex>),
+    AssignDestructure(Box<assign_destructure::AssignDestructure>),
+    Match(Box<match_::Match>),
+    Yield(Box<yield_::Yield>),
+    Await(Box<await_::Await>),
 }
 
 impl Expr {
-    pub fn distance(&self) -> Option<usize> {
-        match self {
-            Variable(v) => v.distance,
-            Assign(v) => v.distance,
-            _ => None,
-        }
-    }
     pub fn assign(name: Token, expr: Expr) -> Self {
         Assign(Box::new(assign::Assign {
             name,
             value: expr,
-            distance: None,
+            id: NodeId::next(),
         }))
     }
     pub fn binary(left: Expr, operator: Token, right: Expr) -> Self {
@@ -67,7 +93,7 @@ impl Expr {
     pub fn variable(name: Token) -> Self {
         Variable(variable::Variable {
             name,
-            distance: None,
+            id: NodeId::next(),
         })
     }
     pub fn logical(left: Expr, operator: Token, right: Expr) -> Self {
@@ -78,11 +104,18 @@ impl Expr {
         }))
     }
 
-    pub fn call(callee: Expr, paren: Token, arguments: Vec<Expr>) -> Self {
+    pub fn call(
+        callee: Expr,
+        paren: Token,
+        arguments: Vec<Expr>,
+        named: Vec<(Token, Expr)>,
+    ) -> Self {
         Call(Box::new(call::Call {
             callee,
             paren,
             arguments,
+            named,
+            id: NodeId::next(),
         }))
     }
     pub fn get(object: Expr, name: Token) -> Self {
@@ -98,27 +131,116 @@ impl Expr {
     }
 
     pub fn this(keyword: Token) -> Self {
-        This(this::This { keyword })
+        This(this::This {
+            keyword,
+            id: NodeId::next(),
+        })
     }
 
     pub fn super_(keyword: Token, method: Token) -> Self {
-        Super(super_::Super { keyword, method })
+        Super(super_::Super {
+            keyword,
+            method,
+            id: NodeId::next(),
+        })
+    }
+
+    pub fn list(elements: Vec<Expr>) -> Self {
+        List(Box::new(list::List { elements }))
+    }
+
+    pub fn map(brace: Token, entries: Vec<(Expr, Expr)>) -> Self {
+        Map(Box::new(map::Map { brace, entries }))
+    }
+
+    pub fn index(object: Expr, bracket: Token, index: Expr) -> Self {
+        Index(Box::new(index::Index {
+            object,
+            bracket,
+            index,
+        }))
+    }
+
+    pub fn set_index(object: Expr, bracket: Token, index: Expr, value: Expr) -> Self {
+        SetIndex(Box::new(set_index::SetIndex {
+            object,
+            bracket,
+            index,
+            value,
+        }))
+    }
+
+    pub fn assign_destructure(target: Target, value: Expr) -> Self {
+        AssignDestructure(Box::new(assign_destructure::AssignDestructure {
+            target,
+            value,
+        }))
+    }
+
+    pub fn r#match(keyword: Token, subject: Expr, arms: Vec<Arm>) -> Self {
+        Match(Box::new(match_::Match {
+            keyword,
+            subject,
+            arms,
+        }))
+    }
+
+    pub fn r#yield(keyword: Token, value: Option<Expr>) -> Self {
+        Yield(Box::new(yield_::Yield { keyword, value }))
+    }
+
+    pub fn r#await(keyword: Token, value: Expr) -> Self {
+        Await(Box::new(await_::Await { keyword, value }))
+    }
+
+    /// line of the expression's first token that has one, `None` for a literal
+    pub(crate) fn line(&self) -> Option<usize> {
+        match self {
+            Assign(v) => Some(v.name.line),
+            Binary(v) => Some(v.operator.line),
+            Grouping(v) => v.expression.line(),
+            Logical(v) => Some(v.operator.line),
+            Literal(_) => None,
+            Unary(v) => Some(v.operator.line),
+            Variable(v) => Some(v.name.line),
+            Call(v) => Some(v.paren.line),
+            Get(v) => Some(v.name.line),
+            Set(v) => Some(v.name.line),
+            This(v) => Some(v.keyword.line),
+            Super(v) => Some(v.keyword.line),
+            List(v) => v.elements.iter().find_map(Expr::line),
+            Map(v) => Some(v.brace.line),
+            Index(v) => Some(v.bracket.line),
+            SetIndex(v) => Some(v.bracket.line),
+            AssignDestructure(v) => v.value.line(),
+            Match(v) => Some(v.keyword.line),
+            Yield(v) => Some(v.keyword.line),
+            Await(v) => Some(v.keyword.line),
+        }
     }
 
     pub fn accept<V: Visitor>(&self, visitor: &mut V) -> Result<Option<Object>, LoxError> {
         match self {
-            Binary(v) => visitor.visit_binary_expr((**v).clone()),
-            Grouping(v) => visitor.visit_grouping_expr((**v).clone()),
-            Literal(v) => visitor.visit_literal_expr((**v).clone()),
-            Unary(v) => visitor.visit_unary_expr((**v).clone()),
-            Assign(v) => visitor.visit_assign_expr(*v.clone()),
-            Variable(v) => visitor.visit_variable_expr(v.clone()),
-            Logical(v) => visitor.visit_logical_expr(*v.clone()),
-            Call(v) => visitor.visit_call_expr(*v.clone()),
-            Get(v) => visitor.visit_get_expr(*v.clone()),
-            Set(v) => visitor.visit_set_expr(*v.clone()),
-            This(v) => visitor.visit_this_expr(v.clone()),
-            Super(v) => visitor.visit_super_expr(v.clone()),
+            Binary(v) => visitor.visit_binary_expr(v),
+            Grouping(v) => visitor.visit_grouping_expr(v),
+            Literal(v) => visitor.visit_literal_expr(v),
+            Unary(v) => visitor.visit_unary_expr(v),
+            Assign(v) => visitor.visit_assign_expr(v),
+            Variable(v) => visitor.visit_variable_expr(v),
+            Logical(v) => visitor.visit_logical_expr(v),
+            Call(v) => visitor.visit_call_expr(v),
+            Get(v) => visitor.visit_get_expr(v),
+            Set(v) => visitor.visit_set_expr(v),
+            This(v) => visitor.visit_this_expr(v),
+            Super(v) => visitor.visit_super_expr(v),
+            List(v) => visitor.visit_list_expr(v),
+            Map(v) => visitor.visit_map_expr(v),
+            Index(v) => visitor.visit_index_expr(v),
+            SetIndex(v) => visitor.visit_set_index_expr(v),
+            AssignDestructure(v) => visitor.visit_assign_destructure_expr(v),
+            Match(v) => visitor.visit_match_expr(v),
+            Yield(v) => visitor.visit_yield_expr(v),
+            Await(v) => visitor.visit_await_expr(v),
         }
     }
 }
@@ -137,33 +259,67 @@ pub(crate) trait Visitor {
     // R visitUnaryExpr(Unary expr);
     // R visitVariableExpr(Variable expr);
 
-    fn visit_literal_expr(&self, expr: literal::Literal) -> Result<Option<Object>, LoxError>;
+    fn visit_literal_expr(&self, expr: &literal::Literal) -> Result<Option<Object>, LoxError>;
 
-    fn visit_grouping_expr(&mut self, expr: grouping::Grouping)
-        -> Result<Option<Object>, LoxError>;
+    fn visit_grouping_expr(
+        &mut self,
+        expr: &grouping::Grouping,
+    ) -> Result<Option<Object>, LoxError>;
 
-    fn visit_unary_expr(&mut self, expr: unary::Unary) -> Result<Option<Object>, LoxError>;
+    fn visit_unary_expr(&mut self, expr: &unary::Unary) -> Result<Option<Object>, LoxError>;
 
-    fn visit_binary_expr(&mut self, expr: binary::Binary) -> Result<Option<Object>, LoxError>;
+    fn visit_binary_expr(&mut self, expr: &binary::Binary) -> Result<Option<Object>, LoxError>;
 
     /// read expr value
-    fn visit_variable_expr(&mut self, expr: variable::Variable)
-        -> Result<Option<Object>, LoxError>;
+    fn visit_variable_expr(
+        &mut self,
+        expr: &variable::Variable,
+    ) -> Result<Option<Object>, LoxError>;
 
     /// evalue right value and assign to left var name
-    fn visit_assign_expr(&mut self, expr: assign::Assign) -> Result<Option<Object>, LoxError>;
+    fn visit_assign_expr(&mut self, expr: &assign::Assign) -> Result<Option<Object>, LoxError>;
 
     /// evalue logical expression
-    fn visit_logical_expr(&mut self, expr: logical::Logical) -> Result<Option<Object>, LoxError>;
+    fn visit_logical_expr(&mut self, expr: &logical::Logical) -> Result<Option<Object>, LoxError>;
 
     /// execute function
-    fn visit_call_expr(&mut self, expr: call::Call) -> Result<Option<Object>, LoxError>;
+    fn visit_call_expr(&mut self, expr: &call::Call) -> Result<Option<Object>, LoxError>;
+
+    fn visit_get_expr(&mut self, expr: &get::Get) -> Result<Option<Object>, LoxError>;
+
+    fn visit_set_expr(&mut self, expr: &set::Set) -> Result<Option<Object>, LoxError>;
+
+    fn visit_this_expr(&mut self, expr: &this::This) -> Result<Option<Object>, LoxError>;
+
+    fn visit_super_expr(&mut self, expr: &super_::Super) -> Result<Option<Object>, LoxError>;
+
+    /// build a new list from element expressions
+    fn visit_list_expr(&mut self, expr: &list::List) -> Result<Option<Object>, LoxError>;
+
+    /// build a new map from key/value expressions
+    fn visit_map_expr(&mut self, expr: &map::Map) -> Result<Option<Object>, LoxError>;
+
+    /// read an element of a list or map
+    fn visit_index_expr(&mut self, expr: &index::Index) -> Result<Option<Object>, LoxError>;
+
+    /// write an element of a list or map
+    fn visit_set_index_expr(
+        &mut self,
+        expr: &set_index::SetIndex,
+    ) -> Result<Option<Object>, LoxError>;
 
-    fn visit_get_expr(&mut self, expr: get::Get) -> Result<Option<Object>, LoxError>;
+    /// assign every name of a destructuring target
+    fn visit_assign_destructure_expr(
+        &mut self,
+        expr: &assign_destructure::AssignDestructure,
+    ) -> Result<Option<Object>, LoxError>;
 
-    fn visit_set_expr(&mut self, expr: set::Set) -> Result<Option<Object>, LoxError>;
+    /// evaluate the first arm whose pattern matches the subject
+    fn visit_match_expr(&mut self, expr: &match_::Match) -> Result<Option<Object>, LoxError>;
 
-    fn visit_this_expr(&mut self, expr: this::This) -> Result<Option<Object>, LoxError>;
+    /// suspend the running generator
+    fn visit_yield_expr(&mut self, expr: &yield_::Yield) -> Result<Option<Object>, LoxError>;
 
-    fn visit_super_expr(&mut self, expr: super_::Super) -> Result<Option<Object>, LoxError>;
+    /// suspend the running async function
+    fn visit_await_expr(&mut self, expr: &await_::Await) -> Result<Option<Object>, LoxError>;
 }
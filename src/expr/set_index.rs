@@ -0,0 +1,11 @@
+use crate::expr::Expr;
+use crate::token::Token;
+
+/// subscript write, such as m[key] = value
+#[derive(Debug, Clone)]
+pub(crate) struct SetIndex {
+    pub object: Expr,
+    pub bracket: Token,
+    pub index: Expr,
+    pub value: Expr,
+}
@@ -1,6 +1,8 @@
+use crate::expr::NodeId;
 use crate::token::Token;
 #[derive(Clone, Debug)]
 pub(crate) struct Super {
     pub keyword: Token,
     pub method: Token,
+    pub id: NodeId,
 }
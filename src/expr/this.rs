@@ -1,5 +1,7 @@
+use crate::expr::NodeId;
 use crate::token::Token;
 #[derive(Clone, Debug)]
 pub(crate) struct This {
     pub keyword: Token,
+    pub id: NodeId,
 }
@@ -1,7 +1,8 @@
+use crate::expr::NodeId;
 use crate::token::Token;
 /// an expression which is a variable
 #[derive(Debug, Clone)]
 pub(crate) struct Variable {
     pub name: Token,
-    pub distance: Option<usize>,
+    pub id: NodeId,
 }
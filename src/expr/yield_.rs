@@ -0,0 +1,9 @@
+use crate::expr::Expr;
+use crate::token::Token;
+
+/// yield value, suspends the running generator, evaluates to the value passed to the next `next()`
+#[derive(Debug, Clone)]
+pub(crate) struct Yield {
+    pub keyword: Token,
+    pub value: Option<Expr>,
+}
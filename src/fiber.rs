@@ -0,0 +1,731 @@
+use crate::error::{ErrorKind, LoxError};
+use crate::function::lox_function::LoxFunction;
+use crate::function::native_function::NativeFunction;
+use crate::function::{Arity, LoxCallable};
+use crate::heap::{self, Managed, Tracer};
+use crate::interpreter::Interpreter;
+use crate::number;
+use crate::object::Object;
+use crate::promise::{LoxPromise, Reaction};
+use crate::resumable::{Resumable, Resumed};
+use crate::token::Token;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::fmt::{Debug, Display, Formatter};
+use std::rc::Rc;
+
+/// a function running on frames of its own, it runs until it yields, waits or returns
+///
+/// fibers created with `Fiber(fn)` are resumed by hand, fibers created with `spawn(fn)` are run by the scheduler
+pub(crate) struct LoxFiber {
+    function: LoxFunction,
+    /// arguments of a spawned fiber, a fiber created with `Fiber(fn)` receives the first resumed value instead
+    arguments: RefCell<Option<Vec<Option<Object>>>>,
+    /// `None` before the first resume, while running and once finished
+    body: RefCell<Option<Resumable>>,
+    state: Cell<FiberState>,
+    spawned: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum FiberState {
+    New,
+    Suspended,
+    Running,
+    Done,
+}
+
+/// what a fiber waits for when it suspends in `sleep()` or a channel operation
+pub(crate) enum Wait {
+    Sleep(f64),
+    Send(Rc<LoxChannel>, Option<Object>),
+    Receive(Rc<LoxChannel>),
+}
+
+impl LoxFiber {
+    fn new(function: LoxFunction, arguments: Option<Vec<Option<Object>>>) -> Self {
+        LoxFiber {
+            function,
+            spawned: arguments.is_some(),
+            arguments: RefCell::new(arguments),
+            body: RefCell::new(None),
+            state: Cell::new(FiberState::New),
+        }
+    }
+
+    /// name of the fiber's function, use to report errors
+    fn name(&self) -> &Token {
+        &self.function.declaration.name
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.state.get() == FiberState::Done
+    }
+
+    /// property access on a fiber, `done` is a field and `resume` a bound method
+    pub fn property(fiber: &Rc<LoxFiber>, name: &Token) -> Result<Object, LoxError> {
+        match name.lexeme.as_str() {
+            "done" => Ok(Object::Boolean(fiber.is_done())),
+            "resume" => Ok(FiberFunction::Resume(fiber.clone()).object()),
+            _ => Err(LoxError::new_runtime_error(
+                name.clone(),
+                ErrorKind::PropertyError,
+                format!("Undefined property '{}'.", name.lexeme),
+            )),
+        }
+    }
+
+    /// run the fiber until it yields, waits or returns, `sent` is the result of the call it is suspended at
+    ///
+    /// `token` is where the fiber is resumed from, errors of resuming it are reported on it
+    fn resume(
+        &self,
+        interpreter: &mut Interpreter,
+        sent: Option<Object>,
+        token: &Token,
+    ) -> Result<Resumed, LoxError> {
+        // the fiber runs on the resumer's native stack, resuming it counts as a call toward the depth limit
+        interpreter.enter_call(token)?;
+        let resumed = self.run(interpreter, sent, token);
+        interpreter.exit_call();
+        resumed
+    }
+
+    fn run(
+        &self,
+        interpreter: &mut Interpreter,
+        sent: Option<Object>,
+        token: &Token,
+    ) -> Result<Resumed, LoxError> {
+        let error = |message: &str| {
+            LoxError::new_runtime_error(token.clone(), ErrorKind::Error, message.into())
+        };
+        let (mut body, sent) = match self.state.get() {
+            FiberState::Done => return Err(error("Can't resume a finished fiber.")),
+            FiberState::Running => return Err(error("Fiber is already running.")),
+            FiberState::New => {
+                let arguments = match self.arguments.take() {
+                    Some(arguments) => arguments,
+                    None if self.function.arity().max == Some(0) => vec![],
+                    None => vec![sent],
+                };
+                let environment =
+                    self.function
+                        .bind_arguments(interpreter, arguments, vec![], token)?;
+                let body = self.function.declaration.body.clone();
+                (Resumable::fiber(body, environment), None)
+            }
+            FiberState::Suspended => match self.body.take() {
+                Some(body) => (body, sent),
+                None => return Err(error("Fiber is already running.")),
+            },
+        };
+        self.state.set(FiberState::Running);
+        let resumed = body.resume(interpreter, sent);
+        match resumed {
+            Ok(Resumed::Yielded(_) | Resumed::Waiting(_)) => {
+                *self.body.borrow_mut() = Some(body);
+                self.state.set(FiberState::Suspended);
+            }
+            _ => self.state.set(FiberState::Done),
+        }
+        resumed
+    }
+}
+
+impl Managed for LoxFiber {
+    fn trace(&self, tracer: &mut Tracer) -> bool {
+        let (Ok(arguments), Ok(body)) = (self.arguments.try_borrow(), self.body.try_borrow())
+        else {
+            return false;
+        };
+        tracer.edge(&self.function.closure);
+        arguments
+            .iter()
+            .flatten()
+            .for_each(|argument| tracer.optional(argument));
+        if let Some(body) = body.as_ref() {
+            body.trace(tracer);
+        }
+        true
+    }
+
+    fn clear(&self) {
+        let arguments = self
+            .arguments
+            .try_borrow_mut()
+            .ok()
+            .and_then(|mut a| a.take());
+        let body = self
+            .body
+            .try_borrow_mut()
+            .ok()
+            .and_then(|mut body| body.take());
+        drop((arguments, body));
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self
+                .body
+                .try_borrow()
+                .map_or(0, |body| body.as_ref().map_or(0, Resumable::size))
+    }
+}
+
+impl Debug for LoxFiber {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Display for LoxFiber {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<fiber {}>", self.name().lexeme)
+    }
+}
+
+impl PartialEq for LoxFiber {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// queue of values passed between fibers, receiving from an empty channel or sending to a full one
+/// suspends the fiber until another fiber sends or receives
+pub(crate) struct LoxChannel {
+    /// `None` for an unbounded channel
+    capacity: Option<usize>,
+    buffer: RefCell<VecDeque<Option<Object>>>,
+    /// fibers waiting in `receive()`, in arrival order
+    receivers: RefCell<VecDeque<Rc<LoxFiber>>>,
+    /// fibers waiting in `send()` with the value they send, in arrival order
+    senders: RefCell<VecDeque<(Rc<LoxFiber>, Option<Object>)>>,
+}
+
+impl LoxChannel {
+    fn new(capacity: Option<usize>) -> Self {
+        LoxChannel {
+            capacity,
+            buffer: RefCell::new(VecDeque::new()),
+            receivers: RefCell::new(VecDeque::new()),
+            senders: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.capacity
+            .is_some_and(|capacity| self.buffer.borrow().len() >= capacity)
+    }
+
+    /// property access on a channel, `size` is the number of buffered values
+    pub fn property(channel: &Rc<LoxChannel>, name: &Token) -> Result<Object, LoxError> {
+        match name.lexeme.as_str() {
+            "size" => Ok(Object::Int(channel.buffer.borrow().len() as i64)),
+            "send" => Ok(FiberFunction::Send(channel.clone()).object()),
+            "receive" => Ok(FiberFunction::Receive(channel.clone()).object()),
+            _ => Err(LoxError::new_runtime_error(
+                name.clone(),
+                ErrorKind::PropertyError,
+                format!("Undefined property '{}'.", name.lexeme),
+            )),
+        }
+    }
+}
+
+impl Managed for LoxChannel {
+    fn trace(&self, tracer: &mut Tracer) -> bool {
+        let (Ok(buffer), Ok(receivers), Ok(senders)) = (
+            self.buffer.try_borrow(),
+            self.receivers.try_borrow(),
+            self.senders.try_borrow(),
+        ) else {
+            return false;
+        };
+        buffer.iter().for_each(|value| tracer.optional(value));
+        receivers.iter().for_each(|fiber| tracer.edge(fiber));
+        senders.iter().for_each(|(fiber, value)| {
+            tracer.edge(fiber);
+            tracer.optional(value);
+        });
+        true
+    }
+
+    fn clear(&self) {
+        let buffer = self
+            .buffer
+            .try_borrow_mut()
+            .map(|mut b| std::mem::take(&mut *b));
+        let receivers = self
+            .receivers
+            .try_borrow_mut()
+            .map(|mut r| std::mem::take(&mut *r));
+        let senders = self
+            .senders
+            .try_borrow_mut()
+            .map(|mut s| std::mem::take(&mut *s));
+        drop((buffer, receivers, senders));
+    }
+
+    fn size(&self) -> usize {
+        let buffer = self
+            .buffer
+            .try_borrow()
+            .map_or(0, |buffer| buffer.capacity());
+        let senders = self
+            .senders
+            .try_borrow()
+            .map_or(0, |senders| senders.capacity());
+        std::mem::size_of::<Self>()
+            + (buffer + senders) * std::mem::size_of::<Option<Object>>()
+            + self.receivers.try_borrow().map_or(0, |r| r.capacity()) * std::mem::size_of::<usize>()
+    }
+}
+
+impl Debug for LoxChannel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Display for LoxChannel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<channel>")
+    }
+}
+
+impl PartialEq for LoxChannel {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// the interpreter's event loop, runs spawned fibers, timers and promise reactions one at a time,
+/// in a deterministic order
+///
+/// ready tasks run first in, first out. When none is ready, the virtual clock jumps to the earliest
+/// timer or sleeping fiber, those due at the same time run in the order they were scheduled
+#[derive(Default)]
+pub(crate) struct Scheduler {
+    /// virtual time in the unit of `sleep()` and `setTimeout()`, it starts at 0
+    clock: f64,
+    ready: VecDeque<Task>,
+    /// timers and sleeping fibers with their due time and their id, ids increase in scheduling order
+    timers: Vec<(f64, usize, Task)>,
+    next_id: usize,
+    /// rejected promises, those without a reaction once the loop is empty are reported
+    rejected: Vec<Rc<LoxPromise>>,
+    /// fibers waiting on a channel with the operation they wait in, in the order they blocked,
+    /// those still waiting once the loop is empty are deadlocked
+    blocked: Vec<(Rc<LoxFiber>, &'static str)>,
+}
+
+/// work queued on the event loop
+pub(crate) enum Task {
+    /// resume a spawned fiber with a value
+    Fiber(Rc<LoxFiber>, Option<Object>),
+    /// call a timer's callback, `token` is where the timer was set
+    Call {
+        callback: Object,
+        arguments: Vec<Option<Object>>,
+        token: Token,
+    },
+    /// react to a promise having settled
+    React(Reaction, Rc<LoxPromise>),
+}
+
+impl Scheduler {
+    /// run tasks until none is ready or scheduled, fibers still blocked on a channel then are reported as a deadlock
+    pub fn run(interpreter: &mut Interpreter) -> Result<(), LoxError> {
+        while let Some(task) = interpreter.scheduler().next() {
+            match task {
+                Task::Fiber(fiber, value) => Scheduler::run_fiber(interpreter, fiber, value)?,
+                Task::Call {
+                    callback,
+                    arguments,
+                    token,
+                } => {
+                    interpreter.call_value(Some(callback), arguments, vec![], &token)?;
+                }
+                Task::React(reaction, promise) => reaction.run(interpreter, &promise)?,
+            }
+        }
+        let blocked = std::mem::take(&mut interpreter.scheduler().blocked);
+        if let Some((fiber, _)) = blocked.first() {
+            let fibers = blocked
+                .iter()
+                .map(|(fiber, operation)| format!("{} in {}()", fiber, operation))
+                .collect::<Vec<String>>();
+            return Err(LoxError::new_runtime_error(
+                fiber.name().clone(),
+                ErrorKind::Error,
+                format!(
+                    "Deadlock, every fiber left is blocked: {}.",
+                    fibers.join(", ")
+                ),
+            ));
+        }
+        let rejected = std::mem::take(&mut interpreter.scheduler().rejected);
+        match rejected.iter().find(|promise| !promise.is_handled()) {
+            Some(promise) => Err(promise.rejection().expect("a rejected promise")),
+            None => Ok(()),
+        }
+    }
+
+    pub fn push(&mut self, task: Task) {
+        self.ready.push_back(task);
+    }
+
+    /// run `task` once the clock has advanced by `delay`, returns the id of the timer
+    pub fn schedule(&mut self, delay: f64, task: Task) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.timers.push((self.clock + delay.max(0f64), id, task));
+        id
+    }
+
+    pub fn rejected(&mut self, promise: Rc<LoxPromise>) {
+        self.rejected.push(promise);
+    }
+
+    fn run_fiber(
+        interpreter: &mut Interpreter,
+        fiber: Rc<LoxFiber>,
+        mut value: Option<Object>,
+    ) -> Result<(), LoxError> {
+        interpreter
+            .scheduler()
+            .blocked
+            .retain(|(blocked, _)| !Rc::ptr_eq(blocked, &fiber));
+        loop {
+            match fiber.resume(interpreter, value, fiber.name())? {
+                Resumed::Yielded(_) => {
+                    interpreter.scheduler().push(Task::Fiber(fiber, None));
+                    return Ok(());
+                }
+                Resumed::Returned(_) => return Ok(()),
+                Resumed::Awaiting(_) => unreachable!("fibers don't await"),
+                Resumed::Waiting(wait) => {
+                    let scheduler = interpreter.scheduler();
+                    match scheduler.attempt(&wait) {
+                        Some(result) => value = result,
+                        None => {
+                            scheduler.block(fiber, wait);
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn next(&mut self) -> Option<Task> {
+        if let Some(ready) = self.ready.pop_front() {
+            return Some(ready);
+        }
+        let earliest = (0..self.timers.len()).min_by(|a, b| {
+            let (a, b) = (&self.timers[*a], &self.timers[*b]);
+            a.0.total_cmp(&b.0).then(a.1.cmp(&b.1))
+        })?;
+        let (time, _, task) = self.timers.remove(earliest);
+        self.clock = self.clock.max(time);
+        Some(task)
+    }
+
+    /// complete a channel operation without waiting, `None` when the fiber has to wait
+    fn attempt(&mut self, wait: &Wait) -> Option<Option<Object>> {
+        match wait {
+            Wait::Sleep(_) => None,
+            Wait::Send(channel, value) => {
+                let receiver = channel.receivers.borrow_mut().pop_front();
+                if let Some(receiver) = receiver {
+                    self.push(Task::Fiber(receiver, value.clone()));
+                    return Some(None);
+                }
+                if channel.is_full() {
+                    return None;
+                }
+                channel.buffer.borrow_mut().push_back(value.clone());
+                Some(None)
+            }
+            Wait::Receive(channel) => {
+                let value = channel.buffer.borrow_mut().pop_front();
+                let sender = channel.senders.borrow_mut().pop_front();
+                match (value, sender) {
+                    // the oldest waiting sender takes the room freed in the buffer
+                    (Some(value), Some((sender, sent))) => {
+                        channel.buffer.borrow_mut().push_back(sent);
+                        self.push(Task::Fiber(sender, None));
+                        Some(value)
+                    }
+                    (Some(value), None) => Some(value),
+                    (None, Some((sender, sent))) => {
+                        self.push(Task::Fiber(sender, None));
+                        Some(sent)
+                    }
+                    (None, None) => None,
+                }
+            }
+        }
+    }
+
+    fn block(&mut self, fiber: Rc<LoxFiber>, wait: Wait) {
+        match wait {
+            Wait::Sleep(duration) => {
+                self.schedule(duration, Task::Fiber(fiber, None));
+            }
+            Wait::Send(channel, value) => {
+                self.blocked.push((fiber.clone(), "send"));
+                channel.senders.borrow_mut().push_back((fiber, value));
+            }
+            Wait::Receive(channel) => {
+                self.blocked.push((fiber.clone(), "receive"));
+                channel.receivers.borrow_mut().push_back(fiber);
+            }
+        }
+    }
+}
+
+/// natives of fibers, the event loop and channels, bound to their receiver where they have one
+#[derive(Debug, Clone)]
+pub enum FiberFunction {
+    /// `Fiber(fn)`, `Fiber.yield` is read from it
+    Fiber,
+    Yield,
+    Resume(Rc<LoxFiber>),
+    Spawn,
+    Sleep,
+    Now,
+    SetTimeout,
+    ClearTimeout,
+    Channel,
+    Send(Rc<LoxChannel>),
+    Receive(Rc<LoxChannel>),
+}
+
+impl FiberFunction {
+    /// the fiber or channel the function is bound to
+    pub fn trace(&self, tracer: &mut Tracer) {
+        match self {
+            FiberFunction::Resume(fiber) => tracer.edge(fiber),
+            FiberFunction::Send(channel) | FiberFunction::Receive(channel) => tracer.edge(channel),
+            _ => {}
+        }
+    }
+
+    pub fn object(self) -> Object {
+        Object::Function(Box::new(LoxCallable::NativeFunction(
+            NativeFunction::Fiber(self),
+        )))
+    }
+
+    /// property access on a native, only `Fiber` has one
+    pub fn property(&self, name: &Token) -> Result<Object, LoxError> {
+        match (self, name.lexeme.as_str()) {
+            (FiberFunction::Fiber, "yield") => Ok(FiberFunction::Yield.object()),
+            _ => Err(LoxError::new_runtime_error(
+                name.clone(),
+                ErrorKind::PropertyError,
+                format!("Undefined property '{}'.", name.lexeme),
+            )),
+        }
+    }
+
+    /// the suspension of a call made directly by a fiber, `None` if the call doesn't suspend
+    pub fn suspend(
+        &self,
+        arguments: &[Option<Object>],
+        token: &Token,
+    ) -> Result<Option<Resumed>, LoxError> {
+        let argument = arguments.first().cloned().flatten();
+        let resumed = match self {
+            FiberFunction::Yield => Resumed::Yielded(argument),
+            FiberFunction::Sleep => match argument.as_ref().and_then(number::as_f64) {
+                Some(duration) => Resumed::Waiting(Wait::Sleep(duration)),
+                None => {
+                    return Err(LoxError::new_runtime_error(
+                        token.clone(),
+                        ErrorKind::TypeError,
+                        "Sleep duration must be a number.".into(),
+                    ))
+                }
+            },
+            FiberFunction::Send(channel) => Resumed::Waiting(Wait::Send(channel.clone(), argument)),
+            FiberFunction::Receive(channel) => Resumed::Waiting(Wait::Receive(channel.clone())),
+            _ => return Ok(None),
+        };
+        Ok(Some(resumed))
+    }
+
+    /// call made outside a fiber's own statements, such as inside an expression, it can't suspend
+    pub fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Option<Object>>,
+        token: &Token,
+    ) -> Result<Option<Object>, LoxError> {
+        let error = |kind: ErrorKind, message: &str| {
+            Err(LoxError::new_runtime_error(
+                token.clone(),
+                kind,
+                message.into(),
+            ))
+        };
+        let mut arguments = arguments.into_iter();
+        let argument = arguments.next().flatten();
+        match self {
+            FiberFunction::Fiber | FiberFunction::Spawn => {
+                let function = match &argument {
+                    Some(Object::Function(function)) => match function.as_ref() {
+                        LoxCallable::LoxFunction(f)
+                            if !f.declaration.is_generator && !f.declaration.is_async =>
+                        {
+                            f.clone()
+                        }
+                        _ => return error(ErrorKind::TypeError, "A fiber runs a Lox function."),
+                    },
+                    _ => return error(ErrorKind::TypeError, "A fiber runs a Lox function."),
+                };
+                if let FiberFunction::Fiber = self {
+                    return Ok(Some(Object::Fiber(heap::track(LoxFiber::new(
+                        function, None,
+                    )))));
+                }
+                let arguments = arguments.collect::<Vec<Option<Object>>>();
+                let callable = LoxCallable::LoxFunction(function.clone());
+                Interpreter::check_arity(&callable, arguments.len(), token)?;
+                let fiber = heap::track(LoxFiber::new(function, Some(arguments)));
+                interpreter
+                    .scheduler()
+                    .push(Task::Fiber(fiber.clone(), None));
+                Ok(Some(Object::Fiber(fiber)))
+            }
+            FiberFunction::Resume(fiber) => {
+                if fiber.spawned {
+                    return error(ErrorKind::Error, "Can't resume a spawned fiber.");
+                }
+                let mut value = argument;
+                loop {
+                    match fiber.resume(interpreter, value, token)? {
+                        Resumed::Yielded(value) | Resumed::Returned(value) => return Ok(value),
+                        Resumed::Awaiting(_) => unreachable!("fibers don't await"),
+                        Resumed::Waiting(wait) => match interpreter.scheduler().attempt(&wait) {
+                            Some(result) => value = result,
+                            None => {
+                                return error(
+                                    ErrorKind::Error,
+                                    "Only a spawned fiber can sleep or wait on a channel.",
+                                )
+                            }
+                        },
+                    }
+                }
+            }
+            FiberFunction::Yield => error(
+                ErrorKind::Error,
+                "Fiber.yield() can only be called as a statement of a fiber.",
+            ),
+            FiberFunction::Sleep => error(
+                ErrorKind::Error,
+                "sleep() can only be called as a statement of a spawned fiber.",
+            ),
+            FiberFunction::Now => Ok(Some(Object::Number(interpreter.scheduler().clock))),
+            FiberFunction::SetTimeout => {
+                let delay = arguments.next().flatten();
+                let (Some(callback @ Object::Function(_)), Some(delay)) =
+                    (argument, delay.as_ref().and_then(number::as_f64))
+                else {
+                    return error(
+                        ErrorKind::TypeError,
+                        "setTimeout() takes a function and a delay.",
+                    );
+                };
+                let task = Task::Call {
+                    callback,
+                    arguments: arguments.collect(),
+                    token: token.clone(),
+                };
+                let id = interpreter.scheduler().schedule(delay, task);
+                Ok(Some(Object::Int(id as i64)))
+            }
+            FiberFunction::ClearTimeout => {
+                if let Some(id) = argument.as_ref().and_then(number::as_i64) {
+                    interpreter.scheduler().timers.retain(|(_, timer, task)| {
+                        *timer as i64 != id || !matches!(task, Task::Call { .. })
+                    });
+                }
+                Ok(None)
+            }
+            FiberFunction::Channel => {
+                let capacity = match argument.as_ref().map(number::as_i64) {
+                    None => None,
+                    Some(Some(n)) if n >= 0 => Some(n as usize),
+                    _ => {
+                        return error(
+                            ErrorKind::TypeError,
+                            "Channel capacity must be a non-negative integer.",
+                        )
+                    }
+                };
+                Ok(Some(Object::Channel(heap::track(LoxChannel::new(
+                    capacity,
+                )))))
+            }
+            FiberFunction::Send(channel) => {
+                match interpreter
+                    .scheduler()
+                    .attempt(&Wait::Send(channel.clone(), argument))
+                {
+                    Some(_) => Ok(None),
+                    None => error(
+                        ErrorKind::Error,
+                        "Channel is full, only a spawned fiber can wait to send.",
+                    ),
+                }
+            }
+            FiberFunction::Receive(channel) => {
+                match interpreter
+                    .scheduler()
+                    .attempt(&Wait::Receive(channel.clone()))
+                {
+                    Some(value) => Ok(value),
+                    None => error(
+                        ErrorKind::Error,
+                        "Channel is empty, only a spawned fiber can wait to receive.",
+                    ),
+                }
+            }
+        }
+    }
+
+    pub fn arity(&self) -> Arity {
+        match self {
+            FiberFunction::Fiber | FiberFunction::Sleep | FiberFunction::Send(_) => Arity::exact(1),
+            FiberFunction::Yield | FiberFunction::Resume(_) | FiberFunction::Channel => Arity {
+                min: 0,
+                max: Some(1),
+            },
+            FiberFunction::Spawn => Arity::at_least(1),
+            FiberFunction::SetTimeout => Arity::at_least(2),
+            FiberFunction::ClearTimeout => Arity::exact(1),
+            FiberFunction::Now | FiberFunction::Receive(_) => Arity::exact(0),
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        let name = match self {
+            FiberFunction::Fiber => "Fiber",
+            FiberFunction::Yield => "Fiber.yield",
+            FiberFunction::Resume(_) => "fiber.resume",
+            FiberFunction::Spawn => "spawn",
+            FiberFunction::Sleep => "sleep",
+            FiberFunction::Now => "now",
+            FiberFunction::SetTimeout => "setTimeout",
+            FiberFunction::ClearTimeout => "clearTimeout",
+            FiberFunction::Channel => "Channel",
+            FiberFunction::Send(_) => "channel.send",
+            FiberFunction::Receive(_) => "channel.receive",
+        };
+        format!("<native fn {}>", name)
+    }
+}
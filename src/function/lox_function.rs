@@ -1,66 +1,237 @@
 use crate::environment::Environment;
-use crate::error::LoxError;
+use crate::error::{ErrorKind, LoxError};
+use crate::function::{Arity, LoxCallable};
+use crate::generator::LoxGenerator;
+use crate::heap;
 use crate::instance::LoxInstance;
 use crate::interpreter::Interpreter;
+use crate::list::LoxList;
 use crate::object::Object;
+use crate::promise::LoxAsync;
+use crate::resumable::Resumable;
 use crate::stmt;
+use crate::symbol;
+use crate::token::Token;
 use std::cell::RefCell;
 use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub struct LoxFunction {
-    pub(crate) declaration: stmt::function::Function,
+    /// shared by every closure created from the same declaration
+    pub(crate) declaration: Rc<stmt::function::Function>,
     pub(crate) closure: Rc<RefCell<Environment>>,
     pub(crate) is_initializer: bool,
 }
 
+/// a call to a Lox function in tail position, evaluated but not made yet
+#[derive(Debug, Clone)]
+pub struct TailCall {
+    pub(crate) function: LoxFunction,
+    pub(crate) arguments: Vec<Option<Object>>,
+    pub(crate) named: Vec<(Token, Option<Object>)>,
+    pub(crate) paren: Token,
+}
+
+impl TailCall {
+    /// make the call like any other, for the bodies that don't run through `LoxFunction::call_named`
+    pub(crate) fn call(self, interpreter: &mut Interpreter) -> Result<Option<Object>, LoxError> {
+        let callee = Object::Function(Box::new(LoxCallable::LoxFunction(self.function)));
+        interpreter.call_value(Some(callee), self.arguments, self.named, &self.paren)
+    }
+}
+
 impl LoxFunction {
-    pub(crate) fn bind(&self, instance: LoxInstance) -> LoxFunction {
+    /// bind `this` to an instance, or to a class object for static methods
+    pub(crate) fn bind(&self, this: Object) -> LoxFunction {
         let mut environment = Environment::new_from_enclosing(self.closure.clone());
-        environment.define("this".into(), Some(Object::Instance(instance)));
+        environment.define("this".into(), Some(this));
         LoxFunction {
             is_initializer: self.is_initializer,
             declaration: self.declaration.clone(),
-            closure: Rc::new(RefCell::new(environment)),
+            closure: heap::environment(environment),
         }
     }
 
+    /// the same declaration, closing over the same environment or bound to the same receiver
+    pub(crate) fn is_equal(&self, other: &LoxFunction) -> bool {
+        if !Rc::ptr_eq(&self.declaration, &other.declaration) {
+            return false;
+        }
+        match (self.receiver(), other.receiver()) {
+            (Some(a), Some(b)) => a.is_equal(&b),
+            (None, None) => Rc::ptr_eq(&self.closure, &other.closure),
+            _ => false,
+        }
+    }
+
+    /// the `this` of a bound method
+    fn receiver(&self) -> Option<Object> {
+        symbol::THIS
+            .with(|this| self.closure.borrow().get_here(this))
+            .flatten()
+    }
+
     pub fn call(
         &self,
         interpreter: &mut Interpreter,
         arguments: Vec<Option<Object>>,
     ) -> Result<Option<Object>, LoxError> {
-        let mut environment = Environment::new_from_enclosing(self.closure.clone());
-        for i in 0..self.declaration.params.len() {
-            if let (Some(param), Some(arg)) = (self.declaration.params.get(i), arguments.get(i)) {
-                environment.define(param.lexeme.clone(), arg.clone());
+        let name = self.declaration.name.clone();
+        self.call_named(interpreter, arguments, vec![], &name)
+    }
+
+    /// tail calls the body ends with run here one after the other, so tail recursion doesn't grow the native stack
+    pub fn call_named(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Option<Object>>,
+        named: Vec<(Token, Option<Object>)>,
+        paren: &Token,
+    ) -> Result<Option<Object>, LoxError> {
+        interpreter.enter_call(paren)?;
+        let mut result = self.run(interpreter, arguments, named, paren);
+        let mut tail_frame = false;
+        while let Err(LoxError::TailCall(call)) = result {
+            let TailCall {
+                function,
+                arguments,
+                named,
+                paren,
+            } = *call;
+            let callable = LoxCallable::LoxFunction(function.clone());
+            if let Err(error) =
+                Interpreter::check_arity(&callable, arguments.len() + named.len(), &paren)
+            {
+                result = Err(error);
+                break;
+            }
+            interpreter.tail_frame(function.to_string(), paren.line, tail_frame);
+            tail_frame = true;
+            result = function.run(interpreter, arguments, named, &paren);
+        }
+        if tail_frame {
+            if let Err(error) = &mut result {
+                interpreter.capture_trace(error);
             }
+            interpreter.pop_frame();
+        }
+        interpreter.exit_call();
+        result
+    }
+
+    /// bind the arguments and run the body, a tail call it ends with is left to the caller
+    fn run(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Option<Object>>,
+        named: Vec<(Token, Option<Object>)>,
+        paren: &Token,
+    ) -> Result<Option<Object>, LoxError> {
+        let environment = self.bind_arguments(interpreter, arguments, named, paren)?;
+        if self.declaration.is_generator {
+            let body = Resumable::new(self.declaration.body.clone(), environment);
+            let generator = LoxGenerator::new(self.declaration.name.clone(), body);
+            return Ok(Some(Object::Generator(heap::track(generator))));
+        }
+        if self.declaration.is_async {
+            let body = Resumable::asynchronous(self.declaration.body.clone(), environment);
+            let promise = LoxAsync::start(interpreter, self.declaration.name.clone(), body)?;
+            return Ok(Some(Object::Promise(promise)));
         }
-        let result = interpreter.execute_block(self.declaration.body.clone(), environment);
+        let result = interpreter.execute_block_in(&self.declaration.body, environment);
         match result {
-            Ok(_) => {
-                if self.is_initializer {
-                    match self.closure.borrow().get_at(0, "this") {
-                        Some(v) => return Ok(v),
-                        None => {}
-                    }
-                }
-                Ok(None)
+            Ok(_) => Ok(self.result(None)),
+            Err(LoxError::ReturnError(returnValue)) => Ok(self.result(returnValue.value)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// value of a call that returned `value`, initializers always return `this`
+    pub(crate) fn result(&self, value: Option<Object>) -> Option<Object> {
+        if self.is_initializer {
+            if let Some(this) = symbol::THIS.with(|this| self.closure.borrow().get_here(this)) {
+                return this;
             }
-            Err(LoxError::ReturnError(returnValue)) => {
-                if self.is_initializer {
-                    if let Some(fun) = self.closure.borrow().get_at(0, "this".into()) {
-                        return Ok(fun);
-                    }
-                }
-                Ok(returnValue.value)
+        }
+        value
+    }
+
+    /// environment of a call, bind positional arguments first, then named ones, missing parameters take their
+    /// default value and extra positional arguments are collected into the rest parameter
+    pub(crate) fn bind_arguments(
+        &self,
+        interpreter: &mut Interpreter,
+        mut arguments: Vec<Option<Object>>,
+        mut named: Vec<(Token, Option<Object>)>,
+        paren: &Token,
+    ) -> Result<Rc<RefCell<Environment>>, LoxError> {
+        let params = &self.declaration.params;
+        let rest = arguments.split_off(params.len().min(arguments.len()));
+        for (i, (name, _)) in named.iter().enumerate() {
+            let Some(position) = params.iter().position(|p| p.lexeme == name.lexeme) else {
+                return Err(LoxError::new_runtime_error(
+                    name.clone(),
+                    ErrorKind::ArityError,
+                    format!("Unexpected argument '{}'.", name.lexeme),
+                ));
+            };
+            if position < arguments.len() || named[..i].iter().any(|(n, _)| n.lexeme == name.lexeme)
+            {
+                return Err(LoxError::new_runtime_error(
+                    name.clone(),
+                    ErrorKind::ArityError,
+                    format!("Got multiple values for argument '{}'.", name.lexeme),
+                ));
             }
-            Err(e) => Err(e),
         }
+
+        let environment = heap::environment(Environment::new_from_enclosing(self.closure.clone()));
+        let mut arguments = arguments.into_iter();
+        for (param, default) in params.iter().zip(&self.declaration.defaults) {
+            let value = if let Some(value) = arguments.next() {
+                value
+            } else if let Some(i) = named.iter().position(|(n, _)| n.lexeme == param.lexeme) {
+                named.swap_remove(i).1
+            } else if let Some(default) = default {
+                interpreter.evaluate_in(default, environment.clone())?
+            } else {
+                return Err(LoxError::new_runtime_error(
+                    paren.clone(),
+                    ErrorKind::ArityError,
+                    format!("Missing argument '{}'.", param.lexeme),
+                ));
+            };
+            environment.borrow_mut().define(param.lexeme.clone(), value);
+        }
+        if let Some(param) = &self.declaration.rest {
+            environment
+                .borrow_mut()
+                .define(param.lexeme.clone(), Some(LoxList::object(rest)));
+        }
+
+        Ok(environment)
     }
 
-    pub fn arity(&self) -> usize {
-        self.declaration.params.len()
+    /// getters are methods without a parameter list, they run when the property is read
+    pub fn is_getter(&self) -> bool {
+        self.declaration.is_getter
+    }
+
+    pub fn arity(&self) -> Arity {
+        let params = self.declaration.params.len();
+        let required = self
+            .declaration
+            .defaults
+            .iter()
+            .filter(|d| d.is_none())
+            .count();
+        match self.declaration.rest {
+            Some(_) => Arity::at_least(required),
+            None => Arity {
+                min: required,
+                max: Some(params),
+            },
+        }
     }
 
     pub fn to_string(&self) -> String {
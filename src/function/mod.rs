@@ -2,16 +2,53 @@ pub mod lox_function;
 pub mod native_function;
 
 use crate::class::LoxClass;
-use crate::error::{LoxError, ParseError};
+use crate::error::{ErrorKind, LoxError, ParseError};
 use crate::interpreter::Interpreter;
 use crate::object::Object;
+use crate::token::Token;
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub enum LoxCallable {
     LoxFunction(lox_function::LoxFunction),
     NativeFunction(native_function::NativeFunction),
-    LoxClass(LoxClass),
+    LoxClass(Rc<LoxClass>),
 }
+/// number of arguments a callable accepts, `max` is `None` for variadic functions
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Arity {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl Arity {
+    pub fn exact(n: usize) -> Self {
+        Arity {
+            min: n,
+            max: Some(n),
+        }
+    }
+
+    pub fn at_least(n: usize) -> Self {
+        Arity { min: n, max: None }
+    }
+
+    pub fn accepts(&self, n: usize) -> bool {
+        n >= self.min && self.max.map_or(true, |max| n <= max)
+    }
+}
+
+impl Display for Arity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{}", max),
+            Some(max) => write!(f, "{} to {}", self.min, max),
+            None => write!(f, "at least {}", self.min),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum FunctionType {
     NONE,
@@ -21,19 +58,43 @@ pub enum FunctionType {
 }
 
 impl LoxCallable {
+    /// call with positional arguments, `token` is the call site used for errors
     pub fn call(
         &self,
         interpreter: &mut Interpreter,
         arguments: Vec<Option<Object>>,
+        token: &Token,
     ) -> Result<Option<Object>, LoxError> {
         match self {
             LoxCallable::LoxFunction(f) => f.call(interpreter, arguments),
-            LoxCallable::NativeFunction(f) => f.call(interpreter, arguments),
+            LoxCallable::NativeFunction(f) => f.call(interpreter, arguments, token),
             LoxCallable::LoxClass(class) => class.call(interpreter, arguments),
         }
     }
 
-    pub fn arity(&self) -> usize {
+    /// call with named arguments, natives only take positional ones
+    pub fn call_named(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Option<Object>>,
+        named: Vec<(Token, Option<Object>)>,
+        paren: &Token,
+    ) -> Result<Option<Object>, LoxError> {
+        match self {
+            LoxCallable::LoxFunction(f) => f.call_named(interpreter, arguments, named, paren),
+            LoxCallable::LoxClass(class) => class.call_named(interpreter, arguments, named, paren),
+            LoxCallable::NativeFunction(f) => match named.first() {
+                Some((name, _)) => Err(LoxError::new_runtime_error(
+                    name.clone(),
+                    ErrorKind::ArityError,
+                    format!("{} doesn't take named arguments.", f.to_string()),
+                )),
+                None => f.call(interpreter, arguments, paren),
+            },
+        }
+    }
+
+    pub fn arity(&self) -> Arity {
         match self {
             LoxCallable::LoxFunction(f) => f.arity(),
             LoxCallable::NativeFunction(f) => f.arity(),
@@ -50,8 +111,16 @@ impl LoxCallable {
     }
 }
 
+/// functions and classes compare by identity, natives are equal to themselves
 impl PartialEq for LoxCallable {
     fn eq(&self, other: &Self) -> bool {
-        false // not matter whether true or false
+        match (self, other) {
+            (LoxCallable::LoxFunction(a), LoxCallable::LoxFunction(b)) => a.is_equal(b),
+            (LoxCallable::NativeFunction(a), LoxCallable::NativeFunction(b)) => {
+                a.to_string() == b.to_string()
+            }
+            (LoxCallable::LoxClass(a), LoxCallable::LoxClass(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
     }
 }
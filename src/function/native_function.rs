@@ -1,12 +1,33 @@
 use crate::environment::Environment;
 use crate::error::{LoxError, ParseError};
+use crate::fiber::FiberFunction;
+use crate::function::Arity;
+use crate::generator::GeneratorNext;
+use crate::heap::{HeapFunction, Tracer};
 use crate::interpreter::Interpreter;
+use crate::list::ListMethod;
+use crate::map::MapMethod;
+use crate::number::NumberFunction;
 use crate::object::Object;
+use crate::promise::PromiseFunction;
+use crate::r#enum::{LoxVariant, VariantConstructor};
+use crate::token::Token;
+use std::rc::Rc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Clone)]
 pub enum NativeFunction {
     Clock(Clock),
+    MapMethod(MapMethod),
+    ListMethod(ListMethod),
+    VariantConstructor(VariantConstructor),
+    /// `toString()` of an enum variant
+    VariantToString(Rc<LoxVariant>),
+    GeneratorNext(GeneratorNext),
+    Fiber(FiberFunction),
+    Promise(PromiseFunction),
+    Heap(HeapFunction),
+    Number(NumberFunction),
 }
 #[derive(Debug, Clone)]
 struct Clock;
@@ -16,25 +37,74 @@ impl NativeFunction {
         NativeFunction::Clock(Clock)
     }
 
+    /// `token` is the call site, natives that can fail report their errors on it
     pub fn call(
         &self,
         interpreter: &mut Interpreter,
         arguments: Vec<Option<Object>>,
+        token: &Token,
     ) -> Result<Option<Object>, LoxError> {
         match self {
             NativeFunction::Clock(clock) => clock.call(interpreter, arguments),
+            NativeFunction::MapMethod(method) => method.call(interpreter, arguments),
+            NativeFunction::ListMethod(method) => method.call(interpreter, arguments),
+            NativeFunction::VariantConstructor(constructor) => {
+                constructor.call(interpreter, arguments)
+            }
+            NativeFunction::VariantToString(variant) => {
+                Ok(Some(Object::string(variant.to_string())))
+            }
+            NativeFunction::GeneratorNext(next) => next.call(interpreter, arguments),
+            NativeFunction::Fiber(function) => function.call(interpreter, arguments, token),
+            NativeFunction::Promise(function) => function.call(interpreter, arguments, token),
+            NativeFunction::Heap(function) => function.call(interpreter, arguments),
+            NativeFunction::Number(function) => function.call(interpreter, arguments, token),
         }
     }
 
-    pub fn arity(&self) -> usize {
+    pub fn arity(&self) -> Arity {
         match self {
             NativeFunction::Clock(clock) => clock.arity(),
+            NativeFunction::MapMethod(method) => method.arity(),
+            NativeFunction::ListMethod(method) => method.arity(),
+            NativeFunction::VariantConstructor(constructor) => constructor.arity(),
+            NativeFunction::VariantToString(_) => Arity::exact(0),
+            NativeFunction::GeneratorNext(next) => next.arity(),
+            NativeFunction::Fiber(function) => function.arity(),
+            NativeFunction::Promise(function) => function.arity(),
+            NativeFunction::Heap(function) => function.arity(),
+            NativeFunction::Number(function) => function.arity(),
+        }
+    }
+
+    /// the receiver a bound native holds on to
+    pub fn trace(&self, tracer: &mut Tracer) {
+        match self {
+            NativeFunction::MapMethod(method) => method.trace(tracer),
+            NativeFunction::ListMethod(method) => method.trace(tracer),
+            NativeFunction::VariantToString(variant) => tracer.edge(variant),
+            NativeFunction::GeneratorNext(next) => next.trace(tracer),
+            NativeFunction::Fiber(function) => function.trace(tracer),
+            NativeFunction::Promise(function) => function.trace(tracer),
+            NativeFunction::Clock(_)
+            | NativeFunction::VariantConstructor(_)
+            | NativeFunction::Heap(_)
+            | NativeFunction::Number(_) => {}
         }
     }
 
     pub fn to_string(&self) -> String {
         match self {
             NativeFunction::Clock(clock) => clock.to_string(),
+            NativeFunction::MapMethod(method) => method.to_string(),
+            NativeFunction::ListMethod(method) => method.to_string(),
+            NativeFunction::VariantConstructor(constructor) => constructor.to_string(),
+            NativeFunction::VariantToString(_) => "<native fn variant.toString>".into(),
+            NativeFunction::GeneratorNext(next) => next.to_string(),
+            NativeFunction::Fiber(function) => function.to_string(),
+            NativeFunction::Promise(function) => function.to_string(),
+            NativeFunction::Heap(function) => function.to_string(),
+            NativeFunction::Number(function) => function.to_string(),
         }
     }
 }
@@ -50,11 +120,11 @@ impl Clock {
             .duration_since(UNIX_EPOCH)
             .map(|v| v.as_secs())
             .unwrap(); // TODO
-        Ok(Some(Object::Number(since_the_epoch as f64)))
+        Ok(Some(Object::Int(since_the_epoch as i64)))
     }
 
-    pub fn arity(&self) -> usize {
-        0
+    pub fn arity(&self) -> Arity {
+        Arity::exact(0)
     }
 
     pub fn to_string(&self) -> String {
@@ -0,0 +1,166 @@
+use crate::error::{ErrorKind, LoxError};
+use crate::function::native_function::NativeFunction;
+use crate::function::{Arity, LoxCallable};
+use crate::heap::{Managed, Tracer};
+use crate::interpreter::Interpreter;
+use crate::object::Object;
+use crate::resumable::{Resumable, Resumed};
+use crate::token::Token;
+use std::cell::{Cell, RefCell};
+use std::fmt::{Debug, Display, Formatter};
+use std::rc::Rc;
+
+/// object returned by calling a generator function, `next()` runs the body up to its next yield
+pub(crate) struct LoxGenerator {
+    /// name of the generator function, use to report errors
+    name: Token,
+    /// `None` while the body is running and once it has finished
+    body: RefCell<Option<Resumable>>,
+    done: Cell<bool>,
+}
+
+impl LoxGenerator {
+    pub fn new(name: Token, body: Resumable) -> Self {
+        LoxGenerator {
+            name,
+            body: RefCell::new(Some(body)),
+            done: Cell::new(false),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done.get()
+    }
+
+    /// property access on a generator, `done` is a field and `next` a bound method
+    pub fn property(generator: &Rc<LoxGenerator>, name: &Token) -> Result<Object, LoxError> {
+        match name.lexeme.as_str() {
+            "done" => Ok(Object::Boolean(generator.is_done())),
+            "next" => Ok(Object::Function(Box::new(LoxCallable::NativeFunction(
+                NativeFunction::GeneratorNext(GeneratorNext {
+                    generator: generator.clone(),
+                }),
+            )))),
+            _ => Err(LoxError::new_runtime_error(
+                name.clone(),
+                ErrorKind::PropertyError,
+                format!("Undefined property '{}'.", name.lexeme),
+            )),
+        }
+    }
+
+    /// run the body up to its next yield, `sent` becomes the value of the yield expression it was suspended at
+    pub fn resume(
+        &self,
+        interpreter: &mut Interpreter,
+        sent: Option<Object>,
+    ) -> Result<Resumed, LoxError> {
+        if self.is_done() {
+            return Ok(Resumed::Returned(None));
+        }
+        let Some(mut body) = self.body.borrow_mut().take() else {
+            return Err(LoxError::new_runtime_error(
+                self.name.clone(),
+                ErrorKind::Error,
+                "Generator is already running.".into(),
+            ));
+        };
+        // the body runs on the caller's native stack, resuming it counts as a call toward the depth limit
+        if let Err(e) = interpreter.enter_call(&self.name) {
+            *self.body.borrow_mut() = Some(body);
+            return Err(e);
+        }
+        let resumed = body.resume(interpreter, sent);
+        interpreter.exit_call();
+        match resumed {
+            Ok(Resumed::Yielded(_)) => *self.body.borrow_mut() = Some(body),
+            _ => self.done.set(true),
+        }
+        resumed
+    }
+}
+
+/// the body is away while it runs, the environments it holds are on the interpreter's stack then
+impl Managed for LoxGenerator {
+    fn trace(&self, tracer: &mut Tracer) -> bool {
+        match self.body.try_borrow() {
+            Ok(body) => {
+                if let Some(body) = body.as_ref() {
+                    body.trace(tracer);
+                }
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn clear(&self) {
+        let body = self
+            .body
+            .try_borrow_mut()
+            .ok()
+            .and_then(|mut body| body.take());
+        drop(body);
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self
+                .body
+                .try_borrow()
+                .map_or(0, |body| body.as_ref().map_or(0, Resumable::size))
+    }
+}
+
+impl Debug for LoxGenerator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Display for LoxGenerator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<generator {}>", self.name.lexeme)
+    }
+}
+
+impl PartialEq for LoxGenerator {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// `next(value)` of a generator, returns the yielded value, or the returned one once the body has finished
+#[derive(Debug, Clone)]
+pub struct GeneratorNext {
+    generator: Rc<LoxGenerator>,
+}
+
+impl GeneratorNext {
+    pub fn trace(&self, tracer: &mut Tracer) {
+        tracer.edge(&self.generator);
+    }
+
+    pub fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Option<Object>>,
+    ) -> Result<Option<Object>, LoxError> {
+        let sent = arguments.into_iter().next().flatten();
+        match self.generator.resume(interpreter, sent)? {
+            Resumed::Yielded(value) | Resumed::Returned(value) => Ok(value),
+            Resumed::Waiting(_) | Resumed::Awaiting(_) => unreachable!("generators don't wait"),
+        }
+    }
+
+    pub fn arity(&self) -> Arity {
+        Arity {
+            min: 0,
+            max: Some(1),
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        "<native fn generator.next>".into()
+    }
+}
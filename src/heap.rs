@@ -0,0 +1,446 @@
+use crate::class::LoxClass;
+use crate::environment::Environment;
+use crate::error::LoxError;
+use crate::function::native_function::NativeFunction;
+use crate::function::{Arity, LoxCallable};
+use crate::instance::LoxInstance;
+use crate::interpreter::Interpreter;
+use crate::limits::Limit;
+use crate::list::LoxList;
+use crate::map::{LoxMap, MapKey};
+use crate::object::Object;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+/// collections run once this many objects were allocated since the last one, or twice the live count if that is more
+const INITIAL_THRESHOLD: usize = 1024;
+
+thread_local! {
+    static HEAP: RefCell<Heap> = RefCell::new(Heap::new());
+}
+
+/// objects that can take part in a reference cycle, they still live in `Rc`s and are freed by reference counting,
+/// the heap only finds cycles nothing else points at and breaks them
+///
+/// a collection doesn't need to know the roots: an object referenced more times than the other heap objects
+/// reference it is held from outside the heap, by the interpreter or a value on the stack, and everything it
+/// reaches is alive, the rest is garbage and is cleared so that the `Rc`s in the cycle drop
+struct Heap {
+    objects: Vec<Weak<dyn Managed>>,
+    /// collect on every allocation, set with the `LOX_GC_STRESS` environment variable
+    stress: bool,
+    threshold: usize,
+    collections: usize,
+    freed: usize,
+    /// caps on the live objects and their bytes, set for each run
+    max_objects: Option<usize>,
+    max_bytes: Option<usize>,
+    /// bytes of the objects alive at the last collection and of the ones allocated since, only kept under a byte cap
+    bytes: usize,
+    /// set once a cap is exceeded, the interpreter fails the run at its next check
+    exceeded: bool,
+}
+
+/// heap statistics, as returned by `heapStats()`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct HeapStats {
+    pub live: usize,
+    pub bytes: usize,
+    pub collections: usize,
+    pub freed: usize,
+}
+
+/// an object the heap can look into
+pub(crate) trait Trace {
+    /// report every heap object this one references directly
+    fn trace(&self, tracer: &mut Tracer);
+    /// drop every reference this one holds, it's only called on garbage
+    fn clear(&mut self);
+    /// approximate number of bytes the object owns
+    fn size(&self) -> usize;
+}
+
+/// addresses of the heap objects referenced by the object being traced
+#[derive(Default)]
+pub(crate) struct Tracer {
+    edges: Vec<usize>,
+}
+
+impl Tracer {
+    pub fn edge<T: ?Sized>(&mut self, object: &Rc<T>) {
+        self.edges.push(address(object));
+    }
+
+    pub fn value(&mut self, value: &Object) {
+        match value {
+            Object::Instance(instance) => self.edge(instance),
+            Object::List(list) => self.edge(list),
+            Object::Map(map) => self.edge(map),
+            Object::Class(class) => self.edge(class),
+            Object::Function(function) => match function.as_ref() {
+                LoxCallable::LoxFunction(function) => self.edge(&function.closure),
+                LoxCallable::LoxClass(class) => self.edge(class),
+                LoxCallable::NativeFunction(function) => function.trace(self),
+            },
+            Object::Module(module) => self.edge(module),
+            Object::Trait(r#trait) => self.edge(r#trait),
+            Object::Variant(variant) => self.edge(variant),
+            Object::Generator(generator) => self.edge(generator),
+            Object::Fiber(fiber) => self.edge(fiber),
+            Object::Channel(channel) => self.edge(channel),
+            Object::Promise(promise) => self.edge(promise),
+            Object::Str(_)
+            | Object::Number(_)
+            | Object::Int(_)
+            | Object::BigInt(_)
+            | Object::Rational(_)
+            | Object::Decimal(_)
+            | Object::Boolean(_)
+            | Object::Void
+            | Object::Range(_)
+            | Object::Enum(_) => {}
+        }
+    }
+
+    /// values carried by an error on its way up, such as a returned or thrown value
+    pub fn error(&mut self, error: &LoxError) {
+        match error {
+            LoxError::ReturnError(error) => self.optional(&error.value),
+            LoxError::ThrowError(error) => self.optional(&error.value),
+            LoxError::TailCall(call) => {
+                self.edge(&call.function.closure);
+                call.arguments.iter().for_each(|value| self.optional(value));
+                call.named
+                    .iter()
+                    .for_each(|(_, value)| self.optional(value));
+            }
+            LoxError::ParseError(_) | LoxError::RuntimeError(_) | LoxError::LimitError(_) => {}
+        }
+    }
+
+    pub fn optional(&mut self, value: &Option<Object>) {
+        if let Some(value) = value {
+            self.value(value);
+        }
+    }
+}
+
+/// type-erased side of `Trace`, objects that are borrowed while collecting can't be traced and are kept alive
+///
+/// objects with cells of their own, such as generators, implement it directly instead of living in a `RefCell`
+pub(crate) trait Managed {
+    /// `false` when the object can't be looked into right now
+    fn trace(&self, tracer: &mut Tracer) -> bool;
+    fn clear(&self);
+    fn size(&self) -> usize;
+}
+
+impl<T: Trace> Managed for RefCell<T> {
+    fn trace(&self, tracer: &mut Tracer) -> bool {
+        match self.try_borrow() {
+            Ok(object) => {
+                object.trace(tracer);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn clear(&self) {
+        if let Ok(mut object) = self.try_borrow_mut() {
+            object.clear();
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.try_borrow().map_or(0, |object| object.size())
+    }
+}
+
+/// classes are immutable, a cycle through a class always goes through an environment or an instance too
+impl Managed for LoxClass {
+    fn trace(&self, tracer: &mut Tracer) -> bool {
+        LoxClass::trace(self, tracer);
+        true
+    }
+
+    fn clear(&self) {}
+
+    fn size(&self) -> usize {
+        LoxClass::size(self)
+    }
+}
+
+fn address<T: ?Sized>(object: &Rc<T>) -> usize {
+    Rc::as_ptr(object) as *const () as usize
+}
+
+impl Heap {
+    fn new() -> Self {
+        Heap {
+            objects: vec![],
+            stress: std::env::var_os("LOX_GC_STRESS").is_some(),
+            threshold: INITIAL_THRESHOLD,
+            collections: 0,
+            freed: 0,
+            max_objects: None,
+            max_bytes: None,
+            bytes: 0,
+            exceeded: false,
+        }
+    }
+
+    /// whether the objects tracked or their bytes are over a cap, the tracked objects include dead ones
+    /// until the next collection drops them
+    fn over_limit(&self) -> bool {
+        self.max_objects.is_some_and(|max| self.objects.len() > max)
+            || self.max_bytes.is_some_and(|max| self.bytes > max)
+    }
+}
+
+/// allocate an object the heap looks into, the other constructors below are shorthands for it
+pub(crate) fn track<T: Managed + 'static>(object: T) -> Rc<T> {
+    let object = Rc::new(object);
+    let weak: Weak<dyn Managed> = Rc::downgrade(&object) as Weak<dyn Managed>;
+    let due = HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.objects.push(weak);
+        if heap.max_bytes.is_some() {
+            heap.bytes += object.size();
+        }
+        let over_limit = !heap.exceeded && heap.over_limit();
+        heap.stress || heap.objects.len() >= heap.threshold || over_limit
+    });
+    if due {
+        collect();
+    }
+    object
+}
+
+/// cap the live objects and their bytes, `None` is unlimited, a run over a cap fails with `Limit::Memory`
+pub(crate) fn limit(objects: Option<usize>, bytes: Option<usize>) {
+    HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.max_objects = objects;
+        heap.max_bytes = bytes;
+        heap.exceeded = false;
+    });
+    if objects.is_some() || bytes.is_some() {
+        collect();
+    }
+}
+
+/// whether the live objects went over a cap since the caps were set
+pub(crate) fn exceeded() -> bool {
+    HEAP.with(|heap| heap.borrow().exceeded)
+}
+
+/// count bytes a value is about to take outside the heap objects, such as a string being built or a bigint result,
+/// against the byte cap before allocating them, a collection runs first when they don't fit
+pub(crate) fn charge(bytes: usize) -> Result<(), Limit> {
+    let fits = || {
+        HEAP.with(|heap| {
+            let mut heap = heap.borrow_mut();
+            match heap.max_bytes {
+                Some(max) if heap.bytes.saturating_add(bytes) > max => false,
+                Some(_) => {
+                    heap.bytes += bytes;
+                    true
+                }
+                None => true,
+            }
+        })
+    };
+    if fits() {
+        return Ok(());
+    }
+    collect();
+    if fits() {
+        return Ok(());
+    }
+    HEAP.with(|heap| heap.borrow_mut().exceeded = true);
+    Err(Limit::Memory)
+}
+
+/// bytes a value owns outside the heap objects, the text of a string or the digits of a bignum, split between the
+/// values sharing them
+pub(crate) fn payload(value: &Object) -> usize {
+    fn shared<T: ?Sized>(value: &Rc<T>, bytes: usize) -> usize {
+        bytes / Rc::strong_count(value)
+    }
+    match value {
+        Object::Str(string) => shared(string, string.len()),
+        Object::BigInt(n) => shared(n, (n.bits() / 8) as usize),
+        Object::Rational(n) => shared(n, ((n.numer().bits() + n.denom().bits()) / 8) as usize),
+        _ => 0,
+    }
+}
+
+pub(crate) fn environment(environment: Environment) -> Rc<RefCell<Environment>> {
+    track(RefCell::new(environment))
+}
+
+pub(crate) fn instance(instance: LoxInstance) -> Rc<RefCell<LoxInstance>> {
+    track(RefCell::new(instance))
+}
+
+pub(crate) fn list(list: LoxList) -> Rc<RefCell<LoxList>> {
+    track(RefCell::new(list))
+}
+
+pub(crate) fn map(map: LoxMap) -> Rc<RefCell<LoxMap>> {
+    track(RefCell::new(map))
+}
+
+pub(crate) fn class(class: LoxClass) -> Rc<LoxClass> {
+    track(class)
+}
+
+/// free every cycle that is no longer reachable, returns the number of objects freed
+pub(crate) fn collect() -> usize {
+    let objects = HEAP.with(|heap| std::mem::take(&mut heap.borrow_mut().objects));
+    let live: Vec<Rc<dyn Managed>> = objects.iter().filter_map(Weak::upgrade).collect();
+    let indices: HashMap<usize, usize> = live
+        .iter()
+        .enumerate()
+        .map(|(index, object)| (address(object), index))
+        .collect();
+
+    // references from outside the heap: the strong count less the one held by `live` and the ones from heap objects
+    let mut edges = Vec::with_capacity(live.len());
+    let mut external: Vec<isize> = live
+        .iter()
+        .map(|object| Rc::strong_count(object) as isize - 1)
+        .collect();
+    let mut traced = vec![true; live.len()];
+    for (index, object) in live.iter().enumerate() {
+        let mut tracer = Tracer::default();
+        traced[index] = object.trace(&mut tracer);
+        let children: Vec<usize> = tracer
+            .edges
+            .iter()
+            .filter_map(|edge| indices.get(edge).copied())
+            .collect();
+        for child in &children {
+            external[*child] -= 1;
+        }
+        edges.push(children);
+    }
+
+    let mut reachable = vec![false; live.len()];
+    let mut pending: Vec<usize> = (0..live.len())
+        .filter(|index| external[*index] > 0 || !traced[*index])
+        .collect();
+    while let Some(index) = pending.pop() {
+        if reachable[index] {
+            continue;
+        }
+        reachable[index] = true;
+        pending.extend(edges[index].iter().filter(|child| !reachable[**child]));
+    }
+
+    let mut freed = 0;
+    for (object, reachable) in live.iter().zip(&reachable) {
+        if !reachable {
+            object.clear();
+            freed += 1;
+        }
+    }
+    let survivors: Vec<Weak<dyn Managed>> = live
+        .iter()
+        .zip(&reachable)
+        .filter(|(_, reachable)| **reachable)
+        .map(|(object, _)| Rc::downgrade(object))
+        .collect();
+    let count = survivors.len();
+    let bytes = HEAP
+        .with(|heap| heap.borrow().max_bytes.is_some())
+        .then(|| {
+            live.iter()
+                .zip(&reachable)
+                .filter(|(_, reachable)| **reachable)
+                .map(|(object, _)| object.size())
+                .sum()
+        });
+    drop(live);
+
+    HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        // objects allocated while the garbage was dropping
+        let allocated = std::mem::replace(&mut heap.objects, survivors);
+        heap.objects.extend(allocated);
+        heap.collections += 1;
+        heap.freed += freed;
+        heap.threshold = INITIAL_THRESHOLD.max(count * 2);
+        heap.bytes = bytes.unwrap_or(0);
+        // checked on the survivors, the objects allocated while dropping the garbage are counted next time
+        let over_limit = heap.max_objects.is_some_and(|max| count > max)
+            || heap.max_bytes.is_some_and(|max| heap.bytes > max);
+        heap.exceeded |= over_limit;
+    });
+    freed
+}
+
+pub(crate) fn stats() -> HeapStats {
+    HEAP.with(|heap| {
+        let heap = heap.borrow();
+        let live: Vec<Rc<dyn Managed>> = heap.objects.iter().filter_map(Weak::upgrade).collect();
+        HeapStats {
+            live: live.len(),
+            bytes: live.iter().map(|object| object.size()).sum(),
+            collections: heap.collections,
+            freed: heap.freed,
+        }
+    })
+}
+
+/// `gc()` and `heapStats()`
+#[derive(Debug, Clone)]
+pub enum HeapFunction {
+    Gc,
+    HeapStats,
+}
+
+impl HeapFunction {
+    pub fn object(self) -> Object {
+        Object::Function(Box::new(LoxCallable::NativeFunction(NativeFunction::Heap(
+            self,
+        ))))
+    }
+
+    pub fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        _arguments: Vec<Option<Object>>,
+    ) -> Result<Option<Object>, LoxError> {
+        match self {
+            HeapFunction::Gc => Ok(Some(Object::Int(collect() as i64))),
+            HeapFunction::HeapStats => {
+                let stats = stats();
+                let mut map = LoxMap::new();
+                let entries = [
+                    ("live", stats.live),
+                    ("bytes", stats.bytes),
+                    ("collections", stats.collections),
+                    ("freed", stats.freed),
+                ];
+                for (key, value) in entries {
+                    map.insert(MapKey::Str(key.into()), Some(Object::Int(value as i64)));
+                }
+                Ok(Some(Object::Map(self::map(map))))
+            }
+        }
+    }
+
+    pub fn arity(&self) -> Arity {
+        Arity::exact(0)
+    }
+
+    pub fn to_string(&self) -> String {
+        match self {
+            HeapFunction::Gc => "<native fn gc>".into(),
+            HeapFunction::HeapStats => "<native fn heapStats>".into(),
+        }
+    }
+}
@@ -1,43 +1,64 @@
 use crate::class::LoxClass;
-use crate::error::LoxError;
+use crate::error::{ErrorKind, LoxError};
 use crate::function::LoxCallable;
 use crate::function::LoxCallable::LoxFunction;
+use crate::heap::{self, Trace, Tracer};
 use crate::object::Object;
+use crate::symbol::Symbol;
 use crate::token::Token;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
+use std::rc::Rc;
 
-#[derive(Debug, Clone, PartialEq)]
+/// instances are shared, `Object::Instance` holds a reference so that every alias sees the same fields
+#[derive(Debug)]
 pub(crate) struct LoxInstance {
-    pub klass: LoxClass,
-    fields: HashMap<String, Object>,
+    pub klass: Rc<LoxClass>,
+    fields: HashMap<Symbol, Option<Object>>,
 }
 
 impl LoxInstance {
-    pub fn new(klass: LoxClass) -> Self {
+    pub fn new(klass: Rc<LoxClass>) -> Self {
         LoxInstance {
             klass,
             fields: HashMap::new(),
         }
     }
 
-    pub fn get(&self, name: Token) -> Result<Object, LoxError> {
-        if let Some(obj) = self.fields.get(&name.lexeme) {
+    pub fn object(self) -> Object {
+        Object::Instance(heap::instance(self))
+    }
+
+    pub fn field(&self, name: &Symbol) -> Option<Object> {
+        self.fields.get(name).cloned().flatten()
+    }
+
+    /// a field, a method bound to the instance, or a constant of its class
+    pub fn get(
+        instance: &Rc<RefCell<LoxInstance>>,
+        name: Token,
+    ) -> Result<Option<Object>, LoxError> {
+        if let Some(obj) = instance.borrow().fields.get(&name.lexeme) {
             return Ok(obj.clone());
         }
-        let method = self.klass.find_method(&name.lexeme);
+        let method = instance.borrow().klass.find_method(&name.lexeme);
         if let Some(method) = method {
-            return Ok(Object::Function(Box::new(LoxFunction(
-                method.bind(self.clone()),
-            ))));
+            return Ok(Some(Object::Function(Box::new(LoxFunction(
+                method.bind(Object::Instance(instance.clone())),
+            )))));
         }
-        Err(LoxError::new_parse_error(
+        if let Some(constant) = instance.borrow().klass.find_field(&name.lexeme) {
+            return Ok(constant);
+        }
+        Err(LoxError::new_runtime_error(
             name.clone(),
+            ErrorKind::PropertyError,
             format!("Undefined property '{}'.", name.lexeme),
         ))
     }
 
-    pub fn set(&mut self, name: &Token, value: Object) -> Result<Object, LoxError> {
+    pub fn set(&mut self, name: &Token, value: Option<Object>) -> Result<Object, LoxError> {
         self.fields.insert(name.lexeme.clone(), value);
         Ok(Object::Void)
     }
@@ -48,3 +69,35 @@ impl Display for LoxInstance {
         write!(f, "{} instance", self.klass.name)
     }
 }
+
+impl PartialEq for LoxInstance {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Trace for LoxInstance {
+    fn trace(&self, tracer: &mut Tracer) {
+        tracer.edge(&self.klass);
+        self.fields
+            .values()
+            .flatten()
+            .for_each(|value| tracer.value(value));
+    }
+
+    fn clear(&mut self) {
+        self.fields.clear();
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.fields.capacity()
+                * (std::mem::size_of::<Symbol>() + std::mem::size_of::<Option<Object>>())
+            + self
+                .fields
+                .values()
+                .flatten()
+                .map(heap::payload)
+                .sum::<usize>()
+    }
+}
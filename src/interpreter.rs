@@ -1,12 +1,15 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
 use std::mem::discriminant;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::thread::LocalKey;
 
 use crate::class::LoxClass;
-use crate::environment::Environment;
-use crate::error::{LoxError, ParseError, Return};
+use crate::environment::{Environment, Slot};
+use crate::error::{ErrorKind, LoxError, ParseError, Return, Throw};
 use crate::expr::binary::Binary;
 use crate::expr::call::Call;
 use crate::expr::grouping::Grouping;
@@ -14,17 +17,32 @@ use crate::expr::literal::Literal;
 use crate::expr::set::Set;
 use crate::expr::this::This;
 use crate::expr::unary::Unary;
-use crate::expr::{assign, get, logical, variable, Expr};
-use crate::function::lox_function::LoxFunction;
+use crate::expr::{assign, get, logical, variable, Expr, NodeId};
+use crate::fiber::{FiberFunction, LoxChannel, LoxFiber, Scheduler};
+use crate::function::lox_function::{LoxFunction, TailCall};
 use crate::function::LoxCallable::NativeFunction;
 use crate::function::{native_function, LoxCallable};
+use crate::generator::LoxGenerator;
+use crate::heap::{self, HeapFunction};
 use crate::instance::LoxInstance;
+use crate::iterator::LoxIterator;
+use crate::limits::{Budget, InterruptHandle, Limits};
+use crate::list::LoxList;
 use crate::lox::Lox;
-use crate::object::Object;
+use crate::map::{LoxMap, MapKey};
+use crate::module::LoxModule;
+use crate::number::{self, NumberFunction, Overflow};
+use crate::object::{repr, Object};
+use crate::pattern::Pattern;
+use crate::promise::{LoxPromise, PromiseFunction};
+use crate::r#enum::{LoxEnum, LoxVariant};
+use crate::r#trait::LoxTrait;
+use crate::range::LoxRange;
 use crate::stmt::class::Class;
 use crate::stmt::function::Function;
 use crate::stmt::print::Print;
-use crate::stmt::{block, expression, r#if, r#return, r#while, Stmt};
+use crate::stmt::{block, expression, r#enum, r#if, r#return, r#trait, r#while, Stmt};
+use crate::symbol::{self, Symbol};
 use crate::token::token_type::TokenType;
 use crate::token::Token;
 use crate::{expr, function, stmt};
@@ -32,67 +50,297 @@ use crate::{expr, function, stmt};
 pub(crate) struct Interpreter {
     globals: Rc<RefCell<Environment>>,
     environment: Rc<RefCell<Environment>>,
-    locals: HashMap<String, usize>,
+    /// slot of every local variable, `this` and `super` expression, by the id of the expression
+    locals: HashMap<NodeId, Slot>,
+    /// functions being called, innermost last, use to build stack traces
+    frames: Vec<CallFrame>,
+    /// file of the module being executed, imports are resolved relative to it
+    script: Option<PathBuf>,
+    /// loaded modules by canonical path, each module only runs once
+    modules: HashMap<PathBuf, Rc<LoxModule>>,
+    /// modules being loaded, innermost last, use to report import cycles
+    loading: Vec<PathBuf>,
+    /// names exported so far by the module being executed
+    exports: HashSet<Symbol>,
+    /// spawned fibers, run once the script's statements have executed
+    scheduler: Scheduler,
+    /// what int arithmetic does on overflow
+    overflow: Overflow,
+    /// calls in tail position, by id, the function they return from makes them once its body is done
+    tail_calls: HashSet<NodeId>,
+    /// Lox function calls being executed, tail calls don't count
+    depth: usize,
+    /// calls deeper than this raise a stack overflow error, set with the `LOX_MAX_DEPTH` environment variable
+    max_depth: usize,
+    /// steps, time and memory left to the current run, unlimited unless the host sets limits
+    budget: Budget,
+    /// the `Error` class of the prelude, runtime errors are caught as its instances
+    error_class: Option<Rc<LoxClass>>,
+}
+
+/// default call depth limit
+pub(crate) const MAX_DEPTH: usize = 10_000;
+
+/// highest call depth `LOX_MAX_DEPTH` can set, the stack for it is as large as a thread's stack can safely get
+const DEPTH_LIMIT: usize = 50_000;
+
+/// native stack the interpreter's thread gets for each call of the depth limit,
+/// a call with nested statements takes up to about 45 KiB in a debug build
+pub(crate) const STACK_PER_CALL: usize = 64 << 10;
+
+/// native stack every step must leave free, expressions and statements nested deeper than the stack allows raise
+/// the same stack overflow error as calls, one step takes up to about 20 KiB in a debug build
+const STACK_RED_ZONE: usize = 256 << 10;
+
+/// the call depth limit, the `LOX_MAX_DEPTH` environment variable clamped to `DEPTH_LIMIT`
+pub(crate) fn max_depth() -> usize {
+    std::env::var("LOX_MAX_DEPTH")
+        .ok()
+        .and_then(|depth| depth.parse().ok())
+        .unwrap_or(MAX_DEPTH)
+        .min(DEPTH_LIMIT)
+}
+
+/// declarations every global scope starts with
+const PRELUDE: &str = r#"
+class Error {
+  init(message, kind = "Error", line = nil, stackTrace = []) {
+    this.message = message;
+    this.kind = kind;
+    this.line = line;
+    this.stackTrace = stackTrace;
+  }
+}
+"#;
+
+/// identical frames shown in a stack trace before the rest of them are counted instead
+const REPEATED_FRAMES: usize = 3;
+
+struct CallFrame {
+    /// callee description, such as <fn add>
+    name: String,
+    /// line of the call site
+    line: usize,
 }
 
 impl Interpreter {
     pub fn new() -> Self {
-        let mut globals = Rc::new(RefCell::new(Environment::new()));
+        let globals = Interpreter::new_globals();
+        let environment = globals.clone();
+
+        let mut interpreter = Self {
+            locals: HashMap::new(),
+            globals,
+            environment,
+            frames: vec![],
+            script: None,
+            modules: HashMap::new(),
+            loading: vec![],
+            exports: HashSet::new(),
+            scheduler: Scheduler::default(),
+            overflow: Overflow::from_env(),
+            tail_calls: HashSet::new(),
+            depth: 0,
+            max_depth: max_depth(),
+            budget: Budget::new(Limits::default()),
+            error_class: None,
+        };
+        let prelude = Lox::compile(&mut interpreter, PRELUDE.into()).expect("the prelude compiles");
+        for stmt in &prelude {
+            interpreter.execute(stmt).expect("the prelude runs");
+        }
+        if let Some(Some(Object::Class(class))) = interpreter
+            .globals
+            .borrow()
+            .get_here(&Symbol::intern("Error"))
+        {
+            interpreter.error_class = Some(class);
+        }
+        interpreter
+    }
+
+    /// interpreter for a script file, so that the file's imports are resolved relative to it
+    pub fn new_from_file(path: &Path) -> Self {
+        let mut interpreter = Interpreter::new();
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        interpreter.loading.push(path.clone());
+        interpreter.script = Some(path);
+        interpreter
+    }
+
+    /// global scope with the native functions defined
+    fn new_globals() -> Rc<RefCell<Environment>> {
+        let globals = heap::environment(Environment::new());
         globals.borrow_mut().define(
             "clock".into(),
             Some(Object::Function(Box::new(NativeFunction(
                 native_function::NativeFunction::clock(),
             )))),
         );
-        let environment = globals.clone();
-
-        Self {
-            locals: HashMap::new(),
-            globals,
-            environment,
+        let fibers = [
+            ("Fiber", FiberFunction::Fiber),
+            ("spawn", FiberFunction::Spawn),
+            ("sleep", FiberFunction::Sleep),
+            ("now", FiberFunction::Now),
+            ("setTimeout", FiberFunction::SetTimeout),
+            ("clearTimeout", FiberFunction::ClearTimeout),
+            ("Channel", FiberFunction::Channel),
+        ];
+        for (name, function) in fibers {
+            globals
+                .borrow_mut()
+                .define(name.into(), Some(function.object()));
         }
+        globals
+            .borrow_mut()
+            .define("Promise".into(), Some(PromiseFunction::Promise.object()));
+        let numbers = [
+            ("bigint", NumberFunction::BigInt),
+            ("rational", NumberFunction::Rational),
+            ("decimal", NumberFunction::Decimal),
+            ("float", NumberFunction::Float),
+            ("round", NumberFunction::Round),
+            ("format", NumberFunction::Format),
+        ];
+        for (name, function) in numbers {
+            globals
+                .borrow_mut()
+                .define(name.into(), Some(function.object()));
+        }
+        for (name, function) in [
+            ("gc", HeapFunction::Gc),
+            ("heapStats", HeapFunction::HeapStats),
+        ] {
+            globals
+                .borrow_mut()
+                .define(name.into(), Some(function.object()));
+        }
+        globals
     }
+
     pub fn interpret(&mut self, statements: &[Stmt]) {
-        for stmt in statements {
-            let result = self.execute(stmt);
-            if let Err(e) = result {
-                Lox::runtime_error(e);
-                return;
-            }
+        if let Err(e) = self.run(statements) {
+            Lox::runtime_error(e);
+        }
+    }
+
+    /// run a script and the fibers it spawns within the limits, errors are returned to the host instead of
+    /// being reported, a `LoxError::LimitError` tells which budget was exceeded
+    pub fn run(&mut self, statements: &[Stmt]) -> Result<(), LoxError> {
+        self.budget.start();
+        let mut result = statements
+            .iter()
+            .try_for_each(|stmt| self.execute(stmt).map(|_| ()))
+            .and_then(|_| Scheduler::run(self));
+        self.budget.finish();
+        if let Err(e) = &mut result {
+            self.capture_trace(e);
         }
+        result
+    }
+
+    /// replace the limits, they apply from the next run
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.budget.set_limits(limits);
+    }
+
+    /// a handle another thread can stop the runs of this interpreter with
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.budget.interrupt_handle()
+    }
+
+    pub(crate) fn scheduler(&mut self) -> &mut Scheduler {
+        &mut self.scheduler
     }
 
     fn evaluate(&mut self, expr: &Expr) -> Result<Option<Object>, LoxError> {
+        self.budget.step().map_err(LoxError::LimitError)?;
+        self.check_stack(|| expr.line())?;
         return expr.accept(self);
     }
 
+    /// evaluate an expression in another environment, such as a default parameter value
+    pub(crate) fn evaluate_in(
+        &mut self,
+        expr: &Expr,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<Option<Object>, LoxError> {
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let result = self.evaluate(expr);
+        self.environment = previous;
+        result
+    }
+
     fn execute(&mut self, stmt: &Stmt) -> Result<Option<Object>, LoxError> {
+        self.budget.step().map_err(LoxError::LimitError)?;
+        self.check_stack(|| stmt.line())?;
         stmt.accept(self)
     }
 
+    /// fail with a stack overflow error before the native stack runs out, `line` is where the step is
+    fn check_stack(&self, line: impl FnOnce() -> Option<usize>) -> Result<(), LoxError> {
+        if stacker::remaining_stack().is_none_or(|remaining| remaining > STACK_RED_ZONE) {
+            return Ok(());
+        }
+        let line = line()
+            .or_else(|| self.frames.last().map(|frame| frame.line))
+            .unwrap_or_default();
+        Err(LoxError::new_runtime_error(
+            Token::new(TokenType::IDENTIFIER, "".into(), None, line),
+            ErrorKind::RecursionError,
+            "Stack overflow.".into(),
+        ))
+    }
+
+    /// execute a statement in another environment, such as a statement of a resumed generator
+    pub(crate) fn execute_in(
+        &mut self,
+        stmt: &Stmt,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<Option<Object>, LoxError> {
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let result = self.execute(stmt);
+        self.environment = previous;
+        result
+    }
+
     fn stringify(&self, object: Option<Object>) -> String {
         let Some(object) = object else {
-            return "null".into();
+            return "nil".into();
         };
         match object {
-            Object::Str(v) => v,
-            Object::Number(v) => {
-                let v = v.to_string();
-                if v.ends_with(".0") {
-                    (&v[0..v.len() - 2]).into()
-                } else {
-                    v
-                }
-            }
+            Object::Str(v) => v.to_string(),
+            Object::Number(v) => number::float_to_string(v),
+            Object::Int(v) => v.to_string(),
+            Object::BigInt(v) => v.to_string(),
+            Object::Rational(v) => v.to_string(),
+            Object::Decimal(v) => v.to_string(),
             Object::Boolean(v) => v.to_string(),
             Object::Void => "".into(),
             Object::Function(f) => f.to_string(),
             Object::Class(class) => class.to_string(),
-            Object::Instance(instance) => instance.to_string(),
+            Object::Instance(instance) => instance.borrow().to_string(),
+            Object::List(list) => list.borrow().to_string(),
+            Object::Map(map) => map.borrow().to_string(),
+            Object::Range(range) => range.to_string(),
+            Object::Module(module) => module.to_string(),
+            Object::Trait(r#trait) => r#trait.to_string(),
+            Object::Enum(r#enum) => r#enum.to_string(),
+            Object::Variant(variant) => variant.to_string(),
+            Object::Generator(generator) => generator.to_string(),
+            Object::Fiber(fiber) => fiber.to_string(),
+            Object::Channel(channel) => channel.to_string(),
+            Object::Promise(promise) => promise.to_string(),
         }
     }
 
-    fn is_truthy(&self, object: &Option<Object>) -> bool {
+    /// `+` with a string, the result is charged against the memory budget before it's built
+    fn concatenate(left: &str, right: &str) -> Result<Object, LoxError> {
+        heap::charge(left.len() + right.len()).map_err(LoxError::LimitError)?;
+        Ok(Object::string(format!("{}{}", left, right)))
+    }
+
+    pub(crate) fn is_truthy(&self, object: &Option<Object>) -> bool {
         match object {
             None => false,
             Some(Object::Boolean(v)) => *v,
@@ -110,13 +358,22 @@ impl Interpreter {
 
     pub(crate) fn execute_block(
         &mut self,
-        statements: Vec<Stmt>,
+        statements: &[Stmt],
         environment: Environment,
+    ) -> Result<(), LoxError> {
+        self.execute_block_in(statements, heap::environment(environment))
+    }
+
+    /// execute statements in an environment that is shared with the caller, such as a function's parameters
+    pub(crate) fn execute_block_in(
+        &mut self,
+        statements: &[Stmt],
+        environment: Rc<RefCell<Environment>>,
     ) -> Result<(), LoxError> {
         let previous = self.environment.clone();
-        self.environment = Rc::new(RefCell::new(environment));
+        self.environment = environment;
         for stmt in statements {
-            if let Err(e) = self.execute(&stmt) {
+            if let Err(e) = self.execute(stmt) {
                 self.environment = previous;
                 return Err(e);
             }
@@ -125,132 +382,646 @@ impl Interpreter {
         Ok(())
     }
 
-    /// save distance info to expr
-    pub(crate) fn resolve(&mut self, expr: &mut Expr, depth: usize) {
-        if let Expr::Variable(v) = expr {
-            v.distance = Some(depth)
+    /// check the arity and call a function, `token` is the call site used for errors and stack traces
+    fn call_function(
+        &mut self,
+        function: &LoxCallable,
+        arguments: Vec<Option<Object>>,
+        named: Vec<(Token, Option<Object>)>,
+        token: &Token,
+    ) -> Result<Option<Object>, LoxError> {
+        Interpreter::check_arity(function, arguments.len() + named.len(), token)?;
+        self.frames.push(CallFrame {
+            name: function.to_string(),
+            line: token.line,
+        });
+        let result = function.call_named(self, arguments, named, token);
+        if let Err(mut e) = result {
+            self.capture_trace(&mut e);
+            self.frames.pop();
+            return Err(e);
+        }
+        self.frames.pop();
+        result
+    }
+
+    pub(crate) fn check_arity(
+        function: &LoxCallable,
+        count: usize,
+        token: &Token,
+    ) -> Result<(), LoxError> {
+        if !function.arity().accepts(count) {
+            return Err(LoxError::new_runtime_error(
+                token.clone(),
+                ErrorKind::ArityError,
+                format!("Expected {} arguments but got {}", function.arity(), count),
+            ));
         }
-        // self.locals.insert(expr.id(), depth); copy from book
+        Ok(())
     }
 
-    fn lookup_variable(&mut self, name: Token, expr: &Expr) -> Result<Option<Object>, LoxError> {
-        //   let distance = self.locals.get(&expr.id()); // TODO bug clone will change expr id
-        let distance = expr.distance();
-        println!("{}, distance is {:?}", name, distance);
-        if let Some(distance) = distance {
-            match self.environment.borrow().get_at(distance, &name.lexeme) {
-                None => Ok(None),
-                Some(v) => Ok(v.clone()),
+    /// count a Lox function call, `paren` is the call site, fails once the calls go deeper than the limit
+    pub(crate) fn enter_call(&mut self, paren: &Token) -> Result<(), LoxError> {
+        if self.depth >= self.max_depth {
+            return Err(LoxError::new_runtime_error(
+                paren.clone(),
+                ErrorKind::RecursionError,
+                "Stack overflow.".into(),
+            ));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    pub(crate) fn exit_call(&mut self) {
+        self.depth -= 1;
+    }
+
+    /// record a tail call on the call stack, successive tail calls made from the same call share one frame
+    pub(crate) fn tail_frame(&mut self, name: String, line: usize, reuse: bool) {
+        if reuse {
+            self.frames.pop();
+        }
+        self.frames.push(CallFrame { name, line });
+    }
+
+    pub(crate) fn pop_frame(&mut self) {
+        self.frames.pop();
+    }
+
+    /// call an evaluated callee, `paren` is the call site
+    pub(crate) fn call_value(
+        &mut self,
+        callee: Option<Object>,
+        arguments: Vec<Option<Object>>,
+        named: Vec<(Token, Option<Object>)>,
+        paren: &Token,
+    ) -> Result<Option<Object>, LoxError> {
+        let function = match callee {
+            Some(Object::Function(function)) => function,
+            Some(Object::Class(class)) => Box::new(LoxCallable::LoxClass(class)),
+            _ => {
+                return Err(LoxError::new_runtime_error(
+                    paren.clone(),
+                    ErrorKind::TypeError,
+                    "Can only call functions and classes.".into(),
+                ))
+            }
+        };
+        self.call_function(&function, arguments, named, paren)
+    }
+
+    /// evaluate the positional and named arguments of a call
+    fn arguments(
+        &mut self,
+        call: &Call,
+    ) -> Result<(Vec<Option<Object>>, Vec<(Token, Option<Object>)>), LoxError> {
+        let mut arguments = vec![];
+        for argument in &call.arguments {
+            arguments.push(self.evaluate(argument)?);
+        }
+        let mut named = vec![];
+        for (name, argument) in &call.named {
+            named.push((name.clone(), self.evaluate(argument)?));
+        }
+        Ok((arguments, named))
+    }
+
+    /// `return f(x);` in tail position, a call to a Lox function is handed to the function being returned from
+    /// so that it runs in place of its body, anything else is called right away and its value returned
+    fn return_tail_call(&mut self, call: &Call) -> Result<(), LoxError> {
+        let callee = self.evaluate(&call.callee)?;
+        let (arguments, named) = self.arguments(call)?;
+        if let Some(Object::Function(function)) = &callee {
+            if let LoxCallable::LoxFunction(function) = function.as_ref() {
+                return Err(LoxError::TailCall(Box::new(TailCall {
+                    function: function.clone(),
+                    arguments,
+                    named,
+                    paren: call.paren.clone(),
+                })));
+            }
+        }
+        let value = self.call_value(callee, arguments, named, &call.paren)?;
+        Err(LoxError::ReturnError(Return { value }))
+    }
+
+    /// call the special method implementing an operator, such as `__add__` for `+`,
+    /// `None` when the operand is not an instance or its class doesn't define the method
+    fn call_operator(
+        &mut self,
+        operand: &Option<Object>,
+        method: &'static LocalKey<Symbol>,
+        arguments: Vec<Option<Object>>,
+        token: &Token,
+    ) -> Result<Option<Option<Object>>, LoxError> {
+        let Some(Object::Instance(instance)) = operand else {
+            return Ok(None);
+        };
+        let function = method.with(|method| instance.borrow().klass.find_method(method));
+        let Some(function) = function else {
+            return Ok(None);
+        };
+        let function = LoxCallable::LoxFunction(function.bind(Object::Instance(instance.clone())));
+        self.call_function(&function, arguments, vec![], token)
+            .map(Some)
+    }
+
+    /// record the current call stack into a runtime error or thrown value, unless it already has one
+    pub(crate) fn capture_trace(&self, error: &mut LoxError) {
+        let mut line = error.line();
+        let Some(trace @ None) = error.trace_mut() else {
+            return;
+        };
+        let mut frames: Vec<String> = vec![];
+        let mut repeated = 0;
+        for frame in self.frames.iter().rev() {
+            let frame_line = format!("[line {}] in {}", line, frame.name);
+            line = frame.line;
+            if frames.last() == Some(&frame_line) {
+                repeated += 1;
+                if repeated >= REPEATED_FRAMES {
+                    continue;
+                }
+            } else {
+                Interpreter::count_repeated(&mut frames, repeated);
+                repeated = 0;
+            }
+            frames.push(frame_line);
+        }
+        Interpreter::count_repeated(&mut frames, repeated);
+        frames.push(format!("[line {}] in script", line));
+        *trace = Some(frames);
+    }
+
+    /// deep recursion fills the trace with one frame, the frames past the first few are counted
+    fn count_repeated(frames: &mut Vec<String>, repeated: usize) {
+        if repeated >= REPEATED_FRAMES {
+            frames.push(format!(
+                "[previous frame repeated {} more times]",
+                repeated + 1 - REPEATED_FRAMES
+            ));
+        }
+    }
+
+    /// methods mixed in by the traits of a class, a name provided by two traits must be overridden by the class
+    fn trait_methods(&mut self, stmt: &Class) -> Result<HashMap<Symbol, LoxFunction>, LoxError> {
+        let mut methods: HashMap<Symbol, LoxFunction> = HashMap::new();
+        let mut owners: HashMap<Symbol, Symbol> = HashMap::new();
+        for variable in &stmt.traits {
+            let Some(Object::Trait(r#trait)) = self.evaluate(&Expr::Variable(variable.clone()))?
+            else {
+                return Err(LoxError::new_runtime_error(
+                    variable.name.clone(),
+                    ErrorKind::TypeError,
+                    "Can only mix in traits.".into(),
+                ));
+            };
+            for (name, method) in &r#trait.methods {
+                let overridden = stmt.methods.iter().any(|m| &m.name.lexeme == name);
+                if let Some(owner) = owners.get(name) {
+                    if !overridden {
+                        return Err(LoxError::new_runtime_error(
+                            variable.name.clone(),
+                            ErrorKind::TypeError,
+                            format!(
+                                "Method '{}' is provided by both '{}' and '{}', override it in the class.",
+                                name, owner, r#trait.name
+                            ),
+                        ));
+                    }
+                }
+                owners.insert(name.clone(), r#trait.name.clone());
+                methods.insert(name.clone(), method.clone());
             }
-        } else {
-            self.globals.borrow().get(&name)
+        }
+        Ok(methods)
+    }
+
+    /// value bound by a catch clause, runtime errors become `Error` instances, other errors are not catchable
+    pub(crate) fn caught_value(&mut self, mut error: LoxError) -> Result<Option<Object>, LoxError> {
+        self.capture_trace(&mut error);
+        match error {
+            LoxError::ThrowError(throw) => Ok(throw.value),
+            LoxError::RuntimeError(error) => {
+                let class = self.error_class.clone().expect("the prelude defines Error");
+                let mut instance = LoxInstance::new(class);
+                let field = |name: &str| {
+                    Token::new(TokenType::IDENTIFIER, name.into(), None, error.token.line)
+                };
+                let trace = error
+                    .trace
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|frame| Some(Object::string(frame)))
+                    .collect();
+                instance.set(&field("message"), Some(Object::string(error.message)))?;
+                instance.set(&field("kind"), Some(Object::string(error.kind.to_string())))?;
+                instance.set(&field("line"), Some(Object::Int(error.token.line as i64)))?;
+                instance.set(&field("stackTrace"), Some(LoxList::object(trace)))?;
+                Ok(Some(instance.object()))
+            }
+            error => Err(error),
+        }
+    }
+
+    /// load a module, running it in its own global scope the first time it's imported
+    fn import(&mut self, path: &Token) -> Result<Rc<LoxModule>, LoxError> {
+        let import_error = |message: String| {
+            LoxError::new_runtime_error(path.clone(), ErrorKind::ImportError, message)
+        };
+        let Some(Object::Str(name)) = &path.literal else {
+            return Err(import_error("Module path must be a string.".into()));
+        };
+        let base = self
+            .script
+            .as_deref()
+            .and_then(Path::parent)
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let file = base
+            .join(&**name)
+            .canonicalize()
+            .map_err(|_| import_error(format!("Could not find module '{}'.", name)))?;
+
+        if let Some(module) = self.modules.get(&file) {
+            return Ok(module.clone());
+        }
+        if let Some(start) = self.loading.iter().position(|loading| *loading == file) {
+            let cycle = self.loading[start..]
+                .iter()
+                .chain([&file])
+                .map(|loading| loading.display().to_string())
+                .collect::<Vec<String>>();
+            return Err(import_error(format!(
+                "Import cycle detected: {}.",
+                cycle.join(" -> ")
+            )));
+        }
+
+        let source = std::fs::read_to_string(&file)
+            .map_err(|e| import_error(format!("Could not read module '{}': {}.", name, e)))?;
+        let previous_script = std::mem::replace(&mut self.script, Some(file.clone()));
+        let stmts = Lox::compile_module(self, source, name);
+        let Some(stmts) = stmts else {
+            self.script = previous_script;
+            return Err(import_error(format!(
+                "Could not compile module '{}'.",
+                name
+            )));
+        };
+
+        let globals = Interpreter::new_globals();
+        if let Some(class) = &self.error_class {
+            globals
+                .borrow_mut()
+                .define("Error".into(), Some(Object::Class(class.clone())));
+        }
+        let previous_globals = std::mem::replace(&mut self.globals, globals.clone());
+        let previous_environment = std::mem::replace(&mut self.environment, globals.clone());
+        let previous_exports = std::mem::take(&mut self.exports);
+        self.loading.push(file.clone());
+        let result = stmts
+            .iter()
+            .try_for_each(|stmt| self.execute(stmt).map(|_| ()));
+        self.loading.pop();
+        let exports = std::mem::replace(&mut self.exports, previous_exports);
+        self.environment = previous_environment;
+        self.globals = previous_globals;
+        self.script = previous_script;
+        result?;
+
+        let module = heap::track(LoxModule::new(name.to_string(), globals, exports));
+        self.modules.insert(file, module.clone());
+        Ok(module)
+    }
+
+    /// record the slot the resolver found for a local variable, `this` or `super` expression
+    pub(crate) fn resolve(&mut self, id: NodeId, slot: Slot) {
+        self.locals.insert(id, slot);
+    }
+
+    /// mark a call in tail position, the resolver only marks the calls that can be deferred
+    pub(crate) fn tail_call(&mut self, id: NodeId) {
+        self.tail_calls.insert(id);
+    }
+
+    /// check `value` against `pattern`, binding names into `environment` along the way
+    fn match_pattern(
+        &mut self,
+        pattern: &Pattern,
+        value: &Option<Object>,
+        environment: &Rc<RefCell<Environment>>,
+    ) -> Result<bool, LoxError> {
+        match pattern {
+            Pattern::Wildcard => Ok(true),
+            Pattern::Literal(literal) => Ok(self.is_equal(literal, value)),
+            Pattern::Value(expected) => {
+                let expected = self.evaluate_in(expected, environment.clone())?;
+                Ok(self.is_equal(&expected, value))
+            }
+            Pattern::Binding(name) => {
+                environment
+                    .borrow_mut()
+                    .define(name.lexeme.clone(), value.clone());
+                Ok(true)
+            }
+            Pattern::Or(alternatives) => {
+                for alternative in alternatives {
+                    if self.match_pattern(alternative, value, environment)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Pattern::List { elements, rest, .. } => {
+                let Some(Object::List(list)) = value else {
+                    return Ok(false);
+                };
+                let items = list.borrow().elements.clone();
+                if items.len() < elements.len() || rest.is_none() && items.len() != elements.len() {
+                    return Ok(false);
+                }
+                for (element, item) in elements.iter().zip(&items) {
+                    if !self.match_pattern(element, item, environment)? {
+                        return Ok(false);
+                    }
+                }
+                if let Some(rest) = rest {
+                    let rest_items = items[elements.len()..].to_vec();
+                    environment
+                        .borrow_mut()
+                        .define(rest.lexeme.clone(), Some(LoxList::object(rest_items)));
+                }
+                Ok(true)
+            }
+            Pattern::Class {
+                name,
+                constructor,
+                fields,
+            } => {
+                let values = match (self.evaluate_in(constructor, environment.clone())?, value) {
+                    (Some(Object::Class(class)), Some(Object::Instance(instance))) => {
+                        if !instance.borrow().klass.inherits(&class) {
+                            return Ok(false);
+                        }
+                        let params = symbol::INIT
+                            .with(|init| class.find_method(init))
+                            .map(|init| init.declaration.params.clone())
+                            .unwrap_or_default();
+                        if fields.len() > params.len() {
+                            return Err(self.too_many_fields(name, fields.len(), params.len()));
+                        }
+                        let instance = instance.borrow();
+                        params
+                            .iter()
+                            .take(fields.len())
+                            .map(|param| instance.field(&param.lexeme))
+                            .collect()
+                    }
+                    (Some(Object::Class(_)), _) => return Ok(false),
+                    (Some(Object::Function(function)), value) => {
+                        let LoxCallable::NativeFunction(
+                            native_function::NativeFunction::VariantConstructor(constructor),
+                        ) = *function
+                        else {
+                            return Err(self.not_a_pattern(name));
+                        };
+                        match value {
+                            Some(Object::Variant(variant))
+                                if Rc::ptr_eq(&variant.lox_enum, &constructor.lox_enum)
+                                    && variant.index == constructor.index =>
+                            {
+                                variant.values.clone()
+                            }
+                            _ => return Ok(false),
+                        }
+                    }
+                    _ => return Err(self.not_a_pattern(name)),
+                };
+                if fields.len() > values.len() {
+                    return Err(self.too_many_fields(name, fields.len(), values.len()));
+                }
+                for (field, field_value) in fields.iter().zip(&values) {
+                    if !self.match_pattern(field, field_value, environment)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+        }
+    }
+
+    fn not_a_pattern(&self, name: &Token) -> LoxError {
+        LoxError::new_runtime_error(
+            name.clone(),
+            ErrorKind::TypeError,
+            format!("'{}' is not a class or an enum variant.", name.lexeme),
+        )
+    }
+
+    fn too_many_fields(&self, name: &Token, fields: usize, count: usize) -> LoxError {
+        LoxError::new_runtime_error(
+            name.clone(),
+            ErrorKind::TypeError,
+            format!(
+                "Pattern has {} fields but '{}' has {}.",
+                fields, name.lexeme, count
+            ),
+        )
+    }
+
+    /// locals are assigned by slot, names the resolver didn't find in a scope are globals
+    fn assign_variable(
+        &mut self,
+        name: &Token,
+        id: NodeId,
+        value: Option<Object>,
+    ) -> Result<(), LoxError> {
+        match self.locals.get(&id).copied() {
+            Some(slot) => self.environment.borrow_mut().assign_at(slot, value),
+            None => self.globals.borrow_mut().assign(name, value)?,
+        }
+        Ok(())
+    }
+
+    fn lookup_variable(&mut self, name: &Token, id: NodeId) -> Result<Option<Object>, LoxError> {
+        match self.locals.get(&id).copied() {
+            Some(slot) => Ok(self.environment.borrow().get_at(slot)),
+            None => self.globals.borrow().get(name),
         }
     }
 }
 
 impl expr::Visitor for Interpreter {
-    fn visit_literal_expr(&self, expr: Literal) -> Result<Option<Object>, LoxError> {
-        Ok(expr.value)
+    fn visit_literal_expr(&self, expr: &Literal) -> Result<Option<Object>, LoxError> {
+        Ok(expr.value.clone())
     }
 
-    fn visit_grouping_expr(&mut self, expr: Grouping) -> Result<Option<Object>, LoxError> {
+    fn visit_grouping_expr(&mut self, expr: &Grouping) -> Result<Option<Object>, LoxError> {
         return self.evaluate(&expr.expression);
     }
 
-    fn visit_unary_expr(&mut self, expr: Unary) -> Result<Option<Object>, LoxError> {
+    fn visit_unary_expr(&mut self, expr: &Unary) -> Result<Option<Object>, LoxError> {
         let right = self.evaluate(&expr.right)?;
+        if expr.operator.r#type == TokenType::MINUS {
+            if let Some(value) = self.call_operator(&right, &symbol::NEG, vec![], &expr.operator)? {
+                return Ok(value);
+            }
+        }
         match (expr.operator.r#type, right) {
-            (TokenType::MINUS, Some(Object::Number(v))) => Ok(Some(Object::Number(-v))),
+            (TokenType::MINUS, Some(v)) if number::is_number(&v) => {
+                number::negate(&expr.operator, &v, self.overflow)
+                    .expect("numeric operand")
+                    .map(Some)
+            }
+            (TokenType::TILDE, Some(v)) => number::invert(&expr.operator, &v).map(Some),
             (TokenType::BANG, v) => Ok(Some(Object::Boolean(self.is_truthy(&v)))),
             _ => Ok(None), // Unreachable.
         }
     }
 
-    fn visit_binary_expr(&mut self, expr: Binary) -> Result<Option<Object>, LoxError> {
+    fn visit_binary_expr(&mut self, expr: &Binary) -> Result<Option<Object>, LoxError> {
         let left = self.evaluate(&expr.left)?;
         let right = self.evaluate(&expr.right)?;
 
-        match (expr.operator.r#type, left, right) {
-            (TokenType::SLASH, Some(Object::Number(left)), Some(Object::Number(0f64))) => Err(
-                LoxError::new_parse_error(expr.operator, "Arithmetic Error: / by zero".into()),
-            ),
-            (TokenType::SLASH, Some(Object::Number(left)), Some(Object::Number(right))) => {
-                Ok(Some(Object::Number(left / right)))
-            }
-            (TokenType::SLASH, _, _) => Err(LoxError::new_parse_error(
-                expr.operator,
-                "Operands must be numbers.".into(),
-            )),
-
-            (TokenType::STAR, Some(Object::Number(left)), Some(Object::Number(right))) => {
-                Ok(Some(Object::Number(left * right)))
+        let method = match expr.operator.r#type {
+            TokenType::PLUS => Some(&symbol::ADD),
+            TokenType::MINUS => Some(&symbol::SUB),
+            TokenType::STAR => Some(&symbol::MUL),
+            TokenType::SLASH => Some(&symbol::DIV),
+            TokenType::LESS => Some(&symbol::LT),
+            TokenType::LESS_EQUAL => Some(&symbol::LE),
+            TokenType::GREATER => Some(&symbol::GT),
+            TokenType::GREATER_EQUAL => Some(&symbol::GE),
+            TokenType::EQUAL_EQUAL | TokenType::BANG_EQUAL => Some(&symbol::EQ),
+            _ => None,
+        };
+        if let Some(method) = method {
+            let result = self.call_operator(&left, method, vec![right.clone()], &expr.operator)?;
+            if let Some(result) = result {
+                if expr.operator.r#type == TokenType::BANG_EQUAL {
+                    return Ok(Some(Object::Boolean(!self.is_truthy(&result))));
+                }
+                return Ok(result);
             }
-            (TokenType::STAR, _, _) => Err(LoxError::new_parse_error(
-                expr.operator,
-                "Operands must be numbers.".into(),
-            )),
+        }
 
-            (TokenType::MINUS, Some(Object::Number(left)), Some(Object::Number(right))) => {
-                Ok(Some(Object::Number(left - right)))
+        match (expr.operator.r#type, left, right) {
+            (
+                TokenType::PLUS
+                | TokenType::MINUS
+                | TokenType::STAR
+                | TokenType::SLASH
+                | TokenType::SLASH_SLASH,
+                Some(left),
+                Some(right),
+            ) if number::is_number(&left) && number::is_number(&right) => {
+                number::arithmetic(&expr.operator, &left, &right, self.overflow)
+                    .expect("numeric operator")
+                    .map(Some)
             }
-            (TokenType::MINUS, _, _) => Err(LoxError::new_parse_error(
-                expr.operator,
+            (
+                TokenType::SLASH | TokenType::SLASH_SLASH | TokenType::STAR | TokenType::MINUS,
+                _,
+                _,
+            ) => Err(LoxError::new_runtime_error(
+                expr.operator.clone(),
+                ErrorKind::TypeError,
                 "Operands must be numbers.".into(),
             )),
 
-            (TokenType::PLUS, Some(Object::Number(left)), Some(Object::Number(right))) => {
-                Ok(Some(Object::Number(left + right)))
-            }
             (TokenType::PLUS, Some(Object::Str(left)), Some(Object::Str(right))) => {
-                Ok(Some(Object::Str(format!("{}{}", left, right))))
+                Interpreter::concatenate(&left, &right).map(Some)
             }
-            (TokenType::PLUS, Some(Object::Number(left)), Some(Object::Str(right))) => {
-                Ok(Some(Object::Str(format!("{}{}", left, right))))
+            (TokenType::PLUS, Some(left), Some(Object::Str(right))) if number::is_number(&left) => {
+                Interpreter::concatenate(&left.to_string(), &right).map(Some)
             }
-            (TokenType::PLUS, Some(Object::Str(left)), Some(Object::Number(right))) => {
-                Ok(Some(Object::Str(format!("{}{}", left, right))))
+            (TokenType::PLUS, Some(Object::Str(left)), Some(right))
+                if number::is_number(&right) =>
+            {
+                Interpreter::concatenate(&left, &right.to_string()).map(Some)
             }
-            (TokenType::PLUS, _, _) => Err(LoxError::new_parse_error(
-                expr.operator,
+            (TokenType::PLUS, _, _) => Err(LoxError::new_runtime_error(
+                expr.operator.clone(),
+                ErrorKind::TypeError,
                 "Operands must be two numbers/strings.".into(),
             )),
 
-            (TokenType::GREATER, Some(Object::Number(left)), Some(Object::Number(right))) => {
-                Ok(Some(Object::Boolean(left > right)))
+            (
+                TokenType::GREATER
+                | TokenType::GREATER_EQUAL
+                | TokenType::LESS
+                | TokenType::LESS_EQUAL,
+                Some(left),
+                Some(right),
+            ) if number::is_number(&left) && number::is_number(&right) => {
+                let ordering = number::compare(&left, &right).flatten();
+                let result = match expr.operator.r#type {
+                    TokenType::GREATER => ordering == Some(Ordering::Greater),
+                    TokenType::GREATER_EQUAL => {
+                        matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
+                    }
+                    TokenType::LESS => ordering == Some(Ordering::Less),
+                    _ => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                };
+                Ok(Some(Object::Boolean(result)))
             }
-            (TokenType::GREATER, _, _) => Err(LoxError::new_parse_error(
-                expr.operator,
+            (
+                TokenType::GREATER
+                | TokenType::GREATER_EQUAL
+                | TokenType::LESS
+                | TokenType::LESS_EQUAL,
+                _,
+                _,
+            ) => Err(LoxError::new_runtime_error(
+                expr.operator.clone(),
+                ErrorKind::TypeError,
                 "Operands must be numbers.".into(),
             )),
 
-            (TokenType::GREATER_EQUAL, Some(Object::Number(left)), Some(Object::Number(right))) => {
-                Ok(Some(Object::Boolean(left >= right)))
-            }
-            (TokenType::GREATER_EQUAL, _, _) => Err(LoxError::new_parse_error(
-                expr.operator,
-                "Operands must be numbers.".into(),
+            (
+                TokenType::AMPERSAND
+                | TokenType::PIPE
+                | TokenType::CARET
+                | TokenType::LESS_LESS
+                | TokenType::GREATER_GREATER,
+                Some(left),
+                Some(right),
+            ) => number::bitwise(&expr.operator, &left, &right).map(Some),
+            (
+                TokenType::AMPERSAND
+                | TokenType::PIPE
+                | TokenType::CARET
+                | TokenType::LESS_LESS
+                | TokenType::GREATER_GREATER,
+                _,
+                _,
+            ) => Err(LoxError::new_runtime_error(
+                expr.operator.clone(),
+                ErrorKind::TypeError,
+                "Operands must be integers.".into(),
             )),
 
-            (TokenType::LESS, Some(Object::Number(left)), Some(Object::Number(right))) => {
-                Ok(Some(Object::Boolean(left < right)))
+            (TokenType::DOT_DOT | TokenType::DOT_DOT_EQUAL, Some(left), Some(right))
+                if number::is_number(&left) && number::is_number(&right) =>
+            {
+                let inclusive = expr.operator.r#type == TokenType::DOT_DOT_EQUAL;
+                Ok(Some(Object::Range(LoxRange::new(&left, &right, inclusive))))
             }
-            (TokenType::LESS, _, _) => Err(LoxError::new_parse_error(
-                expr.operator,
-                "Operands must be numbers.".into(),
-            )),
-
-            (TokenType::LESS_EQUAL, Some(Object::Number(left)), Some(Object::Number(right))) => {
-                Ok(Some(Object::Boolean(left <= right)))
+            (TokenType::DOT_DOT | TokenType::DOT_DOT_EQUAL, _, _) => {
+                Err(LoxError::new_runtime_error(
+                    expr.operator.clone(),
+                    ErrorKind::TypeError,
+                    "Range bounds must be numbers.".into(),
+                ))
             }
-            (TokenType::LESS_EQUAL, _, _) => Err(LoxError::new_parse_error(
-                expr.operator,
-                "Operands must be numbers.".into(),
-            )),
 
             (TokenType::BANG_EQUAL, a, b) => Ok(Some(Object::Boolean(!self.is_equal(&a, &b)))),
             (TokenType::EQUAL_EQUAL, a, b) => Ok(Some(Object::Boolean(self.is_equal(&a, &b)))),
-            _ => Err(LoxError::new_parse_error(
-                expr.operator,
+            _ => Err(LoxError::new_runtime_error(
+                expr.operator.clone(),
+                ErrorKind::Error,
                 "Unknown error.".into(),
             )), // Unreachable.
         }
@@ -258,29 +1029,70 @@ impl expr::Visitor for Interpreter {
 
     fn visit_variable_expr(
         &mut self,
-        expr: variable::Variable,
+        expr: &variable::Variable,
     ) -> Result<Option<Object>, LoxError> {
-        self.lookup_variable(expr.name.clone(), &Expr::variable(expr.name))
+        self.lookup_variable(&expr.name, expr.id)
     }
 
-    fn visit_assign_expr(&mut self, expr: assign::Assign) -> Result<Option<Object>, LoxError> {
+    fn visit_assign_expr(&mut self, expr: &assign::Assign) -> Result<Option<Object>, LoxError> {
         let value = self.evaluate(&expr.value)?;
-        let distance = expr.distance; // TODO self.locals.get(&expr);
-        match distance {
-            Some(distance) => {
-                self.environment
-                    .borrow_mut()
-                    .assign_at(distance, &expr.name, value.clone())
-            }
-            None => self
-                .globals
-                .borrow_mut()
-                .assign(&expr.name, value.clone())?,
+        self.assign_variable(&expr.name, expr.id, value.clone())?;
+        Ok(value)
+    }
+
+    fn visit_assign_destructure_expr(
+        &mut self,
+        expr: &expr::assign_destructure::AssignDestructure,
+    ) -> Result<Option<Object>, LoxError> {
+        let value = self.evaluate(&expr.value)?;
+        for (variable, part) in expr.target.unpack(value.clone())? {
+            self.assign_variable(&variable.name, variable.id, part)?;
         }
         Ok(value)
     }
 
-    fn visit_logical_expr(&mut self, expr: logical::Logical) -> Result<Option<Object>, LoxError> {
+    fn visit_match_expr(&mut self, expr: &expr::match_::Match) -> Result<Option<Object>, LoxError> {
+        let value = self.evaluate(&expr.subject)?;
+        for arm in &expr.arms {
+            let environment =
+                heap::environment(Environment::new_from_enclosing(self.environment.clone()));
+            if !self.match_pattern(&arm.pattern, &value, &environment)? {
+                continue;
+            }
+            if let Some(guard) = &arm.guard {
+                let guard = self.evaluate_in(guard, environment.clone())?;
+                if !self.is_truthy(&guard) {
+                    continue;
+                }
+            }
+            return self.evaluate_in(&arm.body, environment);
+        }
+        Err(LoxError::new_runtime_error(
+            expr.keyword.clone(),
+            ErrorKind::MatchError,
+            format!("No match arm for {}.", repr(&value)),
+        ))
+    }
+
+    /// a yield in a position the resumable frames don't handle, the resolver reports these before running
+    fn visit_yield_expr(&mut self, expr: &expr::yield_::Yield) -> Result<Option<Object>, LoxError> {
+        Err(LoxError::new_runtime_error(
+            expr.keyword.clone(),
+            ErrorKind::Error,
+            "Can't yield here.".into(),
+        ))
+    }
+
+    /// an await in a position the resumable frames don't handle, the resolver reports these before running
+    fn visit_await_expr(&mut self, expr: &expr::await_::Await) -> Result<Option<Object>, LoxError> {
+        Err(LoxError::new_runtime_error(
+            expr.keyword.clone(),
+            ErrorKind::Error,
+            "Can't await here.".into(),
+        ))
+    }
+
+    fn visit_logical_expr(&mut self, expr: &logical::Logical) -> Result<Option<Object>, LoxError> {
         let left = self.evaluate(&expr.left)?;
         if expr.operator.r#type == TokenType::OR {
             if self.is_truthy(&left) {
@@ -294,149 +1106,305 @@ impl expr::Visitor for Interpreter {
         return self.evaluate(&expr.right);
     }
 
-    fn visit_call_expr(&mut self, expr: Call) -> Result<Option<Object>, LoxError> {
+    fn visit_call_expr(&mut self, expr: &Call) -> Result<Option<Object>, LoxError> {
         let callee = self.evaluate(&expr.callee)?;
-        let mut arguments = vec![];
-        for argument in expr.arguments {
-            arguments.push(self.evaluate(&argument)?);
-        }
-
-        let Some(callee) = callee else {
-            return Err(LoxError::new_parse_error(
-                expr.paren,
-                "Can only call functions and classes.".into(),
-            ));
-        };
-
-        let Object::Function(function) = callee else {
-            return Err(LoxError::new_parse_error(
-                expr.paren,
-                "Can only call functions and classes.".into(),
-            ));
-        };
-
-        if (arguments.len() != function.arity()) {
-            return Err(LoxError::new_parse_error(
-                expr.paren,
-                format!(
-                    "Expected {} arguments but got {}",
-                    function.arity(),
-                    arguments.len()
-                ),
-            ));
-        }
-        function.call(self, arguments)
+        let (arguments, named) = self.arguments(expr)?;
+        self.call_value(callee, arguments, named, &expr.paren)
     }
 
-    fn visit_get_expr(&mut self, expr: get::Get) -> Result<Option<Object>, LoxError> {
+    fn visit_get_expr(&mut self, expr: &get::Get) -> Result<Option<Object>, LoxError> {
         let object = self.evaluate(&expr.object)?;
-        if let Some(Object::Instance(object)) = object {
-            return object.get(expr.name).map(|v| Some(v));
+        let value = match object {
+            Some(Object::Instance(object)) => {
+                let Some(value) = LoxInstance::get(&object, expr.name.clone())? else {
+                    return Ok(None);
+                };
+                value
+            }
+            Some(Object::Class(class)) => LoxClass::get(&class, &expr.name)?,
+            Some(Object::Map(map)) => return LoxMap::property(&map, &expr.name).map(Some),
+            Some(Object::List(list)) => return LoxList::property(&list, &expr.name).map(Some),
+            Some(Object::Module(module)) => return module.get(&expr.name),
+            Some(Object::Enum(r#enum)) => LoxEnum::get(&r#enum, &expr.name)?,
+            Some(Object::Variant(variant)) => return LoxVariant::get(&variant, &expr.name),
+            Some(Object::Generator(generator)) => {
+                return LoxGenerator::property(&generator, &expr.name).map(Some)
+            }
+            Some(Object::Fiber(fiber)) => return LoxFiber::property(&fiber, &expr.name).map(Some),
+            Some(Object::Channel(channel)) => {
+                return LoxChannel::property(&channel, &expr.name).map(Some)
+            }
+            Some(Object::Promise(promise)) => {
+                return LoxPromise::property(&promise, &expr.name).map(Some)
+            }
+            Some(Object::Function(function)) => match function.as_ref() {
+                LoxCallable::NativeFunction(native_function::NativeFunction::Fiber(function)) => {
+                    return function.property(&expr.name).map(Some)
+                }
+                LoxCallable::NativeFunction(native_function::NativeFunction::Promise(function)) => {
+                    return function.property(&expr.name).map(Some)
+                }
+                _ => {
+                    return Err(LoxError::new_runtime_error(
+                        expr.name.clone(),
+                        ErrorKind::TypeError,
+                        "Only instances have properties.".into(),
+                    ))
+                }
+            },
+            _ => {
+                return Err(LoxError::new_runtime_error(
+                    expr.name.clone(),
+                    ErrorKind::TypeError,
+                    "Only instances have properties.".into(),
+                ))
+            }
+        };
+        // getters run on access instead of returning the bound method
+        if let Object::Function(function) = &value {
+            if let LoxCallable::LoxFunction(getter) = function.as_ref() {
+                if getter.is_getter() {
+                    return getter.call(self, vec![]);
+                }
+            }
         }
-        Err(LoxError::new_parse_error(
-            expr.name,
-            "Only instances have properties.".into(),
-        ))
+        Ok(Some(value))
     }
 
-    fn visit_set_expr(&mut self, expr: Set) -> Result<Option<Object>, LoxError> {
+    fn visit_set_expr(&mut self, expr: &Set) -> Result<Option<Object>, LoxError> {
         let object = self.evaluate(&expr.object)?;
 
-        let Some(Object::Instance(mut object)) = object else {
-            return Err(LoxError::new_parse_error(
-                expr.name,
-                "Only instances have fields.".into(),
+        let Some(Object::Instance(object)) = object else {
+            let message = match object {
+                Some(Object::Class(_)) => "Can't assign to a class constant.",
+                _ => "Only instances have fields.",
+            };
+            return Err(LoxError::new_runtime_error(
+                expr.name.clone(),
+                ErrorKind::TypeError,
+                message.into(),
             ));
         };
 
         let value = self.evaluate(&expr.value)?;
-        if let Some(value) = value.clone() {
-            object.set(&expr.name, value)?;
-        };
+        object.borrow_mut().set(&expr.name, value.clone())?;
         Ok(value)
     }
 
-    fn visit_this_expr(&mut self, expr: This) -> Result<Option<Object>, LoxError> {
-        self.lookup_variable(expr.keyword.clone(), &Expr::this(expr.keyword))
+    fn visit_this_expr(&mut self, expr: &This) -> Result<Option<Object>, LoxError> {
+        self.lookup_variable(&expr.keyword, expr.id)
     }
 
-    fn visit_super_expr(&mut self, expr: expr::super_::Super) -> Result<Option<Object>, LoxError> {
-        let distance = self.locals.get(""); // expr
-        let Some(distance) = distance else {
-            return Err(LoxError::new_parse_error(
+    fn visit_super_expr(&mut self, expr: &expr::super_::Super) -> Result<Option<Object>, LoxError> {
+        let undefined = || {
+            LoxError::new_runtime_error(
                 expr.method.clone(),
-                "distance not exist".into(),
-            ));
+                ErrorKind::PropertyError,
+                format!("Undefined property '{}'.", expr.method.lexeme),
+            )
         };
-        let distance = *distance;
-        let superclass = self.environment.borrow().get_at(distance, "super"); // (LoxClass)
-        let object = self.environment.borrow().get_at(distance - 1, "this"); // LoxInstance
-
-        let (Some(Some(Object::Class(superclass))), Some(Some(Object::Instance(object)))) =
-            (superclass, object)
-        else {
-            return Err(LoxError::new_parse_error(
-                expr.method.clone(),
-                format!("Undefined property '{}'", expr.method.lexeme),
-            ));
+        let Some(&slot) = self.locals.get(&expr.id) else {
+            return Err(undefined());
         };
-        let method = superclass.find_method(&expr.method.lexeme);
-        match method {
-            Some(method) => Ok(Some(Object::Function(Box::new(LoxCallable::LoxFunction(
-                method.bind(object),
-            ))))),
-            None => Err(LoxError::new_parse_error(
-                expr.method.clone(),
-                format!("Undefined property '{}'", expr.method.lexeme),
+        let superclass = self.environment.borrow().get_at(slot);
+        // `this` is the first name of the scope just inside the one holding `super`
+        let this = Slot {
+            depth: slot.depth - 1,
+            index: 0,
+        };
+        let object = self.environment.borrow().get_at(this);
+        let Some(Object::Class(superclass)) = superclass else {
+            return Err(undefined());
+        };
+        // in a static method `this` is the class, its super methods are the superclass's static ones
+        let method = match &object {
+            Some(Object::Class(_)) => superclass
+                .metaclass()
+                .and_then(|metaclass| metaclass.find_method(&expr.method.lexeme)),
+            _ => superclass.find_method(&expr.method.lexeme),
+        };
+        let (Some(method), Some(object)) = (method, object) else {
+            return Err(undefined());
+        };
+        let method = method.bind(object);
+        if method.is_getter() {
+            return method.call(self, vec![]);
+        }
+        Ok(Some(Object::Function(Box::new(LoxCallable::LoxFunction(
+            method,
+        )))))
+    }
+
+    fn visit_list_expr(&mut self, expr: &expr::list::List) -> Result<Option<Object>, LoxError> {
+        let mut elements = vec![];
+        for element in &expr.elements {
+            elements.push(self.evaluate(element)?);
+        }
+        Ok(Some(LoxList::object(elements)))
+    }
+
+    fn visit_map_expr(&mut self, expr: &expr::map::Map) -> Result<Option<Object>, LoxError> {
+        let mut map = LoxMap::new();
+        for (key, value) in &expr.entries {
+            let key = self.evaluate(key)?;
+            let value = self.evaluate(value)?;
+            let Some(map_key) = MapKey::from_object(&key) else {
+                return Err(LoxError::new_runtime_error(
+                    expr.brace.clone(),
+                    ErrorKind::TypeError,
+                    "Map keys must be strings, numbers, booleans or nil.".into(),
+                ));
+            };
+            map.insert_as(map_key, key, value);
+        }
+        Ok(Some(Object::Map(heap::map(map))))
+    }
+
+    fn visit_index_expr(&mut self, expr: &expr::index::Index) -> Result<Option<Object>, LoxError> {
+        let object = self.evaluate(&expr.object)?;
+        let index = self.evaluate(&expr.index)?;
+        if let Some(value) =
+            self.call_operator(&object, &symbol::INDEX, vec![index.clone()], &expr.bracket)?
+        {
+            return Ok(value);
+        }
+        match object {
+            Some(Object::Map(map)) => {
+                let Some(key) = MapKey::from_object(&index) else {
+                    return Err(LoxError::new_runtime_error(
+                        expr.bracket.clone(),
+                        ErrorKind::TypeError,
+                        "Map keys must be strings, numbers, booleans or nil.".into(),
+                    ));
+                };
+                Ok(map.borrow().get(&key).flatten())
+            }
+            Some(Object::List(list)) => {
+                let list = list.borrow();
+                let Some(position) = list.position(&index) else {
+                    return Err(LoxError::new_runtime_error(
+                        expr.bracket.clone(),
+                        ErrorKind::IndexError,
+                        "List index out of range.".into(),
+                    ));
+                };
+                Ok(list.elements[position].clone())
+            }
+            _ => Err(LoxError::new_runtime_error(
+                expr.bracket.clone(),
+                ErrorKind::TypeError,
+                "Only lists, maps and instances with an '__index__' method can be indexed.".into(),
             )),
         }
     }
+
+    fn visit_set_index_expr(
+        &mut self,
+        expr: &expr::set_index::SetIndex,
+    ) -> Result<Option<Object>, LoxError> {
+        let object = self.evaluate(&expr.object)?;
+        let index = self.evaluate(&expr.index)?;
+        let value = self.evaluate(&expr.value)?;
+        match object {
+            Some(Object::Map(map)) => {
+                let Some(key) = MapKey::from_object(&index) else {
+                    return Err(LoxError::new_runtime_error(
+                        expr.bracket.clone(),
+                        ErrorKind::TypeError,
+                        "Map keys must be strings, numbers, booleans or nil.".into(),
+                    ));
+                };
+                // a new entry allocates nothing the heap sees, so charge it before adding it
+                let bytes = map.borrow().growth(&key, &index, &value);
+                heap::charge(bytes).map_err(LoxError::LimitError)?;
+                map.borrow_mut().insert_as(key, index, value.clone());
+            }
+            Some(Object::List(list)) => {
+                let mut list = list.borrow_mut();
+                let Some(position) = list.position(&index) else {
+                    return Err(LoxError::new_runtime_error(
+                        expr.bracket.clone(),
+                        ErrorKind::IndexError,
+                        "List index out of range.".into(),
+                    ));
+                };
+                list.elements[position] = value.clone();
+            }
+            _ => {
+                return Err(LoxError::new_runtime_error(
+                    expr.bracket.clone(),
+                    ErrorKind::TypeError,
+                    "Only lists and maps can be indexed.".into(),
+                ))
+            }
+        }
+        Ok(value)
+    }
 }
 
 impl stmt::Visitor for Interpreter {
-    fn visit_expression_stmt(&mut self, stmt: expression::Expression) -> Result<(), LoxError> {
+    fn visit_expression_stmt(&mut self, stmt: &expression::Expression) -> Result<(), LoxError> {
         self.evaluate(&stmt.expression).map(|_| ())
     }
 
-    fn visit_print_stmt(&mut self, stmt: Print) -> Result<(), LoxError> {
+    fn visit_print_stmt(&mut self, stmt: &Print) -> Result<(), LoxError> {
         let value = self.evaluate(&stmt.expression)?;
         println!("{}", self.stringify(value));
         Ok(())
     }
 
-    fn visit_var_stmt(&mut self, stmt: stmt::var::Var) -> Result<(), LoxError> {
-        let value = if let Some(initializer) = stmt.initializer {
-            self.evaluate(&initializer)?
+    fn visit_var_stmt(&mut self, stmt: &stmt::var::Var) -> Result<(), LoxError> {
+        let value = if let Some(initializer) = &stmt.initializer {
+            self.evaluate(initializer)?
         } else {
             None
         };
-        self.environment
-            .borrow_mut()
-            .define(stmt.name.lexeme, value);
+        if stmt.is_const {
+            self.environment
+                .borrow_mut()
+                .define_constant(stmt.name.lexeme.clone(), value);
+        } else {
+            self.environment
+                .borrow_mut()
+                .define(stmt.name.lexeme.clone(), value);
+        }
+        Ok(())
+    }
+
+    fn visit_var_destructure_stmt(
+        &mut self,
+        stmt: &stmt::var_destructure::VarDestructure,
+    ) -> Result<(), LoxError> {
+        let value = self.evaluate(&stmt.initializer)?;
+        for (variable, part) in stmt.target.unpack(value)? {
+            self.environment
+                .borrow_mut()
+                .define(variable.name.lexeme.clone(), part);
+        }
         Ok(())
     }
 
-    fn visit_block_stmt(&mut self, stmt: block::Block) -> Result<(), LoxError> {
+    fn visit_block_stmt(&mut self, stmt: &block::Block) -> Result<(), LoxError> {
         self.execute_block(
-            stmt.statements,
+            &stmt.statements,
             Environment::new_from_enclosing(self.environment.clone()),
         )
     }
 
-    fn visit_if_stmt(&mut self, stmt: r#if::If) -> Result<(), LoxError> {
+    fn visit_if_stmt(&mut self, stmt: &r#if::If) -> Result<(), LoxError> {
         let value = self.evaluate(&stmt.condition)?;
         if self.is_truthy(&value) {
             self.execute(&stmt.then_branch)?;
             return Ok(());
         }
-        if let Some(elseBranch) = stmt.else_branch {
-            self.execute(&elseBranch)?;
+        if let Some(elseBranch) = &stmt.else_branch {
+            self.execute(elseBranch)?;
             return Ok(());
         }
         Ok(())
     }
 
-    fn visit_while_stmt(&mut self, stmt: r#while::While) -> Result<(), LoxError> {
+    fn visit_while_stmt(&mut self, stmt: &r#while::While) -> Result<(), LoxError> {
         let mut value = self.evaluate(&stmt.condition)?;
         while self.is_truthy(&value) {
             self.execute(&stmt.body)?; // TODO fix bug, is_truthy always true/false
@@ -445,11 +1413,81 @@ impl stmt::Visitor for Interpreter {
         Ok(())
     }
 
-    fn visit_function_stmt(&mut self, stmt: Function) -> Result<(), LoxError> {
+    fn visit_for_in_stmt(&mut self, stmt: &stmt::for_in::ForIn) -> Result<(), LoxError> {
+        let iterable = self.evaluate(&stmt.iterable)?;
+        let mut iterator = LoxIterator::new(self, iterable, &stmt.name)?;
+        while let Some(value) = iterator.next(self)? {
+            let mut environment = Environment::new_from_enclosing(self.environment.clone());
+            environment.define(stmt.name.lexeme.clone(), value);
+            self.execute_block(std::slice::from_ref(&stmt.body), environment)?;
+        }
+        Ok(())
+    }
+
+    fn visit_throw_stmt(&mut self, stmt: &stmt::throw::Throw) -> Result<(), LoxError> {
+        let value = self.evaluate(&stmt.value)?;
+        Err(LoxError::ThrowError(Throw {
+            keyword: stmt.keyword.clone(),
+            value,
+            trace: None,
+        }))
+    }
+
+    fn visit_try_stmt(&mut self, stmt: &stmt::r#try::Try) -> Result<(), LoxError> {
+        let result = self.execute_block(
+            &stmt.try_block,
+            Environment::new_from_enclosing(self.environment.clone()),
+        );
+        let result = match (result, &stmt.catch) {
+            (Err(error @ (LoxError::RuntimeError(_) | LoxError::ThrowError(_))), Some(catch)) => {
+                let mut environment = Environment::new_from_enclosing(self.environment.clone());
+                environment.define(catch.name.lexeme.clone(), self.caught_value(error)?);
+                self.execute_block(&catch.body, environment)
+            }
+            (result, _) => result,
+        };
+        if let Some(finally_block) = &stmt.finally_block {
+            // an error or return raised by the finally block replaces the pending one
+            self.execute_block(
+                finally_block,
+                Environment::new_from_enclosing(self.environment.clone()),
+            )?;
+        }
+        result
+    }
+
+    fn visit_import_stmt(&mut self, stmt: &stmt::import::Import) -> Result<(), LoxError> {
+        let module = self.import(&stmt.path)?;
+        for name in &stmt.names {
+            let value = module.get(name)?;
+            let mut environment = self.environment.borrow_mut();
+            if module.is_constant(&name.lexeme) {
+                environment.define_constant(name.lexeme.clone(), value);
+            } else {
+                environment.define(name.lexeme.clone(), value);
+            }
+        }
+        if let Some(alias) = &stmt.alias {
+            self.environment
+                .borrow_mut()
+                .define(alias.lexeme.clone(), Some(Object::Module(module)));
+        }
+        Ok(())
+    }
+
+    fn visit_export_stmt(&mut self, stmt: &stmt::export::Export) -> Result<(), LoxError> {
+        self.execute(&stmt.declaration)?;
+        if let Some(name) = stmt.declaration.declared_name() {
+            self.exports.insert(name.lexeme.clone());
+        }
+        Ok(())
+    }
+
+    fn visit_function_stmt(&mut self, stmt: &Rc<Function>) -> Result<(), LoxError> {
         let environment = self.environment.clone();
         let name = stmt.name.lexeme.clone();
         let function = LoxFunction {
-            declaration: stmt,
+            declaration: stmt.clone(),
             closure: environment,
             is_initializer: false,
         };
@@ -460,21 +1498,24 @@ impl stmt::Visitor for Interpreter {
         Ok(())
     }
 
-    fn visit_return_stmt(&mut self, stmt: r#return::Return) -> Result<(), LoxError> {
-        let value = if let Some(value) = stmt.value {
-            self.evaluate(&value)?
-        } else {
-            None
+    fn visit_return_stmt(&mut self, stmt: &r#return::Return) -> Result<(), LoxError> {
+        let value = match &stmt.value {
+            Some(Expr::Call(call)) if self.tail_calls.contains(&call.id) => {
+                return self.return_tail_call(call)
+            }
+            Some(value) => self.evaluate(value)?,
+            None => None,
         };
         Err(LoxError::ReturnError(Return { value }))
     }
 
-    fn visit_class_stmt(&mut self, stmt: Class) -> Result<(), LoxError> {
+    fn visit_class_stmt(&mut self, stmt: &Class) -> Result<(), LoxError> {
         let superclass = if let Some(ref superclass) = stmt.superclass {
-            let object = self.evaluate(&Expr::Variable(superclass.clone()))?;
+            let object = self.lookup_variable(&superclass.name, superclass.id)?;
             let Some(Object::Class(class)) = object else {
-                return Err(LoxError::new_parse_error(
+                return Err(LoxError::new_runtime_error(
                     superclass.name.clone(),
+                    ErrorKind::TypeError,
                     "Superclass must be a class.".into(),
                 ));
             };
@@ -487,16 +1528,24 @@ impl stmt::Visitor for Interpreter {
             .borrow_mut()
             .define(stmt.name.lexeme.clone(), None);
 
+        let mut fields = HashMap::new();
+        for field in &stmt.fields {
+            let value = match &field.initializer {
+                Some(initializer) => self.evaluate(initializer)?,
+                None => None,
+            };
+            fields.insert(field.name.lexeme.clone(), value);
+        }
+
         if stmt.superclass.is_some() {
-            self.environment = Rc::new(RefCell::new(Environment::new_from_enclosing(
-                self.environment.clone(),
-            )));
+            self.environment =
+                heap::environment(Environment::new_from_enclosing(self.environment.clone()));
             self.environment
                 .borrow_mut()
                 .define("super".into(), superclass.clone().map(|v| Object::Class(v)));
         }
 
-        let mut methods = HashMap::new();
+        let mut methods = self.trait_methods(&stmt)?;
         for method in &stmt.methods {
             let function = LoxFunction {
                 declaration: method.clone(),
@@ -506,7 +1555,32 @@ impl stmt::Visitor for Interpreter {
             methods.insert(method.name.lexeme.clone(), function);
         }
 
-        let klass = LoxClass::new(stmt.name.lexeme.clone(), superclass.clone(), methods);
+        let mut class_methods = HashMap::new();
+        for method in &stmt.class_methods {
+            let function = LoxFunction {
+                declaration: method.clone(),
+                closure: self.environment.clone(),
+                is_initializer: false,
+            };
+            class_methods.insert(method.name.lexeme.clone(), function);
+        }
+        let metaclass = LoxClass::new(
+            format!("{} metaclass", stmt.name.lexeme).into(),
+            superclass
+                .as_ref()
+                .and_then(|superclass| superclass.metaclass()),
+            class_methods,
+            None,
+            HashMap::new(),
+        );
+
+        let klass = LoxClass::new(
+            stmt.name.lexeme.clone(),
+            superclass.clone(),
+            methods,
+            Some(heap::class(metaclass)),
+            fields,
+        );
 
         if superclass.is_some() {
             if let Some(enclosing) = self.environment.clone().borrow().enclosing.clone() {
@@ -516,16 +1590,115 @@ impl stmt::Visitor for Interpreter {
 
         self.environment
             .borrow_mut()
-            .assign(&stmt.name, Some(Object::Class(klass)))?;
+            .assign(&stmt.name, Some(Object::Class(heap::class(klass))))?;
+        Ok(())
+    }
+
+    fn visit_enum_stmt(&mut self, stmt: &r#enum::Enum) -> Result<(), LoxError> {
+        let variants = stmt
+            .variants
+            .iter()
+            .map(|(name, fields)| {
+                let fields = fields.iter().map(|f| f.lexeme.clone()).collect();
+                (name.lexeme.clone(), fields)
+            })
+            .collect();
+        let r#enum = LoxEnum::new(stmt.name.lexeme.clone(), variants);
+        self.environment.borrow_mut().define(
+            stmt.name.lexeme.clone(),
+            Some(Object::Enum(Rc::new(r#enum))),
+        );
+        Ok(())
+    }
+
+    fn visit_trait_stmt(&mut self, stmt: &r#trait::Trait) -> Result<(), LoxError> {
+        let mut methods = HashMap::new();
+        for method in &stmt.methods {
+            let function = LoxFunction {
+                declaration: method.clone(),
+                closure: self.environment.clone(),
+                is_initializer: false,
+            };
+            methods.insert(method.name.lexeme.clone(), function);
+        }
+        let r#trait = LoxTrait::new(stmt.name.lexeme.clone(), methods);
+        self.environment.borrow_mut().define(
+            stmt.name.lexeme.clone(),
+            Some(Object::Trait(heap::track(r#trait))),
+        );
         Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::error::{ErrorKind, LoxError, RuntimeError};
     use crate::interpreter::Interpreter;
+    use crate::limits::Limit;
+    use crate::lox::Lox;
     use crate::parser::Parser;
     use crate::scanner::Scanner;
+    use std::time::Duration;
+
+    #[test]
+    fn test_interrupt_from_another_thread() {
+        let mut interpreter = Interpreter::new();
+        let stmts = Lox::compile(&mut interpreter, "while (true) {}".into()).unwrap();
+        let interrupt = interpreter.interrupt_handle();
+        let stopper = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            interrupt.interrupt();
+        });
+        let result = interpreter.run(&stmts);
+        stopper.join().unwrap();
+        assert!(matches!(
+            result,
+            Err(LoxError::LimitError(Limit::Interrupted))
+        ));
+    }
+
+    #[test]
+    fn test_interrupt_before_run() {
+        let mut interpreter = Interpreter::new();
+        let stmts = Lox::compile(
+            &mut interpreter,
+            "var i = 0; while (i < 10000) i = i + 1;".into(),
+        )
+        .unwrap();
+        interpreter.interrupt_handle().interrupt();
+        assert!(matches!(
+            interpreter.run(&stmts),
+            Err(LoxError::LimitError(Limit::Interrupted))
+        ));
+        // the interrupt was used up by the run it stopped
+        assert!(interpreter.run(&stmts).is_ok());
+    }
+
+    #[test]
+    fn test_deep_nesting_raises_recursion_error() {
+        // each call nests far deeper than a plain call, so a small native stack runs
+        // out long before the depth limit does
+        let ifs = "if (true) ".repeat(30);
+        let (open, close) = ("(".repeat(30), ")".repeat(30));
+        let source = format!("fun nest(n) {{ {ifs} return {open}nest(n + 1){close}; }} nest(0);");
+        let result = std::thread::Builder::new()
+            .stack_size(8 << 20)
+            .spawn(move || {
+                let mut interpreter = Interpreter::new();
+                let stmts = Lox::compile(&mut interpreter, source).unwrap();
+                matches!(
+                    interpreter.run(&stmts),
+                    Err(LoxError::RuntimeError(RuntimeError {
+                        kind: ErrorKind::RecursionError,
+                        ..
+                    }))
+                )
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert!(result);
+    }
 
     // #[test]
     // fn test_evaluate_success() {
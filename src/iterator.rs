@@ -0,0 +1,161 @@
+use crate::error::{ErrorKind, LoxError};
+use crate::generator::LoxGenerator;
+use crate::heap::Tracer;
+use crate::instance::LoxInstance;
+use crate::interpreter::Interpreter;
+use crate::list::LoxList;
+use crate::object::Object;
+use crate::range::LoxRange;
+use crate::resumable::Resumed;
+use crate::token::token_type::TokenType;
+use crate::token::Token;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// state of a running for-in loop
+///
+/// strings, lists, maps, ranges and generators are iterated natively, instances go through the iterator
+/// protocol: `iterator()` returns a generator, or an object whose `hasNext()` and `next()` drive the loop
+pub(crate) enum LoxIterator {
+    Range {
+        range: LoxRange,
+        next: f64,
+    },
+    Str {
+        chars: Vec<char>,
+        position: usize,
+    },
+    /// reads the live list, so elements pushed while looping are visited too
+    List {
+        list: Rc<RefCell<LoxList>>,
+        position: usize,
+    },
+    /// iterates over a snapshot of the keys, so removing entries while looping is safe
+    Keys {
+        keys: Vec<Option<Object>>,
+        position: usize,
+    },
+    /// resumes the generator until its body finishes, the returned value is not part of the loop
+    Generator(Rc<LoxGenerator>),
+    Protocol {
+        iterator: Rc<RefCell<LoxInstance>>,
+        /// token of the loop, use to report errors
+        token: Token,
+    },
+}
+
+impl LoxIterator {
+    pub fn new(
+        interpreter: &mut Interpreter,
+        iterable: Option<Object>,
+        token: &Token,
+    ) -> Result<Self, LoxError> {
+        match iterable {
+            Some(Object::Range(range)) => Ok(LoxIterator::Range {
+                range,
+                next: range.start,
+            }),
+            Some(Object::Str(s)) => Ok(LoxIterator::Str {
+                chars: s.chars().collect(),
+                position: 0,
+            }),
+            Some(Object::List(list)) => Ok(LoxIterator::List { list, position: 0 }),
+            Some(Object::Map(map)) => Ok(LoxIterator::Keys {
+                keys: map.borrow().keys(),
+                position: 0,
+            }),
+            Some(Object::Generator(generator)) => Ok(LoxIterator::Generator(generator)),
+            Some(Object::Instance(instance)) => {
+                let method = LoxInstance::get(&instance, Self::method_token(token, "iterator"))?;
+                match Self::call_method(interpreter, method, token)? {
+                    Some(Object::Instance(iterator)) => Ok(LoxIterator::Protocol {
+                        iterator,
+                        token: token.clone(),
+                    }),
+                    Some(Object::Generator(generator)) => Ok(LoxIterator::Generator(generator)),
+                    _ => Err(LoxError::new_runtime_error(token.clone(), ErrorKind::TypeError, "Iterator must be a generator or an instance with 'hasNext()' and 'next()' methods."
+                            .into())),
+                }
+            }
+            _ => Err(LoxError::new_runtime_error(token.clone(), ErrorKind::TypeError, "Can only iterate over strings, lists, maps, ranges, generators and instances with an 'iterator()' method.".into())),
+        }
+    }
+
+    /// produce the next value, `None` when the iteration is over
+    pub fn next(
+        &mut self,
+        interpreter: &mut Interpreter,
+    ) -> Result<Option<Option<Object>>, LoxError> {
+        match self {
+            LoxIterator::Range { range, next } => {
+                if !range.contains(*next) {
+                    return Ok(None);
+                }
+                let value = *next;
+                *next += 1f64;
+                Ok(Some(Some(range.value(value))))
+            }
+            LoxIterator::Str { chars, position } => {
+                let value = chars
+                    .get(*position)
+                    .map(|c| Some(Object::string(c.to_string())));
+                *position += 1;
+                Ok(value)
+            }
+            LoxIterator::List { list, position } => {
+                let value = list.borrow().elements.get(*position).cloned();
+                *position += 1;
+                Ok(value)
+            }
+            LoxIterator::Keys { keys, position } => {
+                let value = keys.get(*position).cloned();
+                *position += 1;
+                Ok(value)
+            }
+            LoxIterator::Generator(generator) => match generator.resume(interpreter, None)? {
+                Resumed::Yielded(value) => Ok(Some(value)),
+                Resumed::Returned(_) => Ok(None),
+                Resumed::Waiting(_) | Resumed::Awaiting(_) => unreachable!("generators don't wait"),
+            },
+            LoxIterator::Protocol { iterator, token } => {
+                let has_next = LoxInstance::get(iterator, Self::method_token(token, "hasNext"))?;
+                let has_next = Self::call_method(interpreter, has_next, token)?;
+                if !interpreter.is_truthy(&has_next) {
+                    return Ok(None);
+                }
+                let next = LoxInstance::get(iterator, Self::method_token(token, "next"))?;
+                Self::call_method(interpreter, next, token).map(Some)
+            }
+        }
+    }
+
+    /// the collection, generator or iterator object being looped over
+    pub fn trace(&self, tracer: &mut Tracer) {
+        match self {
+            LoxIterator::Range { .. } | LoxIterator::Str { .. } => {}
+            LoxIterator::List { list, .. } => tracer.edge(list),
+            LoxIterator::Keys { keys, .. } => keys.iter().for_each(|key| tracer.optional(key)),
+            LoxIterator::Generator(generator) => tracer.edge(generator),
+            LoxIterator::Protocol { iterator, .. } => tracer.edge(iterator),
+        }
+    }
+
+    fn method_token(token: &Token, name: &str) -> Token {
+        Token::new(TokenType::IDENTIFIER, name.into(), None, token.line)
+    }
+
+    fn call_method(
+        interpreter: &mut Interpreter,
+        method: Option<Object>,
+        token: &Token,
+    ) -> Result<Option<Object>, LoxError> {
+        let Some(Object::Function(method)) = method else {
+            return Err(LoxError::new_runtime_error(
+                token.clone(),
+                ErrorKind::TypeError,
+                "Iterator protocol members must be methods.".into(),
+            ));
+        };
+        method.call(interpreter, vec![], token)
+    }
+}
@@ -0,0 +1,161 @@
+use crate::heap;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// steps between two looks at the clock, the interrupt flag and the heap
+const CHECK_INTERVAL: u64 = 1024;
+
+/// budgets of a run, `None` is unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct Limits {
+    /// evaluation steps, every expression evaluated and every statement executed is one step
+    pub fuel: Option<u64>,
+    /// wall-clock time from the start of the run
+    pub timeout: Option<Duration>,
+    /// heap objects alive at once
+    pub objects: Option<usize>,
+    /// approximate bytes owned by the live heap objects
+    pub bytes: Option<usize>,
+}
+
+impl Limits {
+    /// read from the `LOX_FUEL`, `LOX_TIMEOUT` (milliseconds), `LOX_MAX_OBJECTS` and `LOX_MAX_BYTES`
+    /// environment variables
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str) -> Option<T> {
+            std::env::var(name)
+                .ok()
+                .and_then(|value| value.parse().ok())
+        }
+        Limits {
+            fuel: var("LOX_FUEL"),
+            timeout: var("LOX_TIMEOUT").map(Duration::from_millis),
+            objects: var("LOX_MAX_OBJECTS"),
+            bytes: var("LOX_MAX_BYTES"),
+        }
+    }
+}
+
+/// the budget a run went over, scripts can't catch these, the run stops and the host gets the error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Fuel,
+    Deadline,
+    Memory,
+    Interrupted,
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Limit::Fuel => write!(f, "Step limit exceeded."),
+            Limit::Deadline => write!(f, "Time limit exceeded."),
+            Limit::Memory => write!(f, "Memory limit exceeded."),
+            Limit::Interrupted => write!(f, "Interrupted."),
+        }
+    }
+}
+
+/// stops a run from another thread, the run fails with `Limit::Interrupted` within a few steps
+#[derive(Debug, Clone, Default)]
+pub struct InterruptHandle(Arc<AtomicBool>);
+
+impl InterruptHandle {
+    pub fn interrupt(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    fn interrupted(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+/// what is left of the budgets of the current run
+///
+/// counting a step is one comparison, the fuel, the clock, the interrupt flag and the heap are only looked at
+/// every `CHECK_INTERVAL` steps. Once a budget is exceeded every step fails, so finally blocks can't keep running
+#[derive(Debug)]
+pub(crate) struct Budget {
+    limits: Limits,
+    steps: u64,
+    /// the step at which the budgets are checked next
+    next_check: u64,
+    deadline: Option<Instant>,
+    interrupt: InterruptHandle,
+}
+
+impl Budget {
+    pub fn new(limits: Limits) -> Self {
+        Budget {
+            limits,
+            steps: 0,
+            next_check: 0,
+            deadline: None,
+            interrupt: InterruptHandle::default(),
+        }
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+
+    /// begin a run, the fuel and the clock start over, an interrupt sent before the run stops it right away
+    pub fn start(&mut self) {
+        self.steps = 0;
+        self.next_check = 0;
+        self.deadline = self.limits.timeout.map(|timeout| Instant::now() + timeout);
+        heap::limit(self.limits.objects, self.limits.bytes);
+    }
+
+    /// end a run, an interrupt sent during it is used up and doesn't stop the next one
+    pub fn finish(&mut self) {
+        self.interrupt.reset();
+    }
+
+    #[inline]
+    pub fn step(&mut self) -> Result<(), Limit> {
+        self.steps += 1;
+        if self.steps < self.next_check {
+            return Ok(());
+        }
+        self.check()
+    }
+
+    fn check(&mut self) -> Result<(), Limit> {
+        let fuel = self.limits.fuel.unwrap_or(u64::MAX);
+        let exceeded = if self.steps > fuel {
+            Some(Limit::Fuel)
+        } else if self.interrupt.interrupted() {
+            Some(Limit::Interrupted)
+        } else if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            Some(Limit::Deadline)
+        } else if heap::exceeded() {
+            Some(Limit::Memory)
+        } else {
+            None
+        };
+        match exceeded {
+            Some(limit) => {
+                self.next_check = self.steps;
+                Err(limit)
+            }
+            None => {
+                self.next_check = (self.steps + CHECK_INTERVAL).min(fuel.saturating_add(1));
+                Ok(())
+            }
+        }
+    }
+}
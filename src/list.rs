@@ -0,0 +1,141 @@
+use crate::error::{ErrorKind, LoxError};
+use crate::function::native_function::NativeFunction;
+use crate::function::{Arity, LoxCallable};
+use crate::heap::{self, Trace, Tracer};
+use crate::interpreter::Interpreter;
+use crate::number;
+use crate::object::{print_container, repr, Object};
+use crate::token::Token;
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct LoxList {
+    pub elements: Vec<Option<Object>>,
+}
+
+impl LoxList {
+    pub fn new(elements: Vec<Option<Object>>) -> Self {
+        LoxList { elements }
+    }
+
+    /// wrap elements into a new list object
+    pub fn object(elements: Vec<Option<Object>>) -> Object {
+        Object::List(heap::list(LoxList::new(elements)))
+    }
+
+    /// convert a number to a valid position of the list, `None` if it's not an integer in bounds
+    pub fn position(&self, index: &Option<Object>) -> Option<usize> {
+        let index = number::as_i64(index.as_ref()?)?;
+        usize::try_from(index)
+            .ok()
+            .filter(|index| *index < self.elements.len())
+    }
+
+    /// property access on a list, `size` is a field, everything else is a bound method
+    pub fn property(list: &Rc<RefCell<LoxList>>, name: &Token) -> Result<Object, LoxError> {
+        let method = match name.lexeme.as_str() {
+            "size" => return Ok(Object::Int(list.borrow().elements.len() as i64)),
+            "push" => ListMethodKind::Push,
+            "pop" => ListMethodKind::Pop,
+            _ => {
+                return Err(LoxError::new_runtime_error(
+                    name.clone(),
+                    ErrorKind::PropertyError,
+                    format!("Undefined property '{}'.", name.lexeme),
+                ))
+            }
+        };
+        Ok(Object::Function(Box::new(LoxCallable::NativeFunction(
+            NativeFunction::ListMethod(ListMethod {
+                list: list.clone(),
+                kind: method,
+            }),
+        ))))
+    }
+}
+
+impl Display for LoxList {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        print_container(self, "[...]", f, |f| {
+            let elements = self.elements.iter().map(repr).collect::<Vec<String>>();
+            write!(f, "[{}]", elements.join(", "))
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum ListMethodKind {
+    Push,
+    Pop,
+}
+
+/// a list method bound to its receiver, such as `l.push`
+#[derive(Debug, Clone)]
+pub struct ListMethod {
+    list: Rc<RefCell<LoxList>>,
+    kind: ListMethodKind,
+}
+
+impl ListMethod {
+    pub fn trace(&self, tracer: &mut Tracer) {
+        tracer.edge(&self.list);
+    }
+
+    pub fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: Vec<Option<Object>>,
+    ) -> Result<Option<Object>, LoxError> {
+        match self.kind {
+            ListMethodKind::Push => {
+                // growing a list allocates nothing the heap sees, so charge the new slots before adding them
+                let bytes = arguments.len() * std::mem::size_of::<Option<Object>>()
+                    + arguments.iter().flatten().map(heap::payload).sum::<usize>();
+                heap::charge(bytes).map_err(LoxError::LimitError)?;
+                self.list.borrow_mut().elements.extend(arguments);
+                Ok(None)
+            }
+            ListMethodKind::Pop => Ok(self.list.borrow_mut().elements.pop().flatten()),
+        }
+    }
+
+    pub fn arity(&self) -> Arity {
+        match self.kind {
+            ListMethodKind::Push => Arity::at_least(1),
+            ListMethodKind::Pop => Arity::exact(0),
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        let name = match self.kind {
+            ListMethodKind::Push => "push",
+            ListMethodKind::Pop => "pop",
+        };
+        format!("<native fn list.{}>", name)
+    }
+}
+
+impl Trace for LoxList {
+    fn trace(&self, tracer: &mut Tracer) {
+        self.elements
+            .iter()
+            .for_each(|element| tracer.optional(element));
+    }
+
+    fn clear(&mut self) {
+        self.elements.clear();
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.elements.capacity() * std::mem::size_of::<Option<Object>>()
+            + self
+                .elements
+                .iter()
+                .flatten()
+                .map(heap::payload)
+                .sum::<usize>()
+    }
+}
@@ -1,12 +1,16 @@
 use once_cell::sync::Lazy;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 
-use crate::error::{LoxError, ParseError};
+use crate::error::{LoxError, ParseError, RuntimeError, Throw};
 use crate::interpreter::Interpreter;
+use crate::limits::Limits;
 use crate::parser::Parser;
 use crate::resolver::Resolver;
 use crate::scanner::Scanner;
+use crate::stmt::Stmt;
 use crate::token::token_type::TokenType;
 use crate::token::Token;
 
@@ -15,10 +19,19 @@ pub struct Lox;
 static HAD_ERROR: AtomicBool = AtomicBool::new(false);
 static HAD_RUNTIME_ERROR: AtomicBool = AtomicBool::new(false);
 
+thread_local! {
+    /// path of the module being compiled, compile errors in a module name its file
+    static MODULE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
 impl Lox {
     pub(crate) fn run_file(path: &str) -> anyhow::Result<()> {
         let source_code = std::fs::read_to_string(path)?;
-        Lox::run(Interpreter::new(), source_code);
+        let interpreter = Interpreter::new_from_file(Path::new(path));
+        // Ctrl-C stops the script with "LimitError: Interrupted." and where it was, rather than killing the process
+        let interrupt = interpreter.interrupt_handle();
+        ctrlc::set_handler(move || interrupt.interrupt())?;
+        Lox::run(interpreter, source_code);
         if HAD_ERROR.load(Ordering::Relaxed) {
             std::process::exit(65);
         }
@@ -44,30 +57,60 @@ impl Lox {
     }
 
     fn run(mut interpreter: Interpreter, source: String) {
+        interpreter.set_limits(Limits::from_env());
+        if let Some(stmts) = Lox::compile(&mut interpreter, source) {
+            interpreter.interpret(&stmts);
+        }
+    }
+
+    /// scan, parse and resolve source code, `None` if there was a compile error
+    pub(crate) fn compile(interpreter: &mut Interpreter, source: String) -> Option<Vec<Stmt>> {
         let mut scanner = Scanner::new(source);
         let tokens = scanner.scan_tokens();
         let mut parser = Parser::new(tokens);
         let stmts = parser.parse();
 
         if HAD_ERROR.load(Ordering::Relaxed) {
-            return;
+            return None;
         }
 
-        if let Ok(stmts) = stmts {
-            let mut resolver = Resolver::new(interpreter);
+        let stmts = stmts.ok()?;
+        let mut resolver = Resolver::new(interpreter);
 
-            for stmt in &stmts {
-                //  println!("{:?}", stmt);
-            }
+        for stmt in &stmts {
+            //  println!("{:?}", stmt);
+        }
 
-            resolver.resolve(&stmts);
+        resolver.resolve(&stmts);
 
-            // Stop if there was a resolution error.
-            if HAD_ERROR.load(Ordering::Relaxed) {
-                return;
-            }
-            resolver.interpreter.interpret(&stmts);
+        // Stop if there was a resolution error.
+        if HAD_ERROR.load(Ordering::Relaxed) {
+            return None;
         }
+        Some(stmts)
+    }
+
+    /// compile an imported module, its compile errors are reported with its path and don't fail the importing script,
+    /// which gets an `ImportError` it can catch instead
+    pub(crate) fn compile_module(
+        interpreter: &mut Interpreter,
+        source: String,
+        path: &str,
+    ) -> Option<Vec<Stmt>> {
+        let had_error = HAD_ERROR.swap(false, Ordering::Relaxed);
+        let previous = MODULE.with(|module| module.replace(Some(path.to_string())));
+        let stmts = Lox::compile(interpreter, source);
+        MODULE.with(|module| *module.borrow_mut() = previous);
+        HAD_ERROR.store(had_error, Ordering::Relaxed);
+        stmts
+    }
+
+    /// `line 3`, or `line 3 in lib/math.lox` while compiling a module
+    fn location(line: usize) -> String {
+        MODULE.with(|module| match module.borrow().as_deref() {
+            Some(path) => format!("line {} in {}", line, path),
+            None => format!("line {}", line),
+        })
     }
 
     pub(crate) fn error(line: usize, message: &str) {
@@ -75,7 +118,7 @@ impl Lox {
     }
 
     fn report(line: usize, r#where: &str, message: &str) {
-        eprintln!("[line {}] Error {}: {}", line, r#where, message);
+        eprintln!("[{}] Error {}: {}", Lox::location(line), r#where, message);
         HAD_ERROR.store(true, Ordering::Relaxed);
     }
 
@@ -87,10 +130,29 @@ impl Lox {
         }
     }
 
+    /// report a likely mistake, unlike errors it doesn't stop the program from running
+    pub(crate) fn warning(token: &Token, message: &str) {
+        eprintln!(
+            "[{}] Warning at '{}': {}",
+            Lox::location(token.line),
+            token.lexeme,
+            message
+        );
+    }
+
     pub(crate) fn runtime_error(error: LoxError) {
         match error {
             LoxError::ParseError(e) => eprintln!("{}\n[line {} ]", e.message, e.token.line),
             LoxError::ReturnError(e) => eprintln!("ReturnError: {:?}", e),
+            LoxError::TailCall(e) => eprintln!("TailCall: {}", e.function.to_string()),
+            LoxError::LimitError(e) => eprintln!("LimitError: {}", e),
+            LoxError::RuntimeError(RuntimeError { ref trace, .. })
+            | LoxError::ThrowError(Throw { ref trace, .. }) => {
+                eprintln!("{}", error);
+                for frame in trace.iter().flatten() {
+                    eprintln!("{}", frame);
+                }
+            }
         }
 
         HAD_RUNTIME_ERROR.store(true, Ordering::SeqCst);
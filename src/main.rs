@@ -10,30 +10,57 @@ use crate::token::token_type::TokenType;
 use crate::token::Token;
 
 mod class;
+mod destructure;
+mod r#enum;
 mod environment;
 mod error;
 mod expr;
+mod fiber;
 mod function;
+mod generator;
+mod heap;
 mod instance;
 mod interpreter;
+mod iterator;
+mod limits;
+mod list;
 mod lox;
+mod map;
+mod module;
+mod number;
 mod object;
 mod parser;
+mod pattern;
+mod promise;
+mod range;
 mod resolver;
+mod resumable;
 pub(crate) mod scanner;
 pub(crate) mod stmt;
+mod symbol;
 pub(crate) mod token;
+mod r#trait;
 
 fn main() -> anyhow::Result<()> {
+    // the interpreter recurses on the native stack, its thread has room for the calls of the depth limit
+    let stack_size =
+        interpreter::max_depth().max(interpreter::MAX_DEPTH) * interpreter::STACK_PER_CALL;
+    std::thread::Builder::new()
+        .stack_size(stack_size)
+        .spawn(run)?
+        .join()
+        .map_err(|_| anyhow!("the interpreter panicked"))?
+}
+
+fn run() -> anyhow::Result<()> {
     let args = std::env::args();
-    println!("args len: {}, {:?}", args.len(), args);
     if args.len() > 2 {
         println!("Usage: jlox [script]");
         std::process::exit(64);
     } else if args.len() == 2 {
         Lox::run_file(
             args.into_iter()
-                .next()
+                .nth(1)
                 .ok_or(anyhow!("no arg provide"))?
                 .as_str(),
         )?;
@@ -0,0 +1,285 @@
+use crate::error::{ErrorKind, LoxError};
+use crate::function::native_function::NativeFunction;
+use crate::function::{Arity, LoxCallable};
+use crate::heap::{self, Trace, Tracer};
+use crate::interpreter::Interpreter;
+use crate::list::LoxList;
+use crate::number;
+use crate::object::{print_container, repr, Object};
+use crate::token::Token;
+use num_rational::BigRational;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+
+/// hashable form of a map key, two keys are equal exactly when `Object::is_equal` says so
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum MapKey {
+    Nil,
+    Boolean(bool),
+    /// ints, and floats that have the value of an int, so that `1` and `1.0` are the same key
+    Int(i64),
+    /// bits of an infinite float
+    Number(u64),
+    /// bigints, rationals, decimals and finite floats that aren't ints, by value so that `1/2r`, `0.5d` and `0.5`
+    /// are the same key
+    Exact(BigRational),
+    Str(Rc<str>),
+}
+
+impl MapKey {
+    /// only strings, numbers, booleans and nil can be used as keys, NaN is never equal to itself so it is rejected too
+    pub fn from_object(value: &Option<Object>) -> Option<MapKey> {
+        match value {
+            None => Some(MapKey::Nil),
+            Some(Object::Boolean(b)) => Some(MapKey::Boolean(*b)),
+            Some(Object::Number(n)) if n.is_nan() => None,
+            Some(n) if number::is_number(n) => match number::as_i64(n) {
+                Some(n) => Some(MapKey::Int(n)),
+                None => match number::as_exact(n) {
+                    Some(n) => Some(MapKey::Exact(n)),
+                    None => number::as_f64(n).map(|n| match BigRational::from_float(n) {
+                        Some(n) => MapKey::Exact(n),
+                        None => MapKey::Number(n.to_bits()),
+                    }),
+                },
+            },
+            Some(Object::Str(s)) => Some(MapKey::Str(s.clone())),
+            _ => None,
+        }
+    }
+
+    pub fn to_object(&self) -> Option<Object> {
+        match self {
+            MapKey::Nil => None,
+            MapKey::Boolean(b) => Some(Object::Boolean(*b)),
+            MapKey::Int(n) => Some(Object::Int(*n)),
+            MapKey::Number(bits) => Some(Object::Number(f64::from_bits(*bits))),
+            MapKey::Exact(n) if n.is_integer() => Some(Object::BigInt(Rc::new(n.to_integer()))),
+            MapKey::Exact(n) => Some(Object::Rational(Rc::new(n.clone()))),
+            MapKey::Str(s) => Some(Object::Str(s.clone())),
+        }
+    }
+}
+
+/// map keeps entries in insertion order, so iterating and printing a map is deterministic
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct LoxMap {
+    entries: Vec<Entry>,
+    indices: HashMap<MapKey, usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    key: MapKey,
+    /// the key as it was first inserted, a float key is stored exact but is still a float in `keys()`
+    object: Option<Object>,
+    value: Option<Object>,
+}
+
+impl LoxMap {
+    pub fn new() -> Self {
+        LoxMap::default()
+    }
+
+    pub fn get(&self, key: &MapKey) -> Option<Option<Object>> {
+        self.indices
+            .get(key)
+            .map(|index| self.entries[*index].value.clone())
+    }
+
+    pub fn insert(&mut self, key: MapKey, value: Option<Object>) {
+        let object = key.to_object();
+        self.insert_as(key, object, value);
+    }
+
+    /// insert under `key`, a new entry shows `object` as its key
+    pub fn insert_as(&mut self, key: MapKey, object: Option<Object>, value: Option<Object>) {
+        match self.indices.get(&key) {
+            Some(index) => self.entries[*index].value = value,
+            None => {
+                self.indices.insert(key.clone(), self.entries.len());
+                self.entries.push(Entry { key, object, value });
+            }
+        }
+    }
+
+    /// bytes inserting `value` under `key` adds to the map, nothing when it replaces an entry's value
+    pub fn growth(&self, key: &MapKey, object: &Option<Object>, value: &Option<Object>) -> usize {
+        if self.contains(key) {
+            return 0;
+        }
+        let key = match key {
+            MapKey::Str(key) => key.len() / Rc::strong_count(key),
+            _ => 0,
+        };
+        std::mem::size_of::<Entry>()
+            + std::mem::size_of::<(MapKey, usize)>()
+            + key
+            + object.iter().chain(value).map(heap::payload).sum::<usize>()
+    }
+
+    pub fn contains(&self, key: &MapKey) -> bool {
+        self.indices.contains_key(key)
+    }
+
+    pub fn remove(&mut self, key: &MapKey) -> Option<Option<Object>> {
+        let index = self.indices.remove(key)?;
+        let entry = self.entries.remove(index);
+        for Entry { key, .. } in &self.entries[index..] {
+            if let Some(i) = self.indices.get_mut(key) {
+                *i -= 1;
+            }
+        }
+        Some(entry.value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn keys(&self) -> Vec<Option<Object>> {
+        self.entries
+            .iter()
+            .map(|entry| entry.object.clone())
+            .collect()
+    }
+
+    pub fn values(&self) -> Vec<Option<Object>> {
+        self.entries
+            .iter()
+            .map(|entry| entry.value.clone())
+            .collect()
+    }
+
+    /// property access on a map, `size` is a field, everything else is a bound method
+    pub fn property(map: &Rc<RefCell<LoxMap>>, name: &Token) -> Result<Object, LoxError> {
+        let method = match name.lexeme.as_str() {
+            "size" => return Ok(Object::Int(map.borrow().len() as i64)),
+            "keys" => MapMethodKind::Keys,
+            "values" => MapMethodKind::Values,
+            "has" => MapMethodKind::Has,
+            "remove" => MapMethodKind::Remove,
+            _ => {
+                return Err(LoxError::new_runtime_error(
+                    name.clone(),
+                    ErrorKind::PropertyError,
+                    format!("Undefined property '{}'.", name.lexeme),
+                ))
+            }
+        };
+        Ok(Object::Function(Box::new(LoxCallable::NativeFunction(
+            NativeFunction::MapMethod(MapMethod {
+                map: map.clone(),
+                kind: method,
+            }),
+        ))))
+    }
+}
+
+impl Display for LoxMap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        print_container(self, "{...}", f, |f| {
+            let entries = self
+                .entries
+                .iter()
+                .map(|entry| format!("{}: {}", repr(&entry.object), repr(&entry.value)))
+                .collect::<Vec<String>>();
+            write!(f, "{{{}}}", entries.join(", "))
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum MapMethodKind {
+    Keys,
+    Values,
+    Has,
+    Remove,
+}
+
+/// a map method bound to its receiver, such as `m.keys`
+#[derive(Debug, Clone)]
+pub struct MapMethod {
+    map: Rc<RefCell<LoxMap>>,
+    kind: MapMethodKind,
+}
+
+impl MapMethod {
+    pub fn trace(&self, tracer: &mut Tracer) {
+        tracer.edge(&self.map);
+    }
+
+    pub fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: Vec<Option<Object>>,
+    ) -> Result<Option<Object>, LoxError> {
+        match self.kind {
+            MapMethodKind::Keys => Ok(Some(LoxList::object(self.map.borrow().keys()))),
+            MapMethodKind::Values => Ok(Some(LoxList::object(self.map.borrow().values()))),
+            MapMethodKind::Has => {
+                let has = MapKey::from_object(&arguments[0])
+                    .map(|key| self.map.borrow().contains(&key))
+                    .unwrap_or(false);
+                Ok(Some(Object::Boolean(has)))
+            }
+            MapMethodKind::Remove => Ok(MapKey::from_object(&arguments[0])
+                .and_then(|key| self.map.borrow_mut().remove(&key))
+                .flatten()),
+        }
+    }
+
+    pub fn arity(&self) -> Arity {
+        match self.kind {
+            MapMethodKind::Keys | MapMethodKind::Values => Arity::exact(0),
+            MapMethodKind::Has | MapMethodKind::Remove => Arity::exact(1),
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        let name = match self.kind {
+            MapMethodKind::Keys => "keys",
+            MapMethodKind::Values => "values",
+            MapMethodKind::Has => "has",
+            MapMethodKind::Remove => "remove",
+        };
+        format!("<native fn map.{}>", name)
+    }
+}
+
+impl Trace for LoxMap {
+    fn trace(&self, tracer: &mut Tracer) {
+        self.entries
+            .iter()
+            .for_each(|entry| tracer.optional(&entry.value));
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.indices.clear();
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.entries.capacity() * std::mem::size_of::<Entry>()
+            + self.indices.capacity() * std::mem::size_of::<(MapKey, usize)>()
+            + self
+                .entries
+                .iter()
+                .map(|entry| {
+                    let key = match &entry.key {
+                        MapKey::Str(key) => key.len() / Rc::strong_count(key),
+                        _ => 0,
+                    };
+                    key + entry
+                        .object
+                        .iter()
+                        .chain(&entry.value)
+                        .map(heap::payload)
+                        .sum::<usize>()
+                })
+                .sum::<usize>()
+    }
+}
@@ -0,0 +1,72 @@
+use crate::environment::Environment;
+use crate::error::{ErrorKind, LoxError};
+use crate::heap::{Managed, Tracer};
+use crate::object::Object;
+use crate::symbol::Symbol;
+use crate::token::Token;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+
+/// a loaded module, it only exposes the names declared with `export`
+#[derive(Debug)]
+pub(crate) struct LoxModule {
+    /// path of the module file
+    pub name: String,
+    /// module's own global scope, exports are read from it so they stay live
+    globals: Rc<RefCell<Environment>>,
+    exports: HashSet<Symbol>,
+}
+
+impl LoxModule {
+    pub fn new(name: String, globals: Rc<RefCell<Environment>>, exports: HashSet<Symbol>) -> Self {
+        LoxModule {
+            name,
+            globals,
+            exports,
+        }
+    }
+
+    pub fn get(&self, name: &Token) -> Result<Option<Object>, LoxError> {
+        if !self.exports.contains(&name.lexeme) {
+            return Err(LoxError::new_runtime_error(
+                name.clone(),
+                ErrorKind::ImportError,
+                format!("Module '{}' does not export '{}'.", self.name, name.lexeme),
+            ));
+        }
+        self.globals.borrow().get(name)
+    }
+
+    /// whether an export was declared with const, importing it by name binds a constant too
+    pub fn is_constant(&self, name: &Symbol) -> bool {
+        self.globals.borrow().is_constant(name)
+    }
+}
+
+/// modules stay loaded, they are only traced so that their global scope counts as referenced by them
+impl Managed for LoxModule {
+    fn trace(&self, tracer: &mut Tracer) -> bool {
+        tracer.edge(&self.globals);
+        true
+    }
+
+    fn clear(&self) {}
+
+    fn size(&self) -> usize {
+        std::mem::size_of::<Self>() + self.exports.capacity() * std::mem::size_of::<Symbol>()
+    }
+}
+
+impl Display for LoxModule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<module {}>", self.name)
+    }
+}
+
+impl PartialEq for LoxModule {
+    fn eq(&self, other: &Self) -> bool {
+        self.name.eq(&other.name)
+    }
+}
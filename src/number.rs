@@ -0,0 +1,801 @@
+use crate::error::{ErrorKind, LoxError};
+use crate::function::native_function::NativeFunction;
+use crate::function::{Arity, LoxCallable};
+use crate::heap;
+use crate::interpreter::Interpreter;
+use crate::object::Object;
+use crate::token::token_type::TokenType;
+use crate::token::Token;
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{FromPrimitive, One, Pow, Signed, ToPrimitive, Zero};
+use rust_decimal::{Decimal, RoundingStrategy};
+use std::cmp::Ordering;
+use std::rc::Rc;
+use std::str::FromStr;
+
+/// bits a shift may give a bigint, a larger one is an `ArithmeticError` rather than an allocation that can't succeed
+const MAX_SHIFT_BITS: u64 = 1 << 30;
+
+/// what int arithmetic does when a result doesn't fit in 64 bits
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum Overflow {
+    /// raise an `ArithmeticError`, the default
+    Error,
+    /// carry on with the float result
+    Promote,
+    /// carry on with the bigint result
+    BigInt,
+}
+
+impl Overflow {
+    /// selected with the `LOX_INT_OVERFLOW` environment variable, `error`, `promote` or `bigint`
+    pub fn from_env() -> Self {
+        match std::env::var("LOX_INT_OVERFLOW").as_deref() {
+            Ok("promote") => Overflow::Promote,
+            Ok("bigint") => Overflow::BigInt,
+            _ => Overflow::Error,
+        }
+    }
+}
+
+/// a numeric operand, borrowed from the object it was read from
+///
+/// operands of different kinds are coerced to the wider one, int < bigint < decimal < rational,
+/// a float with any of them gives a float, except with a decimal, exact money shouldn't silently become inexact
+#[derive(Debug, Clone, PartialEq)]
+enum Number<'a> {
+    Int(i64),
+    BigInt(&'a BigInt),
+    Decimal(Decimal),
+    Rational(&'a BigRational),
+    Float(f64),
+}
+
+impl<'a> Number<'a> {
+    fn of(object: &'a Object) -> Option<Number<'a>> {
+        match object {
+            Object::Int(n) => Some(Number::Int(*n)),
+            Object::BigInt(n) => Some(Number::BigInt(n)),
+            Object::Decimal(n) => Some(Number::Decimal(*n)),
+            Object::Rational(n) => Some(Number::Rational(n)),
+            Object::Number(n) => Some(Number::Float(*n)),
+            _ => None,
+        }
+    }
+
+    fn float(&self) -> f64 {
+        match self {
+            Number::Int(n) => *n as f64,
+            Number::BigInt(n) => n.to_f64().unwrap_or(f64::NAN),
+            Number::Decimal(n) => n.to_f64().unwrap_or(f64::NAN),
+            Number::Rational(n) => n.to_f64().unwrap_or(f64::NAN),
+            Number::Float(n) => *n,
+        }
+    }
+
+    /// the exact value, `None` for floats
+    fn rational(&self) -> Option<BigRational> {
+        match self {
+            Number::Int(n) => Some(BigRational::from_integer(BigInt::from(*n))),
+            Number::BigInt(n) => Some(BigRational::from_integer((*n).clone())),
+            Number::Decimal(n) => Some(decimal_to_rational(*n)),
+            Number::Rational(n) => Some((*n).clone()),
+            Number::Float(_) => None,
+        }
+    }
+
+    /// ints and bigints only
+    fn bigint(&self) -> Option<BigInt> {
+        match self {
+            Number::Int(n) => Some(BigInt::from(*n)),
+            Number::BigInt(n) => Some((*n).clone()),
+            _ => None,
+        }
+    }
+
+    /// ints, bigints and decimals, `None` for a bigint too large for a decimal
+    fn decimal(&self) -> Option<Decimal> {
+        match self {
+            Number::Int(n) => Some(Decimal::from(*n)),
+            Number::BigInt(n) => n
+                .to_i128()
+                .and_then(|n| Decimal::try_from_i128_with_scale(n, 0).ok()),
+            Number::Decimal(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+fn decimal_to_rational(n: Decimal) -> BigRational {
+    BigRational::new(BigInt::from(n.mantissa()), BigInt::from(10).pow(n.scale()))
+}
+
+/// the closest decimal, it has as many fractional digits as fit, `None` when the integer part doesn't fit
+fn rational_to_decimal(n: &BigRational) -> Option<Decimal> {
+    (0..=Decimal::MAX_SCALE).rev().find_map(|scale| {
+        let scaled = round_rational(&(n * BigInt::from(10).pow(scale)), Rounding::HalfEven);
+        let mantissa = scaled.to_i128()?;
+        Decimal::try_from_i128_with_scale(mantissa, scale)
+            .ok()
+            .map(|n| n.normalize())
+    })
+}
+
+pub(crate) fn is_number(object: &Object) -> bool {
+    Number::of(object).is_some()
+}
+
+/// the value of a number as a float
+pub(crate) fn as_f64(object: &Object) -> Option<f64> {
+    Number::of(object).map(|n| n.float())
+}
+
+/// the value of a number without a fractional part that fits in an int
+pub(crate) fn as_i64(object: &Object) -> Option<i64> {
+    match Number::of(object)? {
+        Number::Int(n) => Some(n),
+        Number::Float(n) => float_to_int(n),
+        n => {
+            let n = n.rational()?;
+            if n.is_integer() {
+                n.to_integer().to_i64()
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// the exact value of a number that isn't an int or a float, used to key maps by value
+pub(crate) fn as_exact(object: &Object) -> Option<BigRational> {
+    match Number::of(object)? {
+        Number::Int(_) | Number::Float(_) => None,
+        n => n.rational(),
+    }
+}
+
+/// 2^63 is exactly representable, every float below it and above -2^63 with no fraction fits
+fn float_to_int(n: f64) -> Option<i64> {
+    if n.fract() == 0f64 && n >= -(2f64.powi(63)) && n < 2f64.powi(63) {
+        Some(n as i64)
+    } else {
+        None
+    }
+}
+
+/// numbers are equal when they hold the same value, whatever their kinds, `1 == 1.0` and `1/2r == 0.5d`
+pub(crate) fn equal(left: &Object, right: &Object) -> Option<bool> {
+    let ordering = compare(left, right)?;
+    Some(ordering == Some(Ordering::Equal))
+}
+
+/// `None` when either side isn't a number, `Some(None)` when they are unordered because of NaN
+pub(crate) fn compare(left: &Object, right: &Object) -> Option<Option<Ordering>> {
+    let ordering = match (Number::of(left)?, Number::of(right)?) {
+        (Number::Int(a), Number::Int(b)) => Some(a.cmp(&b)),
+        (Number::Int(a), Number::Float(b)) => compare_mixed(a, b),
+        (Number::Float(a), Number::Int(b)) => compare_mixed(b, a).map(Ordering::reverse),
+        (Number::Float(a), Number::Float(b)) => a.partial_cmp(&b),
+        (Number::Float(a), b) => compare_float(&b.rational()?, a).map(Ordering::reverse),
+        (a, Number::Float(b)) => compare_float(&a.rational()?, b),
+        (Number::Decimal(a), Number::Decimal(b)) => Some(a.cmp(&b)),
+        (a, b) => Some(a.rational()?.cmp(&b.rational()?)),
+    };
+    Some(ordering)
+}
+
+/// compare without rounding the int, large ints don't all have a float of the same value
+fn compare_mixed(int: i64, float: f64) -> Option<Ordering> {
+    if float.is_nan() {
+        return None;
+    }
+    match float_to_int(float.floor()) {
+        Some(floor) => Some(int.cmp(&floor).then(if float.fract() > 0f64 {
+            Ordering::Less
+        } else {
+            Ordering::Equal
+        })),
+        None if float > 0f64 => Some(Ordering::Less),
+        None => Some(Ordering::Greater),
+    }
+}
+
+/// every finite float is an exact rational
+fn compare_float(exact: &BigRational, float: f64) -> Option<Ordering> {
+    match BigRational::from_float(float) {
+        Some(float) => Some(exact.cmp(&float)),
+        None if float.is_nan() => None,
+        None if float > 0f64 => Some(Ordering::Less),
+        None => Some(Ordering::Greater),
+    }
+}
+
+fn error(operator: &Token, kind: ErrorKind, message: String) -> LoxError {
+    LoxError::new_runtime_error(operator.clone(), kind, message)
+}
+
+/// `+ - * / //` on numbers, `None` when an operand isn't a number
+pub(crate) fn arithmetic(
+    operator: &Token,
+    left: &Object,
+    right: &Object,
+    overflow: Overflow,
+) -> Option<Result<Object, LoxError>> {
+    let (left, right) = (Number::of(left)?, Number::of(right)?);
+    let divides = matches!(operator.r#type, TokenType::SLASH | TokenType::SLASH_SLASH);
+    if divides && right.float() == 0f64 {
+        return Some(Err(error(
+            operator,
+            ErrorKind::ArithmeticError,
+            format!("Arithmetic Error: {} by zero", operator.lexeme),
+        )));
+    }
+    let result = match (&left, &right) {
+        (Number::Int(a), Number::Int(b)) => {
+            let checked = match operator.r#type {
+                TokenType::PLUS => a.checked_add(*b),
+                TokenType::MINUS => a.checked_sub(*b),
+                TokenType::STAR => a.checked_mul(*b),
+                TokenType::SLASH => return Some(Ok(Object::Number(*a as f64 / *b as f64))),
+                TokenType::SLASH_SLASH => floor_div(*a, *b),
+                _ => return None,
+            };
+            match checked {
+                Some(n) => Ok(Object::Int(n)),
+                None => overflowed(operator, *a, *b, overflow),
+            }
+        }
+        (Number::Decimal(_), Number::Float(_)) | (Number::Float(_), Number::Decimal(_)) => {
+            Err(error(
+                operator,
+                ErrorKind::TypeError,
+                "Can't mix decimals and floats, convert one with decimal() or float().".into(),
+            ))
+        }
+        (Number::Float(_), _) | (_, Number::Float(_)) => Ok(Object::Number(float_arithmetic(
+            operator.r#type,
+            left.float(),
+            right.float(),
+        )?)),
+        (Number::Rational(_), _) | (_, Number::Rational(_)) => {
+            let (a, b) = (left.rational()?, right.rational()?);
+            rational_arithmetic(operator.r#type, a, b)
+        }
+        (Number::Decimal(_), _) | (_, Number::Decimal(_)) => {
+            match (left.decimal(), right.decimal()) {
+                (Some(a), Some(b)) => decimal_arithmetic(operator, a, b),
+                _ => Err(error(
+                    operator,
+                    ErrorKind::ArithmeticError,
+                    "Bigint is too large for a decimal.".into(),
+                )),
+            }
+        }
+        _ => bigint_arithmetic(operator.r#type, left.bigint()?, right.bigint()?),
+    };
+    Some(result)
+}
+
+fn float_arithmetic(operator: TokenType, a: f64, b: f64) -> Option<f64> {
+    let result = match operator {
+        TokenType::PLUS => a + b,
+        TokenType::MINUS => a - b,
+        TokenType::STAR => a * b,
+        TokenType::SLASH => a / b,
+        TokenType::SLASH_SLASH => (a / b).floor(),
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// charge a result of about `bits` bits against the memory budget before computing it
+fn charge(bits: u64) -> Result<(), LoxError> {
+    heap::charge((bits / 8) as usize).map_err(LoxError::LimitError)
+}
+
+/// `/` on bigints is exact and gives a rational
+fn bigint_arithmetic(operator: TokenType, a: BigInt, b: BigInt) -> Result<Object, LoxError> {
+    charge(match operator {
+        TokenType::PLUS | TokenType::MINUS => a.bits().max(b.bits()) + 1,
+        TokenType::STAR | TokenType::SLASH => a.bits() + b.bits(),
+        _ => a.bits(),
+    })?;
+    let result = match operator {
+        TokenType::PLUS => a + b,
+        TokenType::MINUS => a - b,
+        TokenType::STAR => a * b,
+        TokenType::SLASH => return Ok(Object::Rational(Rc::new(BigRational::new(a, b)))),
+        _ => a.div_floor(&b),
+    };
+    Ok(Object::BigInt(Rc::new(result)))
+}
+
+fn rational_arithmetic(
+    operator: TokenType,
+    a: BigRational,
+    b: BigRational,
+) -> Result<Object, LoxError> {
+    charge(a.numer().bits() + a.denom().bits() + b.numer().bits() + b.denom().bits())?;
+    let result = match operator {
+        TokenType::PLUS => a + b,
+        TokenType::MINUS => a - b,
+        TokenType::STAR => a * b,
+        TokenType::SLASH => a / b,
+        _ => return Ok(Object::BigInt(Rc::new((a / b).floor().to_integer()))),
+    };
+    Ok(Object::Rational(Rc::new(result)))
+}
+
+/// decimals keep up to 28 fractional digits, `/` rounds the last one half to even
+fn decimal_arithmetic(operator: &Token, a: Decimal, b: Decimal) -> Result<Object, LoxError> {
+    let result = match operator.r#type {
+        TokenType::PLUS => a.checked_add(b),
+        TokenType::MINUS => a.checked_sub(b),
+        TokenType::STAR => a.checked_mul(b),
+        TokenType::SLASH => a.checked_div(b),
+        _ => a.checked_div(b).map(|n| n.floor()),
+    };
+    result.map(Object::Decimal).ok_or_else(|| {
+        error(
+            operator,
+            ErrorKind::ArithmeticError,
+            format!("Decimal overflow in {} {} {}.", a, operator.lexeme, b),
+        )
+    })
+}
+
+/// rounds towards negative infinity, `-7 // 2` is -4
+fn floor_div(a: i64, b: i64) -> Option<i64> {
+    let quotient = a.checked_div(b)?;
+    if a % b != 0 && (a < 0) != (b < 0) {
+        return Some(quotient - 1);
+    }
+    Some(quotient)
+}
+
+fn overflowed(operator: &Token, a: i64, b: i64, overflow: Overflow) -> Result<Object, LoxError> {
+    match overflow {
+        Overflow::Promote => Ok(Object::Number(
+            float_arithmetic(operator.r#type, a as f64, b as f64).unwrap_or(f64::NAN),
+        )),
+        Overflow::BigInt => bigint_arithmetic(operator.r#type, BigInt::from(a), BigInt::from(b)),
+        Overflow::Error => Err(error(
+            operator,
+            ErrorKind::ArithmeticError,
+            format!("Integer overflow in {} {} {}.", a, operator.lexeme, b),
+        )),
+    }
+}
+
+/// unary `-`, `None` when the operand isn't a number
+pub(crate) fn negate(
+    operator: &Token,
+    operand: &Object,
+    overflow: Overflow,
+) -> Option<Result<Object, LoxError>> {
+    let result = match Number::of(operand)? {
+        Number::Int(n) => match (n.checked_neg(), overflow) {
+            (Some(n), _) => Ok(Object::Int(n)),
+            (None, Overflow::Promote) => Ok(Object::Number(-(n as f64))),
+            (None, Overflow::BigInt) => Ok(Object::BigInt(Rc::new(-BigInt::from(n)))),
+            (None, Overflow::Error) => Err(error(
+                operator,
+                ErrorKind::ArithmeticError,
+                format!("Integer overflow in -{}.", n),
+            )),
+        },
+        Number::BigInt(n) => Ok(Object::BigInt(Rc::new(-n))),
+        Number::Decimal(n) => Ok(Object::Decimal(-n)),
+        Number::Rational(n) => Ok(Object::Rational(Rc::new(-n))),
+        Number::Float(n) => Ok(Object::Number(-n)),
+    };
+    Some(result)
+}
+
+/// `& | ^ << >>` on ints and bigints, an int with a bigint gives a bigint
+pub(crate) fn bitwise(operator: &Token, left: &Object, right: &Object) -> Result<Object, LoxError> {
+    let operands = (Number::of(left), Number::of(right));
+    let (Some(a), Some(b)) = (
+        operands.0.as_ref().and_then(Number::bigint),
+        operands.1.as_ref().and_then(Number::bigint),
+    ) else {
+        return Err(error(
+            operator,
+            ErrorKind::TypeError,
+            "Operands must be integers.".into(),
+        ));
+    };
+    let shifts = matches!(
+        operator.r#type,
+        TokenType::LESS_LESS | TokenType::GREATER_GREATER
+    );
+    let ints = matches!((left, right), (Object::Int(_), Object::Int(_)));
+    let amount = match b.to_u32() {
+        Some(b) if b < 64 || !ints => b,
+        _ if shifts && ints => {
+            return Err(error(
+                operator,
+                ErrorKind::ArithmeticError,
+                format!("Shift amount must be between 0 and 63, got {}.", b),
+            ))
+        }
+        _ if shifts => {
+            return Err(error(
+                operator,
+                ErrorKind::ArithmeticError,
+                format!(
+                    "Shift amount must be between 0 and {}, got {}.",
+                    u32::MAX,
+                    b
+                ),
+            ))
+        }
+        _ => 0,
+    };
+    if let (Object::Int(a), Object::Int(b)) = (left, right) {
+        let result = match operator.r#type {
+            TokenType::AMPERSAND => a & b,
+            TokenType::PIPE => a | b,
+            TokenType::CARET => a ^ b,
+            TokenType::LESS_LESS => a << amount,
+            _ => a >> amount,
+        };
+        return Ok(Object::Int(result));
+    }
+    let bits = match operator.r#type {
+        TokenType::LESS_LESS if !a.is_zero() => a.bits() + amount as u64,
+        TokenType::LESS_LESS | TokenType::GREATER_GREATER => a.bits(),
+        _ => a.bits().max(b.bits()),
+    };
+    if bits > MAX_SHIFT_BITS {
+        return Err(error(
+            operator,
+            ErrorKind::ArithmeticError,
+            format!(
+                "Shift result would have {} bits, the most is {}.",
+                bits, MAX_SHIFT_BITS
+            ),
+        ));
+    }
+    charge(bits)?;
+    let result = match operator.r#type {
+        TokenType::AMPERSAND => a & b,
+        TokenType::PIPE => a | b,
+        TokenType::CARET => a ^ b,
+        TokenType::LESS_LESS => a << amount,
+        _ => a >> amount,
+    };
+    Ok(Object::BigInt(Rc::new(result)))
+}
+
+/// unary `~`
+pub(crate) fn invert(operator: &Token, operand: &Object) -> Result<Object, LoxError> {
+    match operand {
+        Object::Int(n) => Ok(Object::Int(!n)),
+        Object::BigInt(n) => Ok(Object::BigInt(Rc::new(!n.as_ref()))),
+        _ => Err(error(
+            operator,
+            ErrorKind::TypeError,
+            "Operand must be an integer.".into(),
+        )),
+    }
+}
+
+/// floats always show a fraction or an exponent so they can't be mistaken for ints, `3.0` and `1e100`
+pub(crate) fn float_to_string(n: f64) -> String {
+    format!("{:?}", n)
+}
+
+/// value of a literal with a suffix, `123n`, `3r`, `1.5r` or `19.99d`, `None` if it's out of range or malformed
+pub(crate) fn parse_literal(digits: &str, suffix: char) -> Option<Object> {
+    match suffix {
+        'n' => BigInt::from_str(digits)
+            .ok()
+            .map(|n| Object::BigInt(Rc::new(n))),
+        'd' => Decimal::from_str_exact(digits).ok().map(Object::Decimal),
+        'r' => parse_rational(digits).map(|n| Object::Rational(Rc::new(n))),
+        _ => None,
+    }
+}
+
+/// such as `3`, `1.25` or `1/3`
+fn parse_rational(text: &str) -> Option<BigRational> {
+    if let Some((numerator, denominator)) = text.split_once('/') {
+        let denominator = BigInt::from_str(denominator.trim()).ok()?;
+        if denominator.is_zero() {
+            return None;
+        }
+        return Some(BigRational::new(
+            BigInt::from_str(numerator.trim()).ok()?,
+            denominator,
+        ));
+    }
+    Decimal::from_str_exact(text.trim())
+        .ok()
+        .map(decimal_to_rational)
+        .or_else(|| {
+            BigInt::from_str(text.trim())
+                .ok()
+                .map(BigRational::from_integer)
+        })
+}
+
+/// how `round()` and `format()` break ties and which way they round
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Rounding {
+    /// to the nearest, ties to the even neighbour, the default
+    HalfEven,
+    /// to the nearest, ties away from zero
+    HalfUp,
+    /// to the nearest, ties towards zero
+    HalfDown,
+    /// towards zero
+    Down,
+    /// away from zero
+    Up,
+    Floor,
+    Ceiling,
+}
+
+impl Rounding {
+    fn parse(name: &str) -> Option<Rounding> {
+        let rounding = match name {
+            "half-even" => Rounding::HalfEven,
+            "half-up" => Rounding::HalfUp,
+            "half-down" => Rounding::HalfDown,
+            "down" => Rounding::Down,
+            "up" => Rounding::Up,
+            "floor" => Rounding::Floor,
+            "ceiling" => Rounding::Ceiling,
+            _ => return None,
+        };
+        Some(rounding)
+    }
+
+    fn strategy(self) -> RoundingStrategy {
+        match self {
+            Rounding::HalfEven => RoundingStrategy::MidpointNearestEven,
+            Rounding::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+            Rounding::HalfDown => RoundingStrategy::MidpointTowardZero,
+            Rounding::Down => RoundingStrategy::ToZero,
+            Rounding::Up => RoundingStrategy::AwayFromZero,
+            Rounding::Floor => RoundingStrategy::ToNegativeInfinity,
+            Rounding::Ceiling => RoundingStrategy::ToPositiveInfinity,
+        }
+    }
+}
+
+/// the integer `n` rounds to
+fn round_rational(n: &BigRational, rounding: Rounding) -> BigInt {
+    let floor = n.floor().to_integer();
+    let ceiling = n.ceil().to_integer();
+    if floor == ceiling {
+        return floor;
+    }
+    let half = BigRational::new(BigInt::one(), BigInt::from(2));
+    let tie = (n - BigRational::from_integer(floor.clone())).cmp(&half);
+    let away = if n.is_positive() { &ceiling } else { &floor };
+    let toward = if n.is_positive() { &floor } else { &ceiling };
+    let rounded = match (rounding, tie) {
+        (Rounding::Floor, _) => &floor,
+        (Rounding::Ceiling, _) => &ceiling,
+        (Rounding::Down, _) => toward,
+        (Rounding::Up, _) => away,
+        (_, Ordering::Less) => &floor,
+        (_, Ordering::Greater) => &ceiling,
+        (Rounding::HalfUp, Ordering::Equal) => away,
+        (Rounding::HalfDown, Ordering::Equal) => toward,
+        (Rounding::HalfEven, Ordering::Equal) if floor.is_even() => &floor,
+        (Rounding::HalfEven, Ordering::Equal) => &ceiling,
+    };
+    rounded.clone()
+}
+
+fn round_float(n: f64, rounding: Rounding) -> f64 {
+    match rounding {
+        Rounding::HalfEven => n.round_ties_even(),
+        Rounding::HalfUp => n.round(),
+        Rounding::HalfDown if n.fract().abs() == 0.5 => n.trunc(),
+        Rounding::HalfDown => n.round(),
+        Rounding::Down => n.trunc(),
+        Rounding::Up if n.fract() == 0f64 => n,
+        Rounding::Up => n.trunc() + n.signum(),
+        Rounding::Floor => n.floor(),
+        Rounding::Ceiling => n.ceil(),
+    }
+}
+
+/// `n` rounded to `places` fractional digits, the result is of the same kind
+fn round(n: Number, places: u32, rounding: Rounding) -> Option<Object> {
+    let scale = BigInt::from(10).pow(places);
+    let result = match n {
+        Number::Int(n) => Object::Int(n),
+        Number::BigInt(n) => Object::BigInt(Rc::new(n.clone())),
+        Number::Decimal(n) => {
+            Object::Decimal(n.round_dp_with_strategy(places, rounding.strategy()))
+        }
+        Number::Rational(n) => Object::Rational(Rc::new(BigRational::new(
+            round_rational(&(n * &scale), rounding),
+            scale,
+        ))),
+        Number::Float(n) => {
+            let scale = 10f64.powi(places as i32);
+            Object::Number(round_float(n * scale, rounding) / scale)
+        }
+    };
+    Some(result)
+}
+
+/// `n` with exactly `places` fractional digits, rounded half to even
+fn format(n: Number, places: u32) -> Option<String> {
+    if let Number::Float(n) = n {
+        return Some(format!("{:.*}", places as usize, n));
+    }
+    let scale = BigInt::from(10).pow(places);
+    let digits = round_rational(&(n.rational()? * scale), Rounding::HalfEven);
+    let sign = if digits.is_negative() { "-" } else { "" };
+    let digits = format!("{:0>width$}", digits.abs(), width = places as usize + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - places as usize);
+    if places == 0 {
+        return Some(format!("{}{}", sign, whole));
+    }
+    Some(format!("{}{}.{}", sign, whole, fraction))
+}
+
+/// conversions between the kinds of numbers, `round()` and `format()`
+#[derive(Debug, Clone)]
+pub enum NumberFunction {
+    /// `bigint(x)`, truncates a fraction, parses a string
+    BigInt,
+    /// `rational(x)` or `rational(numerator, denominator)`, parses a string such as "1/3"
+    Rational,
+    /// `decimal(x)`, parses a string such as "19.99"
+    Decimal,
+    /// `float(x)`
+    Float,
+    /// `round(x, places = 0, mode = "half-even")`
+    Round,
+    /// `format(x, places)`
+    Format,
+}
+
+impl NumberFunction {
+    pub fn object(self) -> Object {
+        Object::Function(Box::new(LoxCallable::NativeFunction(
+            NativeFunction::Number(self),
+        )))
+    }
+
+    pub fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: Vec<Option<Object>>,
+        token: &Token,
+    ) -> Result<Option<Object>, LoxError> {
+        let fail = |kind: ErrorKind, message: &str| Err(error(token, kind, message.into()));
+        let mut arguments = arguments.into_iter();
+        let argument = arguments.next().flatten();
+        let text = match &argument {
+            Some(Object::Str(text)) => Some(&**text),
+            _ => None,
+        };
+        let number = argument.as_ref().and_then(Number::of);
+        let result = match self {
+            NumberFunction::BigInt => match (&number, text) {
+                (Some(Number::Float(n)), _) if !n.is_finite() => None,
+                (Some(Number::Float(n)), _) => BigInt::from_f64(n.trunc()),
+                (Some(n), _) => n.rational().map(|n| n.trunc().to_integer()),
+                (None, Some(text)) => BigInt::from_str(text.trim()).ok(),
+                (None, None) => None,
+            }
+            .map(|n| Object::BigInt(Rc::new(n))),
+            NumberFunction::Rational => {
+                let denominator = arguments.next().flatten();
+                match (&number, text, &denominator) {
+                    (Some(Number::Float(n)), _, None) => BigRational::from_float(*n),
+                    (Some(n), _, None) => n.rational(),
+                    (None, Some(text), None) => parse_rational(text),
+                    (Some(numerator), _, Some(denominator)) => {
+                        match (
+                            numerator.bigint(),
+                            Number::of(denominator).and_then(|n| n.bigint()),
+                        ) {
+                            (_, Some(denominator)) if denominator.is_zero() => {
+                                return fail(
+                                    ErrorKind::ArithmeticError,
+                                    "Arithmetic Error: / by zero",
+                                )
+                            }
+                            (Some(numerator), Some(denominator)) => {
+                                Some(BigRational::new(numerator, denominator))
+                            }
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                }
+                .map(|n| Object::Rational(Rc::new(n)))
+            }
+            NumberFunction::Decimal => match (&number, text) {
+                (Some(Number::Float(n)), _) => Decimal::from_f64(*n),
+                (Some(Number::Rational(n)), _) => rational_to_decimal(n),
+                (Some(n), _) => n.decimal(),
+                (None, Some(text)) => Decimal::from_str_exact(text.trim()).ok(),
+                (None, None) => None,
+            }
+            .map(Object::Decimal),
+            NumberFunction::Float => match (&number, text) {
+                (Some(n), _) => Some(n.float()),
+                (None, Some(text)) => f64::from_str(text.trim()).ok(),
+                (None, None) => None,
+            }
+            .map(Object::Number),
+            NumberFunction::Round | NumberFunction::Format => {
+                let places = match arguments.next().flatten() {
+                    None => Some(0),
+                    Some(places) => as_i64(&places).and_then(|n| u32::try_from(n).ok()),
+                };
+                let Some(places) = places.filter(|places| *places <= Decimal::MAX_SCALE) else {
+                    return fail(
+                        ErrorKind::TypeError,
+                        "Places must be an integer between 0 and 28.",
+                    );
+                };
+                let Some(number) = number else {
+                    return fail(ErrorKind::TypeError, "Can only round numbers.");
+                };
+                if let NumberFunction::Format = self {
+                    return Ok(format(number, places).map(Object::string));
+                }
+                let rounding = match arguments.next().flatten() {
+                    None => Some(Rounding::HalfEven),
+                    Some(Object::Str(mode)) => Rounding::parse(&mode),
+                    Some(_) => None,
+                };
+                let Some(rounding) = rounding else {
+                    return fail(ErrorKind::TypeError, "Rounding mode must be one of \"half-even\", \"half-up\", \"half-down\", \"down\", \"up\", \"floor\" or \"ceiling\".");
+                };
+                round(number, places, rounding)
+            }
+        };
+        match result {
+            Some(result) => Ok(Some(result)),
+            None => fail(
+                ErrorKind::TypeError,
+                &format!(
+                    "Can't convert {} with {}.",
+                    crate::object::repr(&argument),
+                    self.to_string()
+                ),
+            ),
+        }
+    }
+
+    pub fn arity(&self) -> Arity {
+        match self {
+            NumberFunction::Rational | NumberFunction::Format => Arity {
+                min: 1,
+                max: Some(2),
+            },
+            NumberFunction::Round => Arity {
+                min: 1,
+                max: Some(3),
+            },
+            _ => Arity::exact(1),
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        let name = match self {
+            NumberFunction::BigInt => "bigint",
+            NumberFunction::Rational => "rational",
+            NumberFunction::Decimal => "decimal",
+            NumberFunction::Float => "float",
+            NumberFunction::Round => "round",
+            NumberFunction::Format => "format",
+        };
+        format!("<native fn {}>", name)
+    }
+}
@@ -1,24 +1,57 @@
 use crate::class::LoxClass;
+use crate::fiber::{LoxChannel, LoxFiber};
+use crate::generator::LoxGenerator;
 use crate::instance::LoxInstance;
-use std::cmp::Ordering;
+use crate::list::LoxList;
+use crate::map::LoxMap;
+use crate::module::LoxModule;
+use crate::number;
+use crate::promise::LoxPromise;
+use crate::r#enum::{LoxEnum, LoxVariant};
+use crate::r#trait::LoxTrait;
+use crate::range::LoxRange;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use rust_decimal::Decimal;
+use std::cell::RefCell;
 use std::fmt::{Debug, Display, Formatter};
+use std::rc::Rc;
 
 use crate::object::Object::{Boolean, Number, Str};
 
 #[derive(Clone, PartialEq)]
 pub(crate) enum Object {
-    Str(String),
+    Str(Rc<str>),
     Number(f64),
+    /// produced by integer literals, arithmetic on two ints stays an int
+    Int(i64),
+    /// arbitrary precision int, such as `123n`
+    BigInt(Rc<BigInt>),
+    /// exact fraction, such as `1/3r`
+    Rational(Rc<BigRational>),
+    /// fixed-point decimal with up to 28 fractional digits, such as `19.99d`
+    Decimal(Decimal),
     Boolean(bool),
     Void,
     Function(Box<crate::function::LoxCallable>),
-    Class(LoxClass),
-    Instance(LoxInstance),
+    Class(Rc<LoxClass>),
+    Instance(Rc<RefCell<LoxInstance>>),
+    List(Rc<RefCell<LoxList>>),
+    Map(Rc<RefCell<LoxMap>>),
+    Range(LoxRange),
+    Module(Rc<LoxModule>),
+    Trait(Rc<LoxTrait>),
+    Enum(Rc<LoxEnum>),
+    Variant(Rc<LoxVariant>),
+    Generator(Rc<LoxGenerator>),
+    Fiber(Rc<LoxFiber>),
+    Channel(Rc<LoxChannel>),
+    Promise(Rc<LoxPromise>),
 }
 
 impl Object {
     pub fn string(str: String) -> Self {
-        Str(str)
+        Str(str.into())
     }
     pub fn number(n: f64) -> Self {
         Number(n)
@@ -31,7 +64,23 @@ impl Object {
         match (self, other) {
             (Str(a), Str(b)) => a == b,
             (Boolean(a), Boolean(b)) => a == b,
-            (Number(a), Number(b)) => a.partial_cmp(b).unwrap_or(Ordering::Less) == Ordering::Equal,
+            (a, b) if number::is_number(a) && number::is_number(b) => {
+                number::equal(a, b).unwrap_or(false)
+            }
+            (Object::Instance(a), Object::Instance(b)) => Rc::ptr_eq(a, b),
+            (Object::Class(a), Object::Class(b)) => Rc::ptr_eq(a, b),
+            (Object::Function(a), Object::Function(b)) => a == b,
+            (Object::List(a), Object::List(b)) => Rc::ptr_eq(a, b),
+            (Object::Map(a), Object::Map(b)) => Rc::ptr_eq(a, b),
+            (Object::Range(a), Object::Range(b)) => a == b,
+            (Object::Module(a), Object::Module(b)) => Rc::ptr_eq(a, b),
+            (Object::Trait(a), Object::Trait(b)) => Rc::ptr_eq(a, b),
+            (Object::Enum(a), Object::Enum(b)) => Rc::ptr_eq(a, b),
+            (Object::Variant(a), Object::Variant(b)) => a.is_equal(b),
+            (Object::Generator(a), Object::Generator(b)) => Rc::ptr_eq(a, b),
+            (Object::Fiber(a), Object::Fiber(b)) => Rc::ptr_eq(a, b),
+            (Object::Channel(a), Object::Channel(b)) => Rc::ptr_eq(a, b),
+            (Object::Promise(a), Object::Promise(b)) => Rc::ptr_eq(a, b),
             _ => false,
         }
     }
@@ -39,13 +88,60 @@ impl Object {
 
 fn to_string(object: &Object) -> String {
     match object {
-        Str(v) => format!("{}", v),
-        Number(v) => format!("{}", v),
+        Str(v) => v.to_string(),
+        Number(v) => number::float_to_string(*v),
+        Object::Int(v) => format!("{}", v),
+        Object::BigInt(v) => format!("{}", v),
+        Object::Rational(v) => format!("{}", v),
+        Object::Decimal(v) => format!("{}", v),
         Boolean(v) => format!("{}", v),
         Object::Void => "".into(),
         Object::Function(f) => f.to_string(),
         Object::Class(class) => class.to_string(),
-        Object::Instance(instance) => instance.to_string(),
+        Object::Instance(instance) => instance.borrow().to_string(),
+        Object::List(list) => list.borrow().to_string(),
+        Object::Map(map) => map.borrow().to_string(),
+        Object::Range(range) => range.to_string(),
+        Object::Module(module) => module.to_string(),
+        Object::Trait(r#trait) => r#trait.to_string(),
+        Object::Enum(r#enum) => r#enum.to_string(),
+        Object::Variant(variant) => variant.to_string(),
+        Object::Generator(generator) => generator.to_string(),
+        Object::Fiber(fiber) => fiber.to_string(),
+        Object::Channel(channel) => channel.to_string(),
+        Object::Promise(promise) => promise.to_string(),
+    }
+}
+
+thread_local! {
+    /// addresses of the lists and maps being printed, innermost last
+    static PRINTING: RefCell<Vec<usize>> = const { RefCell::new(vec![]) };
+}
+
+/// print a list or a map, or `placeholder` if it is already being printed further out,
+/// so that a list containing itself prints as `[[...]]` instead of recursing forever
+pub(crate) fn print_container<T>(
+    container: &T,
+    placeholder: &str,
+    f: &mut Formatter<'_>,
+    print: impl FnOnce(&mut Formatter<'_>) -> std::fmt::Result,
+) -> std::fmt::Result {
+    let address = container as *const T as usize;
+    if PRINTING.with(|printing| printing.borrow().contains(&address)) {
+        return write!(f, "{}", placeholder);
+    }
+    PRINTING.with(|printing| printing.borrow_mut().push(address));
+    let result = print(f);
+    PRINTING.with(|printing| printing.borrow_mut().pop());
+    result
+}
+
+/// string form of a value inside a container, strings are quoted and nil is spelled out
+pub(crate) fn repr(value: &Option<Object>) -> String {
+    match value {
+        None => "nil".into(),
+        Some(Str(s)) => format!("\"{}\"", s),
+        Some(v) => to_string(v),
     }
 }
 impl Debug for Object {
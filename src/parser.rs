@@ -1,9 +1,13 @@
+use crate::destructure::Target;
 use crate::error::{LoxError, ParseError};
 use crate::expr::set::Set;
 use crate::expr::Expr::Logical;
-use crate::expr::{variable, Expr};
+use crate::expr::{variable, Expr, NodeId};
 use crate::lox::Lox;
 use crate::object::Object;
+use crate::pattern::{Arm, Pattern};
+use crate::stmt::r#try::Catch;
+use crate::stmt::var::Var;
 use crate::stmt::Stmt;
 use crate::stmt::Stmt::Function;
 use crate::token::token_type::TokenType;
@@ -35,14 +39,25 @@ impl Parser {
         self.assignment()
     }
 
-    /// declaration → classDecl | funDecl | varDecl | statement
+    /// declaration → exportDecl | importDecl | classDecl | traitDecl | enumDecl | funDecl | varDecl
+    ///  | constDecl | statement
     fn declaration(&mut self) -> Option<Stmt> {
-        let res = if self.match_(&[CLASS]) {
+        let res = if self.match_(&[EXPORT]) {
+            self.export_declaration()
+        } else if self.match_(&[IMPORT]) {
+            self.import_declaration()
+        } else if self.match_(&[CLASS]) {
             self.class_declaration()
-        } else if self.match_(&[FUN]) {
-            self.function("function")
-        } else if self.match_(&[VAR]) {
+        } else if self.match_(&[TRAIT]) {
+            self.trait_declaration()
+        } else if self.match_(&[ENUM]) {
+            self.enum_declaration()
+        } else if self.match_(&[FUN, ASYNC]) {
+            self.fun_declaration()
+        } else if self.match_(&[VAR, LET]) {
             self.var_declaration()
+        } else if self.match_(&[CONST]) {
+            self.const_declaration()
         } else {
             self.statement()
         };
@@ -55,35 +70,173 @@ impl Parser {
         }
     }
 
-    /// classDecl  → "class" IDENTIFIER ( "<" IDENTIFIER )? "{" function* "}" ;
+    /// exportDecl → "export" ( classDecl | traitDecl | enumDecl | funDecl | varDecl | constDecl ) ;
+    fn export_declaration(&mut self) -> Result<Stmt, LoxError> {
+        let keyword = self.previous().clone();
+        let declaration = if self.match_(&[CLASS]) {
+            self.class_declaration()?
+        } else if self.match_(&[TRAIT]) {
+            self.trait_declaration()?
+        } else if self.match_(&[ENUM]) {
+            self.enum_declaration()?
+        } else if self.match_(&[FUN, ASYNC]) {
+            self.fun_declaration()?
+        } else if self.match_(&[VAR, LET]) {
+            self.var_declaration()?
+        } else if self.match_(&[CONST]) {
+            self.const_declaration()?
+        } else {
+            return Err(self.error(
+                self.peek().clone(),
+                "Expect class, trait, enum, function or variable declaration after 'export'.",
+            ));
+        };
+        Ok(Stmt::export(keyword, declaration))
+    }
+
+    /// importDecl → "import" STRING ( "as" IDENTIFIER )? ";"
+    ///  | "import" "{" IDENTIFIER ( "," IDENTIFIER )* "}" "from" STRING ";" ;
+    ///
+    /// `as` and `from` are contextual, they can still be used as identifiers
+    fn import_declaration(&mut self) -> Result<Stmt, LoxError> {
+        let mut names = vec![];
+        if self.match_(&[LEFT_BRACE]) {
+            loop {
+                names.push(self.consume(IDENTIFIER, "Expect imported name.")?);
+                if !self.match_(&[COMMA]) {
+                    break;
+                }
+            }
+            self.consume(RIGHT_BRACE, "Expect '}' after imported names.")?;
+            self.consume_contextual("from", "Expect 'from' after imported names.")?;
+        }
+        let path = self.consume(STRING, "Expect module path.")?;
+        let alias = if names.is_empty() && self.check_contextual("as") {
+            self.advance();
+            Some(self.consume(IDENTIFIER, "Expect module name after 'as'.")?)
+        } else {
+            None
+        };
+        self.consume(SEMICOLON, "Expect ';' after import.")?;
+        Ok(Stmt::import(path, alias, names))
+    }
+
+    /// classDecl  → "class" IDENTIFIER ( "<" IDENTIFIER )? ( "with" IDENTIFIER ( "," IDENTIFIER )* )? "{" member* "}" ;
+    /// member → "class"? "async"? function | "class"? getter | IDENTIFIER "=" expression ";" ;
+    /// getter → IDENTIFIER block ;
     fn class_declaration(&mut self) -> Result<Stmt, LoxError> {
         let name = self.consume(IDENTIFIER, "Expect class name.")?;
         let superclass = if self.match_(&[LESS]) {
             self.consume(IDENTIFIER, "Expect superclass name.")?;
             Some(variable::Variable {
                 name: self.previous().clone(),
-                distance: None,
+                id: NodeId::next(),
             })
         } else {
             None
         };
+        let mut traits = vec![];
+        if self.check_contextual("with") {
+            self.advance();
+            loop {
+                let name = self.consume(IDENTIFIER, "Expect trait name.")?;
+                traits.push(variable::Variable {
+                    name,
+                    id: NodeId::next(),
+                });
+                if !self.match_(&[COMMA]) {
+                    break;
+                }
+            }
+        }
 
         self.consume(LEFT_BRACE, "Expect '{' before class body.")?;
         let mut methods = vec![];
+        let mut class_methods = vec![];
+        let mut fields = vec![];
         while !self.check(RIGHT_BRACE) && !self.is_at_end() {
-            let function = self.function("method")?;
+            if self.check(IDENTIFIER) && self.check_next(EQUAL) {
+                let name = self.consume(IDENTIFIER, "Expect field name.")?;
+                self.consume(EQUAL, "Expect '=' after field name.")?;
+                let initializer = self.expression()?;
+                self.consume(SEMICOLON, "Expect ';' after field value.")?;
+                fields.push(Var {
+                    name,
+                    initializer: Some(initializer),
+                    is_const: true,
+                });
+                continue;
+            }
+            let is_class_method = self.match_(&[CLASS]);
+            let is_async = self.match_(&[ASYNC]);
+            let function = self.function("method", is_async)?;
             if let Function(function) = function {
-                methods.push(*function);
+                if is_class_method {
+                    class_methods.push(function);
+                } else {
+                    methods.push(function);
+                }
             }
         }
         self.consume(RIGHT_BRACE, "Expect '}' after class body.")?;
-        Ok(Stmt::class(name, superclass, methods))
+        Ok(Stmt::class(
+            name,
+            superclass,
+            traits,
+            methods,
+            class_methods,
+            fields,
+        ))
+    }
+    /// traitDecl → "trait" IDENTIFIER "{" ( "async"? function | getter )* "}" ;
+    fn trait_declaration(&mut self) -> Result<Stmt, LoxError> {
+        let name = self.consume(IDENTIFIER, "Expect trait name.")?;
+        self.consume(LEFT_BRACE, "Expect '{' before trait body.")?;
+        let mut methods = vec![];
+        while !self.check(RIGHT_BRACE) && !self.is_at_end() {
+            let is_async = self.match_(&[ASYNC]);
+            if let Function(function) = self.function("method", is_async)? {
+                methods.push(function);
+            }
+        }
+        self.consume(RIGHT_BRACE, "Expect '}' after trait body.")?;
+        Ok(Stmt::r#trait(name, methods))
     }
+
+    /// enumDecl → "enum" IDENTIFIER "{" ( variant ( "," variant )* ","? )? "}" ;
+    /// variant → IDENTIFIER ( "(" IDENTIFIER ( "," IDENTIFIER )* ")" )? ;
+    fn enum_declaration(&mut self) -> Result<Stmt, LoxError> {
+        let name = self.consume(IDENTIFIER, "Expect enum name.")?;
+        self.consume(LEFT_BRACE, "Expect '{' before enum body.")?;
+        let mut variants = vec![];
+        while !self.check(RIGHT_BRACE) && !self.is_at_end() {
+            let variant = self.consume(IDENTIFIER, "Expect variant name.")?;
+            let mut fields = vec![];
+            if self.match_(&[LEFT_PAREN]) {
+                loop {
+                    fields.push(self.consume(IDENTIFIER, "Expect field name.")?);
+                    if !self.match_(&[COMMA]) {
+                        break;
+                    }
+                }
+                self.consume(RIGHT_PAREN, "Expect ')' after variant fields.")?;
+            }
+            variants.push((variant, fields));
+            if !self.match_(&[COMMA]) {
+                break;
+            }
+        }
+        self.consume(RIGHT_BRACE, "Expect '}' after enum body.")?;
+        Ok(Stmt::r#enum(name, variants))
+    }
+
     /// statement → exprStmt
     ///  | forStmt
     ///  | ifStmt
     ///  | printStmt
     ///  | returnStmt
+    ///  | throwStmt
+    ///  | tryStmt
     ///  | whileStmt
     ///  | block ;
     fn statement(&mut self) -> Result<Stmt, LoxError> {
@@ -99,6 +252,12 @@ impl Parser {
         if self.match_(&[RETURN]) {
             return self.return_statement();
         }
+        if self.match_(&[THROW]) {
+            return self.throw_statement();
+        }
+        if self.match_(&[TRY]) {
+            return self.try_statement();
+        }
         if self.match_(&[WHILE]) {
             return self.while_statement();
         }
@@ -145,6 +304,43 @@ impl Parser {
         };
         Ok(Stmt::r#return(keyword, value))
     }
+    /// throwStmt → "throw" expression ";" ;
+    fn throw_statement(&mut self) -> Result<Stmt, LoxError> {
+        let keyword = self.previous().clone();
+        let value = self.expression()?;
+        self.consume(SEMICOLON, "Expect ';' after thrown value.")?;
+        Ok(Stmt::throw(keyword, value))
+    }
+
+    /// tryStmt → "try" block ( "catch" "(" IDENTIFIER ")" block )? ( "finally" block )? ;
+    fn try_statement(&mut self) -> Result<Stmt, LoxError> {
+        let keyword = self.previous().clone();
+        self.consume(LEFT_BRACE, "Expect '{' after 'try'.")?;
+        let try_block = self.block()?;
+        let catch = if self.match_(&[CATCH]) {
+            self.consume(LEFT_PAREN, "Expect '(' after 'catch'.")?;
+            let name = self.consume(IDENTIFIER, "Expect exception variable name.")?;
+            self.consume(RIGHT_PAREN, "Expect ')' after exception variable.")?;
+            self.consume(LEFT_BRACE, "Expect '{' before catch body.")?;
+            Some(Catch {
+                name,
+                body: self.block()?,
+            })
+        } else {
+            None
+        };
+        let finally_block = if self.match_(&[FINALLY]) {
+            self.consume(LEFT_BRACE, "Expect '{' after 'finally'.")?;
+            Some(self.block()?)
+        } else {
+            None
+        };
+        if catch.is_none() && finally_block.is_none() {
+            return Err(self.error(keyword, "Expect 'catch' or 'finally' after try block."));
+        }
+        Ok(Stmt::r#try(try_block, catch, finally_block))
+    }
+
     /// whileStmt → "while" "(" expression ")" statement ;
     fn while_statement(&mut self) -> Result<Stmt, LoxError> {
         self.consume(LEFT_PAREN, "Expect '(' after 'while'.")?;
@@ -154,8 +350,27 @@ impl Parser {
         Ok(Stmt::r#while(condition, body))
     }
 
-    /// varDecl → "var" IDENTIFIER ( "=" expression )? ";"
+    /// constDecl → "const" IDENTIFIER "=" expression ";"
+    fn const_declaration(&mut self) -> Result<Stmt, LoxError> {
+        let name = self.consume(IDENTIFIER, "Expect constant name.")?;
+        self.consume(
+            EQUAL,
+            "Expect '=' after constant name, a constant needs a value.",
+        )?;
+        let initializer = self.expression()?;
+        self.consume(SEMICOLON, "Expect ';' after constant declaration.")?;
+        Ok(Stmt::constant(name, initializer))
+    }
+
+    /// varDecl → ( "var" | "let" ) ( IDENTIFIER ( "=" expression )? | target "=" expression ) ";"
     fn var_declaration(&mut self) -> Result<Stmt, LoxError> {
+        if self.check(LEFT_BRACKET) || self.check(LEFT_BRACE) {
+            let target = self.destructure_target()?;
+            self.consume(EQUAL, "Expect '=' after destructuring target.")?;
+            let initializer = self.expression()?;
+            self.consume(SEMICOLON, "Expect ';' after variable declaration.")?;
+            return Ok(Stmt::var_destructure(target, initializer));
+        }
         let name = self.consume(IDENTIFIER, "Expect variable name.")?; // var had been match by its caller
         let initializer: Option<Expr> = if self.match_(&[EQUAL]) {
             Some(self.expression()?)
@@ -166,6 +381,47 @@ impl Parser {
         Ok(Stmt::var(name, initializer))
     }
 
+    /// target → "[" ( IDENTIFIER ( "," IDENTIFIER )* ( "," "..." IDENTIFIER )? | "..." IDENTIFIER )? "]"
+    ///  | "{" IDENTIFIER ( "," IDENTIFIER )* "}" ;
+    fn destructure_target(&mut self) -> Result<Target, LoxError> {
+        let variable = |name| variable::Variable {
+            name,
+            id: NodeId::next(),
+        };
+        let mut names = vec![];
+        if self.match_(&[LEFT_BRACKET]) {
+            let bracket = self.previous().clone();
+            let mut rest = None;
+            if !self.check(RIGHT_BRACKET) {
+                loop {
+                    if self.match_(&[DOT_DOT_DOT]) {
+                        rest = Some(variable(self.consume(IDENTIFIER, "Expect rest name.")?));
+                        break;
+                    }
+                    names.push(variable(self.consume(IDENTIFIER, "Expect variable name.")?));
+                    if !self.match_(&[COMMA]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(RIGHT_BRACKET, "Expect ']' after destructuring target.")?;
+            return Ok(Target::List {
+                bracket,
+                names,
+                rest,
+            });
+        }
+        let brace = self.consume(LEFT_BRACE, "Expect '[' or '{' to destructure.")?;
+        loop {
+            names.push(variable(self.consume(IDENTIFIER, "Expect field name.")?));
+            if !self.match_(&[COMMA]) {
+                break;
+            }
+        }
+        self.consume(RIGHT_BRACE, "Expect '}' after destructuring target.")?;
+        Ok(Target::Fields { brace, names })
+    }
+
     /// exprStmt → expression ";"
     fn expression_statement(&mut self) -> Result<Stmt, LoxError> {
         let expr = self.expression()?;
@@ -173,16 +429,63 @@ impl Parser {
         Ok(Stmt::expression(expr))
     }
 
-    fn function(&mut self, kind: &str) -> Result<Stmt, LoxError> {
+    /// funDecl → "async"? "fun" function ;
+    fn fun_declaration(&mut self) -> Result<Stmt, LoxError> {
+        let is_async = self.previous().r#type == ASYNC;
+        if is_async {
+            self.consume(FUN, "Expect 'fun' after 'async'.")?;
+        }
+        self.function("function", is_async)
+    }
+
+    /// function → "*"? IDENTIFIER "(" parameters? ")" block ;
+    /// parameters → ( param ( "," param )* ( "," "..." IDENTIFIER )? ) | "..." IDENTIFIER ;
+    /// param → IDENTIFIER ( "=" expression )? ;
+    fn function(&mut self, kind: &str, is_async: bool) -> Result<Stmt, LoxError> {
+        let is_generator = self.match_(&[STAR]);
         let name = self.consume(IDENTIFIER, &format!("Expect {} name.", kind))?;
+        if is_async && is_generator {
+            return Err(self.error(name, "An async function can't be a generator."));
+        }
+        if kind == "method" && self.match_(&[LEFT_BRACE]) {
+            if is_generator {
+                return Err(self.error(name, "A getter can't be a generator."));
+            }
+            if is_async {
+                return Err(self.error(name, "A getter can't be async."));
+            }
+            let body = self.block()?;
+            return Ok(Stmt::getter(name, body));
+        }
         self.consume(LEFT_PAREN, &format!("Expect '(' after {} name.", kind))?;
         let mut parameters = vec![];
+        let mut defaults = vec![];
+        let mut rest = None;
         if !self.check(RIGHT_PAREN) {
             loop {
                 if (parameters.len() >= 255) {
                     self.error(self.peek().clone(), "Can't have more than 255 parameters.");
                 }
-                parameters.push(self.consume(IDENTIFIER, "Expect parameter name.")?);
+                if self.match_(&[DOT_DOT_DOT]) {
+                    rest = Some(self.consume(IDENTIFIER, "Expect rest parameter name.")?);
+                    if self.check(COMMA) {
+                        return Err(self.error(self.peek().clone(), "Rest parameter must be last."));
+                    }
+                    break;
+                }
+                let parameter = self.consume(IDENTIFIER, "Expect parameter name.")?;
+                let default = if self.match_(&[EQUAL]) {
+                    Some(self.expression()?)
+                } else if let Some(Some(_)) = defaults.last() {
+                    return Err(self.error(
+                        parameter,
+                        "Parameter without a default can't follow one with a default.",
+                    ));
+                } else {
+                    None
+                };
+                parameters.push(parameter);
+                defaults.push(default);
                 if !self.match_(&[COMMA]) {
                     break;
                 }
@@ -194,18 +497,30 @@ impl Parser {
             &format!("Expect '{}'  before {} body.", '{', kind),
         )?;
         let body = self.block()?;
-        Ok(Stmt::function(name, parameters, body))
+        Ok(Stmt::function(
+            name,
+            parameters,
+            defaults,
+            rest,
+            body,
+            is_generator,
+            is_async,
+        ))
     }
 
     /// forStmt → "for" "(" ( varDecl | exprStmt | ";" )
     ///  expression? ";"
-    ///  expression? ")" statement ;
+    ///  expression? ")" statement
+    ///  | "for" "(" IDENTIFIER "in" expression ")" statement ;
     fn for_statement(&mut self) -> Result<Stmt, LoxError> {
         self.consume(LEFT_PAREN, "Expect '(' after 'for'.")?;
+        if self.check(IDENTIFIER) && self.check_next(IN) {
+            return self.for_in_statement();
+        }
 
         let initializer = if self.match_(&[SEMICOLON]) {
             None
-        } else if self.match_(&[VAR]) {
+        } else if self.match_(&[VAR, LET]) {
             Some(self.var_declaration()?)
         } else {
             Some(self.expression_statement()?)
@@ -240,6 +555,15 @@ impl Parser {
         Ok(body)
     }
 
+    fn for_in_statement(&mut self) -> Result<Stmt, LoxError> {
+        let name = self.consume(IDENTIFIER, "Expect loop variable name.")?;
+        self.consume(IN, "Expect 'in' after loop variable.")?;
+        let iterable = self.expression()?;
+        self.consume(RIGHT_PAREN, "Expect ')' after for-in clauses.")?;
+        let body = self.statement()?;
+        Ok(Stmt::for_in(name, iterable, body))
+    }
+
     fn block(&mut self) -> Result<Vec<Stmt>, LoxError> {
         let mut statements = vec![];
         while !self.check(RIGHT_BRACE) && !self.is_at_end() {
@@ -251,8 +575,21 @@ impl Parser {
         Ok(statements)
     }
 
-    /// assignment  → ( call "." )? IDENTIFIER "=" assignment | logic_or ;
+    /// assignment  → ( call "." )? IDENTIFIER "=" assignment
+    ///  | call "[" expression "]" "=" assignment
+    ///  | "[" IDENTIFIER ( "," IDENTIFIER )* "]" "=" assignment
+    ///  | "yield" assignment?
+    ///  | logic_or ;
     fn assignment(&mut self) -> Result<Expr, LoxError> {
+        if self.match_(&[YIELD]) {
+            let keyword = self.previous().clone();
+            let value = if self.check(SEMICOLON) || self.check(RIGHT_PAREN) {
+                None
+            } else {
+                Some(self.assignment()?)
+            };
+            return Ok(Expr::r#yield(keyword, value));
+        }
         let expr = self.or()?;
         if self.match_(&[EQUAL]) {
             let equals = self.previous().clone();
@@ -262,6 +599,27 @@ impl Parser {
                 return Ok(Expr::assign(name, value));
             } else if let Expr::Get(get) = expr {
                 return Ok(Expr::set(get.object, get.name, value));
+            } else if let Expr::Index(index) = expr {
+                return Ok(Expr::set_index(
+                    index.object,
+                    index.bracket,
+                    index.index,
+                    value,
+                ));
+            } else if let Expr::List(list) = expr {
+                let mut names = vec![];
+                for element in list.elements {
+                    let Expr::Variable(variable) = element else {
+                        return Err(self.error(equals, "Invalid assignment target."));
+                    };
+                    names.push(variable);
+                }
+                let target = Target::List {
+                    bracket: equals,
+                    names,
+                    rest: None,
+                };
+                return Ok(Expr::assign_destructure(target, value));
             } else {
                 self.error(equals, "Invalid assignment target."); // TODO thorw?
             }
@@ -303,15 +661,72 @@ impl Parser {
     }
 
     fn comparison(&mut self) -> Result<Expr, LoxError> {
-        let mut expr = self.term();
+        let mut expr = self.bit_or();
         while self.match_(&[GREATER, GREATER_EQUAL, LESS, LESS_EQUAL]) {
             let operator = self.previous().clone();
-            let right = self.term();
+            let right = self.bit_or();
             expr = Ok(Expr::binary(expr?, operator, right?));
         }
         return expr;
     }
 
+    /// bit_or → bit_xor ( "|" bit_xor )* ;
+    ///
+    /// bitwise operators bind tighter than comparisons, `n & 1 == 0` tests the lowest bit
+    fn bit_or(&mut self) -> Result<Expr, LoxError> {
+        let mut expr = self.bit_xor()?;
+        while self.match_(&[PIPE]) {
+            let operator = self.previous().clone();
+            let right = self.bit_xor()?;
+            expr = Expr::binary(expr, operator, right);
+        }
+        Ok(expr)
+    }
+
+    /// bit_xor → bit_and ( "^" bit_and )* ;
+    fn bit_xor(&mut self) -> Result<Expr, LoxError> {
+        let mut expr = self.bit_and()?;
+        while self.match_(&[CARET]) {
+            let operator = self.previous().clone();
+            let right = self.bit_and()?;
+            expr = Expr::binary(expr, operator, right);
+        }
+        Ok(expr)
+    }
+
+    /// bit_and → shift ( "&" shift )* ;
+    fn bit_and(&mut self) -> Result<Expr, LoxError> {
+        let mut expr = self.shift()?;
+        while self.match_(&[AMPERSAND]) {
+            let operator = self.previous().clone();
+            let right = self.shift()?;
+            expr = Expr::binary(expr, operator, right);
+        }
+        Ok(expr)
+    }
+
+    /// shift → range ( ( "<<" | ">>" ) range )* ;
+    fn shift(&mut self) -> Result<Expr, LoxError> {
+        let mut expr = self.range()?;
+        while self.match_(&[LESS_LESS, GREATER_GREATER]) {
+            let operator = self.previous().clone();
+            let right = self.range()?;
+            expr = Expr::binary(expr, operator, right);
+        }
+        Ok(expr)
+    }
+
+    /// range → term ( ( ".." | "..=" ) term )? ;
+    fn range(&mut self) -> Result<Expr, LoxError> {
+        let expr = self.term()?;
+        if self.match_(&[DOT_DOT, DOT_DOT_EQUAL]) {
+            let operator = self.previous().clone();
+            let right = self.term()?;
+            return Ok(Expr::binary(expr, operator, right));
+        }
+        Ok(expr)
+    }
+
     fn term(&mut self) -> Result<Expr, LoxError> {
         let mut expr = self.factor();
         while self.match_(&[MINUS, PLUS]) {
@@ -322,9 +737,10 @@ impl Parser {
         return expr;
     }
 
+    /// factor → unary ( ( "/" | "//" | "*" ) unary )* ;
     fn factor(&mut self) -> Result<Expr, LoxError> {
         let mut expr = self.unary();
-        while self.match_(&[SLASH, STAR]) {
+        while self.match_(&[SLASH, SLASH_SLASH, STAR]) {
             let operator = self.previous().clone();
             let right = self.unary();
             expr = Ok(Expr::binary(expr?, operator, right?));
@@ -332,9 +748,14 @@ impl Parser {
         return expr;
     }
 
-    /// unary → ( "!" | "-" ) unary | call ;
+    /// unary → ( "!" | "-" | "~" ) unary | "await" unary | call ;
     fn unary(&mut self) -> Result<Expr, LoxError> {
-        if self.match_(&[BANG, MINUS]) {
+        if self.match_(&[AWAIT]) {
+            let keyword = self.previous().clone();
+            let value = self.unary()?;
+            return Ok(Expr::r#await(keyword, value));
+        }
+        if self.match_(&[BANG, MINUS, TILDE]) {
             let operator = self.previous().clone(); // TODO
             let right = self.unary()?;
             return Ok(Expr::unary(operator, right));
@@ -342,7 +763,7 @@ impl Parser {
         return self.call();
     }
 
-    /// call → primary ( "(" arguments? ")" | "." IDENTIFIER )* ;
+    /// call → primary ( "(" arguments? ")" | "." IDENTIFIER | "[" expression "]" )* ;
     ///
     /// function or object method
     fn call(&mut self) -> Result<Expr, LoxError> {
@@ -351,8 +772,17 @@ impl Parser {
             if self.match_(&[LEFT_PAREN]) {
                 expr = self.finish_call(expr)?;
             } else if (self.match_(&[DOT])) {
-                let name = self.consume(IDENTIFIER, "Expect property name after '.'.")?;
+                // `yield` is a keyword but still a property name, as in `Fiber.yield`
+                let name = if self.match_(&[YIELD]) {
+                    self.previous().clone()
+                } else {
+                    self.consume(IDENTIFIER, "Expect property name after '.'.")?
+                };
                 expr = Expr::get(expr, name);
+            } else if self.match_(&[LEFT_BRACKET]) {
+                let index = self.expression()?;
+                let bracket = self.consume(RIGHT_BRACKET, "Expect ']' after index.")?;
+                expr = Expr::index(expr, bracket, index);
             } else {
                 break;
             }
@@ -360,14 +790,28 @@ impl Parser {
         Ok(expr)
     }
 
+    /// arguments → argument ( "," argument )* ;
+    /// argument → ( IDENTIFIER ":" )? expression ;
     fn finish_call(&mut self, callee: Expr) -> Result<Expr, LoxError> {
         let mut arguments = vec![];
+        let mut named = vec![];
         if !self.check(RIGHT_PAREN) {
             loop {
-                if arguments.len() >= 255 {
+                if arguments.len() + named.len() >= 255 {
                     self.error(self.peek().clone(), "Can't have more than 255 arguments.");
                 }
-                arguments.push(self.expression()?);
+                if self.check(IDENTIFIER) && self.check_next(COLON) {
+                    let name = self.advance().clone();
+                    self.advance();
+                    named.push((name, self.expression()?));
+                } else if !named.is_empty() {
+                    return Err(self.error(
+                        self.peek().clone(),
+                        "Positional argument can't follow a named argument.",
+                    ));
+                } else {
+                    arguments.push(self.expression()?);
+                }
                 if !self.match_(&[COMMA]) {
                     break;
                 }
@@ -375,19 +819,14 @@ impl Parser {
         }
 
         let paren = self.consume(RIGHT_PAREN, "Expect ')' after arguments.")?;
-        Ok(Expr::call(callee, paren, arguments))
-    }
-
-    /// arguments → expression ( "," expression )* ;
-    fn arguments(&mut self) -> Result<Expr, LoxError> {
-        todo!()
+        Ok(Expr::call(callee, paren, arguments, named))
     }
 
     /// To access a variable, we define a new kind of primary expressio
     ///
     /// primary        → "true" | "false" | "nil" | "this"
     //                | NUMBER | STRING | IDENTIFIER | "(" expression ")"
-    //                | "super" "." IDENTIFIER ;
+    //                | "super" "." IDENTIFIER | list | map | match ;
     fn primary(&mut self) -> Result<Expr, LoxError> {
         if self.match_(&[FALSE]) {
             return Ok(Expr::literal(Some(Object::Boolean(false))));
@@ -414,6 +853,15 @@ impl Parser {
         if self.match_(&[IDENTIFIER]) {
             return Ok(Expr::variable(self.previous().clone()));
         }
+        if self.match_(&[LEFT_BRACKET]) {
+            return self.list();
+        }
+        if self.match_(&[LEFT_BRACE]) {
+            return self.map();
+        }
+        if self.match_(&[MATCH]) {
+            return self.match_expression();
+        }
         if self.match_(&[LEFT_PAREN]) {
             let expr = self.expression()?;
             self.consume(RIGHT_PAREN, "Expect ')' after expression.")?;
@@ -423,6 +871,167 @@ impl Parser {
         }
     }
 
+    /// match → "match" "(" expression ")" "{" ( arm ( "," arm )* ","? )? "}" ;
+    /// arm → pattern ( "if" expression )? "=>" expression ;
+    fn match_expression(&mut self) -> Result<Expr, LoxError> {
+        let keyword = self.previous().clone();
+        self.consume(LEFT_PAREN, "Expect '(' after 'match'.")?;
+        let subject = self.expression()?;
+        self.consume(RIGHT_PAREN, "Expect ')' after match value.")?;
+        self.consume(LEFT_BRACE, "Expect '{' before match arms.")?;
+        let mut arms = vec![];
+        while !self.check(RIGHT_BRACE) && !self.is_at_end() {
+            let token = self.peek().clone();
+            let pattern = self.pattern()?;
+            let guard = if self.match_(&[IF]) {
+                Some(self.expression()?)
+            } else {
+                None
+            };
+            self.consume(EQUAL_GREATER, "Expect '=>' after match pattern.")?;
+            let body = self.expression()?;
+            arms.push(Arm {
+                token,
+                pattern,
+                guard,
+                body,
+            });
+            if !self.match_(&[COMMA]) {
+                break;
+            }
+        }
+        self.consume(RIGHT_BRACE, "Expect '}' after match arms.")?;
+        Ok(Expr::r#match(keyword, subject, arms))
+    }
+
+    /// pattern → alternative ( "|" alternative )* ;
+    /// alternative → "_" | IDENTIFIER | path | path "(" ( pattern ( "," pattern )* )? ")"
+    ///  | "[" ( pattern ( "," pattern )* ( "," "..." IDENTIFIER )? | "..." IDENTIFIER )? "]"
+    ///  | "-"? NUMBER | STRING | "true" | "false" | "nil" ;
+    /// path → IDENTIFIER ( "." IDENTIFIER )* ;
+    fn pattern(&mut self) -> Result<Pattern, LoxError> {
+        let mut alternatives = vec![self.alternative()?];
+        while self.match_(&[PIPE]) {
+            alternatives.push(self.alternative()?);
+        }
+        if alternatives.len() == 1 {
+            return Ok(alternatives.remove(0));
+        }
+        Ok(Pattern::Or(alternatives))
+    }
+
+    fn alternative(&mut self) -> Result<Pattern, LoxError> {
+        if self.match_(&[FALSE]) {
+            return Ok(Pattern::Literal(Some(Object::Boolean(false))));
+        }
+        if self.match_(&[TRUE]) {
+            return Ok(Pattern::Literal(Some(Object::Boolean(true))));
+        }
+        if self.match_(&[NIL]) {
+            return Ok(Pattern::Literal(None));
+        }
+        if self.match_(&[NUMBER, STRING]) {
+            return Ok(Pattern::Literal(self.previous().literal.clone()));
+        }
+        if self.match_(&[MINUS]) {
+            let number = self.consume(NUMBER, "Expect number after '-' in pattern.")?;
+            let value = match number.literal {
+                Some(Object::Number(n)) => Object::Number(-n),
+                Some(Object::Int(n)) => Object::Int(-n),
+                _ => return Err(self.error(number, "Expect number after '-' in pattern.")),
+            };
+            return Ok(Pattern::Literal(Some(value)));
+        }
+        if self.match_(&[LEFT_BRACKET]) {
+            let bracket = self.previous().clone();
+            let mut elements = vec![];
+            let mut rest = None;
+            if !self.check(RIGHT_BRACKET) {
+                loop {
+                    if self.match_(&[DOT_DOT_DOT]) {
+                        rest = Some(self.consume(IDENTIFIER, "Expect rest name.")?);
+                        break;
+                    }
+                    elements.push(self.pattern()?);
+                    if !self.match_(&[COMMA]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(RIGHT_BRACKET, "Expect ']' after list pattern.")?;
+            return Ok(Pattern::List {
+                bracket,
+                elements,
+                rest,
+            });
+        }
+        let mut name = self.consume(IDENTIFIER, "Expect pattern.")?;
+        let mut path = Expr::variable(name.clone());
+        let is_path = self.check(DOT);
+        while self.match_(&[DOT]) {
+            name = self.consume(IDENTIFIER, "Expect property name after '.'.")?;
+            path = Expr::get(path, name.clone());
+        }
+        if self.match_(&[LEFT_PAREN]) {
+            let mut fields = vec![];
+            if !self.check(RIGHT_PAREN) {
+                loop {
+                    fields.push(self.pattern()?);
+                    if !self.match_(&[COMMA]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(RIGHT_PAREN, "Expect ')' after class pattern.")?;
+            return Ok(Pattern::Class {
+                name,
+                constructor: path,
+                fields,
+            });
+        }
+        if is_path {
+            return Ok(Pattern::Value(path));
+        }
+        if name.lexeme == "_" {
+            return Ok(Pattern::Wildcard);
+        }
+        Ok(Pattern::Binding(name))
+    }
+
+    /// list → "[" ( expression ( "," expression )* )? "]" ;
+    fn list(&mut self) -> Result<Expr, LoxError> {
+        let mut elements = vec![];
+        if !self.check(RIGHT_BRACKET) {
+            loop {
+                elements.push(self.expression()?);
+                if !self.match_(&[COMMA]) {
+                    break;
+                }
+            }
+        }
+        self.consume(RIGHT_BRACKET, "Expect ']' after list elements.")?;
+        Ok(Expr::list(elements))
+    }
+
+    /// map → "{" ( expression ":" expression ( "," expression ":" expression )* )? "}" ;
+    fn map(&mut self) -> Result<Expr, LoxError> {
+        let brace = self.previous().clone();
+        let mut entries = vec![];
+        if !self.check(RIGHT_BRACE) {
+            loop {
+                let key = self.expression()?;
+                self.consume(COLON, "Expect ':' after map key.")?;
+                let value = self.expression()?;
+                entries.push((key, value));
+                if !self.match_(&[COMMA]) {
+                    break;
+                }
+            }
+        }
+        self.consume(RIGHT_BRACE, "Expect '}' after map entries.")?;
+        Ok(Expr::map(brace, entries))
+    }
+
     ///
     fn consume(&mut self, token_type: TokenType, msg: &str) -> Result<Token, LoxError> {
         if self.check(token_type) {
@@ -445,7 +1054,8 @@ impl Parser {
                 return;
             }
             match self.peek().r#type {
-                CLASS | FUN | VAR | FOR | IF | WHILE | PRINT | RETURN => return,
+                CLASS | TRAIT | ENUM | ASYNC | FUN | VAR | LET | CONST | FOR | IF | WHILE
+                | PRINT | YIELD | RETURN | THROW | TRY | IMPORT | EXPORT => return,
                 _ => {}
             }
             self.advance();
@@ -459,6 +1069,26 @@ impl Parser {
         }
         return self.peek().r#type == token_type;
     }
+    /// check whether current token is the identifier `word`
+    fn check_contextual(&self, word: &str) -> bool {
+        self.check(IDENTIFIER) && self.peek().lexeme == word
+    }
+
+    fn consume_contextual(&mut self, word: &str, msg: &str) -> Result<Token, LoxError> {
+        if self.check_contextual(word) {
+            Ok(self.advance().clone())
+        } else {
+            Err(self.error(self.peek().clone(), msg))
+        }
+    }
+
+    /// check whether the token after current one is token_type
+    fn check_next(&self, token_type: TokenType) -> bool {
+        self.tokens
+            .get(self.current + 1)
+            .map(|token| token.r#type == token_type)
+            .unwrap_or(false)
+    }
     fn peek(&self) -> &Token {
         return self.tokens.get(self.current).unwrap(); // TODO
     }
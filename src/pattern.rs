@@ -0,0 +1,69 @@
+use crate::expr::Expr;
+use crate::object::Object;
+use crate::token::Token;
+
+/// pattern of a match arm
+#[derive(Debug, Clone)]
+pub(crate) enum Pattern {
+    /// `_` matches anything without binding it
+    Wildcard,
+    /// such as 0, "x", true or nil, compared with ==
+    Literal(Option<Object>),
+    /// an identifier matches anything and binds it to a new variable
+    Binding(Token),
+    /// such as Point(x, y), matches instances of the class or its subclasses,
+    /// the subpatterns match the fields named by the parameters of `init`, in order.
+    /// Shape.Circle(r) matches an enum variant and its values the same way
+    Class {
+        /// last name of the constructor, used to report errors
+        name: Token,
+        constructor: Expr,
+        fields: Vec<Pattern>,
+    },
+    /// such as Shape.Empty or Math.PI, compared with ==
+    Value(Expr),
+    /// such as [first, ...rest]
+    List {
+        bracket: Token,
+        elements: Vec<Pattern>,
+        rest: Option<Token>,
+    },
+    /// such as "x" | "y", alternatives can't bind names
+    Or(Vec<Pattern>),
+}
+
+impl Pattern {
+    /// names bound when the pattern matches
+    pub fn bindings(&self) -> Vec<&Token> {
+        match self {
+            Pattern::Wildcard | Pattern::Literal(_) | Pattern::Value(_) => vec![],
+            Pattern::Binding(name) => vec![name],
+            Pattern::Class { fields, .. } => fields.iter().flat_map(Pattern::bindings).collect(),
+            Pattern::List { elements, rest, .. } => elements
+                .iter()
+                .flat_map(Pattern::bindings)
+                .chain(rest)
+                .collect(),
+            Pattern::Or(alternatives) => alternatives.iter().flat_map(Pattern::bindings).collect(),
+        }
+    }
+
+    /// a pattern matching every value
+    pub fn is_irrefutable(&self) -> bool {
+        match self {
+            Pattern::Wildcard | Pattern::Binding(_) => true,
+            Pattern::Or(alternatives) => alternatives.iter().any(Pattern::is_irrefutable),
+            _ => false,
+        }
+    }
+}
+
+/// such as `[x, y] if x > y => x - y`
+#[derive(Debug, Clone)]
+pub(crate) struct Arm {
+    /// first token of the pattern, used to report warnings
+    pub token: Token,
+    pub pattern: Pattern,
+    pub guard: Option<Expr>,
+    pub body: Expr,
+}
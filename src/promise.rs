@@ -0,0 +1,608 @@
+use crate::error::{ErrorKind, LoxError, Throw};
+use crate::fiber::Task;
+use crate::function::native_function::NativeFunction;
+use crate::function::{Arity, LoxCallable};
+use crate::heap::{self, Managed, Tracer};
+use crate::interpreter::Interpreter;
+use crate::iterator::LoxIterator;
+use crate::list::LoxList;
+use crate::object::Object;
+use crate::resumable::{Resumable, Resumed};
+use crate::token::Token;
+use std::cell::{Cell, RefCell};
+use std::fmt::{Debug, Display, Formatter};
+use std::rc::Rc;
+
+/// result of an asynchronous computation, reactions registered with `then` or `await` run once it settles
+pub(crate) struct LoxPromise {
+    state: RefCell<PromiseState>,
+    /// reactions waiting for the promise to settle, they are queued on the event loop in order
+    reactions: RefCell<Vec<Reaction>>,
+    /// whether a reaction has been registered, a rejection nobody handles is reported once the event loop is empty
+    handled: Cell<bool>,
+}
+
+enum PromiseState {
+    Pending,
+    Fulfilled(Option<Object>),
+    /// the error is raised again where the promise is awaited
+    Rejected(Box<LoxError>),
+}
+
+/// what runs once a promise settles
+pub(crate) enum Reaction {
+    /// an async function suspended in an await
+    Await(Rc<LoxAsync>),
+    Then(Box<Then>),
+    /// a promise resolved with another one takes its outcome
+    Adopt(Rc<LoxPromise>),
+    /// an element of `Promise.all`, at its position in the list
+    All(Rc<All>, usize),
+}
+
+/// callbacks of `then`, their result resolves `promise`
+pub(crate) struct Then {
+    on_fulfilled: Option<Object>,
+    on_rejected: Option<Object>,
+    promise: Rc<LoxPromise>,
+    token: Token,
+}
+
+/// state of a `Promise.all`, it is fulfilled when no element remains pending
+pub(crate) struct All {
+    values: RefCell<Vec<Option<Object>>>,
+    remaining: Cell<usize>,
+    promise: Rc<LoxPromise>,
+}
+
+impl LoxPromise {
+    fn new(state: PromiseState) -> Rc<Self> {
+        heap::track(LoxPromise {
+            state: RefCell::new(state),
+            reactions: RefCell::new(vec![]),
+            handled: Cell::new(false),
+        })
+    }
+
+    pub fn pending() -> Rc<Self> {
+        LoxPromise::new(PromiseState::Pending)
+    }
+
+    /// the promise itself, or a promise fulfilled with any other value
+    pub fn of(value: Option<Object>) -> Rc<Self> {
+        match value {
+            Some(Object::Promise(promise)) => promise,
+            value => LoxPromise::new(PromiseState::Fulfilled(value)),
+        }
+    }
+
+    pub fn is_handled(&self) -> bool {
+        self.handled.get()
+    }
+
+    /// error of a rejected promise
+    pub fn rejection(&self) -> Option<LoxError> {
+        match &*self.state.borrow() {
+            PromiseState::Rejected(error) => Some(*error.clone()),
+            _ => None,
+        }
+    }
+
+    /// property access on a promise, `state` is a field and `then` a bound method
+    pub fn property(promise: &Rc<LoxPromise>, name: &Token) -> Result<Object, LoxError> {
+        match name.lexeme.as_str() {
+            "state" => {
+                let state = match &*promise.state.borrow() {
+                    PromiseState::Pending => "pending",
+                    PromiseState::Fulfilled(_) => "fulfilled",
+                    PromiseState::Rejected(_) => "rejected",
+                };
+                Ok(Object::Str(state.into()))
+            }
+            "then" => Ok(PromiseFunction::Then(promise.clone()).object()),
+            _ => Err(LoxError::new_runtime_error(
+                name.clone(),
+                ErrorKind::PropertyError,
+                format!("Undefined property '{}'.", name.lexeme),
+            )),
+        }
+    }
+
+    /// run `reaction` once the promise settles, right away on the event loop if it already has
+    pub fn react(self: &Rc<Self>, interpreter: &mut Interpreter, reaction: Reaction) {
+        self.handled.set(true);
+        if let PromiseState::Pending = &*self.state.borrow() {
+            self.reactions.borrow_mut().push(reaction);
+            return;
+        }
+        interpreter
+            .scheduler()
+            .push(Task::React(reaction, self.clone()));
+    }
+
+    /// settle with `value`, a promise resolved with another promise settles the same way as that one
+    pub fn resolve(
+        self: &Rc<Self>,
+        interpreter: &mut Interpreter,
+        value: Option<Object>,
+        token: &Token,
+    ) {
+        match value {
+            Some(Object::Promise(promise)) if Rc::ptr_eq(&promise, self) => {
+                let error = LoxError::new_runtime_error(
+                    token.clone(),
+                    ErrorKind::TypeError,
+                    "A promise can't be resolved with itself.".into(),
+                );
+                self.reject(interpreter, error);
+            }
+            Some(Object::Promise(promise)) => {
+                promise.react(interpreter, Reaction::Adopt(self.clone()))
+            }
+            value => {
+                self.settle(interpreter, PromiseState::Fulfilled(value));
+            }
+        }
+    }
+
+    pub fn reject(self: &Rc<Self>, interpreter: &mut Interpreter, mut error: LoxError) {
+        interpreter.capture_trace(&mut error);
+        if self.settle(interpreter, PromiseState::Rejected(Box::new(error))) {
+            interpreter.scheduler().rejected(self.clone());
+        }
+    }
+
+    /// a promise settles once, later resolutions are ignored, `false` if it had already settled
+    fn settle(self: &Rc<Self>, interpreter: &mut Interpreter, state: PromiseState) -> bool {
+        if !matches!(&*self.state.borrow(), PromiseState::Pending) {
+            return false;
+        }
+        *self.state.borrow_mut() = state;
+        for reaction in self.reactions.take() {
+            interpreter
+                .scheduler()
+                .push(Task::React(reaction, self.clone()));
+        }
+        true
+    }
+
+    /// value of a fulfilled promise, or the error of a rejected one
+    fn outcome(&self) -> Result<Option<Object>, LoxError> {
+        match &*self.state.borrow() {
+            PromiseState::Fulfilled(value) => Ok(value.clone()),
+            PromiseState::Rejected(error) => Err(*error.clone()),
+            PromiseState::Pending => unreachable!("reactions run once the promise settles"),
+        }
+    }
+}
+
+impl Managed for LoxPromise {
+    fn trace(&self, tracer: &mut Tracer) -> bool {
+        let (Ok(state), Ok(reactions)) = (self.state.try_borrow(), self.reactions.try_borrow())
+        else {
+            return false;
+        };
+        match &*state {
+            PromiseState::Pending => {}
+            PromiseState::Fulfilled(value) => tracer.optional(value),
+            PromiseState::Rejected(error) => tracer.error(error),
+        }
+        reactions.iter().for_each(|reaction| reaction.trace(tracer));
+        true
+    }
+
+    fn clear(&self) {
+        let state = self
+            .state
+            .try_borrow_mut()
+            .map(|mut state| std::mem::replace(&mut *state, PromiseState::Pending));
+        let reactions = self
+            .reactions
+            .try_borrow_mut()
+            .map(|mut reactions| std::mem::take(&mut *reactions));
+        drop((state, reactions));
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.reactions.try_borrow().map_or(0, |r| r.capacity())
+                * std::mem::size_of::<Reaction>()
+    }
+}
+
+impl Debug for LoxPromise {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Display for LoxPromise {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<promise>")
+    }
+}
+
+impl PartialEq for LoxPromise {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Reaction {
+    fn trace(&self, tracer: &mut Tracer) {
+        match self {
+            Reaction::Await(task) => tracer.edge(task),
+            Reaction::Then(then) => {
+                tracer.optional(&then.on_fulfilled);
+                tracer.optional(&then.on_rejected);
+                tracer.edge(&then.promise);
+            }
+            Reaction::Adopt(promise) => tracer.edge(promise),
+            Reaction::All(all, _) => tracer.edge(all),
+        }
+    }
+
+    /// run the reaction to `promise` having settled
+    pub fn run(self, interpreter: &mut Interpreter, promise: &LoxPromise) -> Result<(), LoxError> {
+        let outcome = promise.outcome();
+        match self {
+            Reaction::Await(task) => task.run(interpreter, outcome),
+            Reaction::Then(then) => {
+                let Then {
+                    on_fulfilled,
+                    on_rejected,
+                    promise,
+                    token,
+                } = *then;
+                let (callback, argument) = match outcome {
+                    Ok(value) => match on_fulfilled {
+                        Some(callback) => (callback, value),
+                        None => {
+                            promise.resolve(interpreter, value, &token);
+                            return Ok(());
+                        }
+                    },
+                    Err(error) => match on_rejected {
+                        Some(callback) => (callback, interpreter.caught_value(error)?),
+                        None => {
+                            promise.reject(interpreter, error);
+                            return Ok(());
+                        }
+                    },
+                };
+                match call_back(interpreter, callback, argument, &token) {
+                    Ok(value) => promise.resolve(interpreter, value, &token),
+                    Err(error) => reject_or_raise(interpreter, &promise, error)?,
+                }
+                Ok(())
+            }
+            Reaction::Adopt(promise) => {
+                match outcome {
+                    Ok(value) => {
+                        promise.settle(interpreter, PromiseState::Fulfilled(value));
+                    }
+                    Err(error) => promise.reject(interpreter, error),
+                }
+                Ok(())
+            }
+            Reaction::All(all, index) => {
+                match outcome {
+                    Ok(value) => {
+                        all.values.borrow_mut()[index] = value;
+                        all.remaining.set(all.remaining.get() - 1);
+                        if all.remaining.get() == 0 {
+                            let values = all.values.take();
+                            let list = PromiseState::Fulfilled(Some(LoxList::object(values)));
+                            all.promise.settle(interpreter, list);
+                        }
+                    }
+                    Err(error) => all.promise.reject(interpreter, error),
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// call a callback with `argument`, unless it takes no parameter
+fn call_back(
+    interpreter: &mut Interpreter,
+    callback: Object,
+    argument: Option<Object>,
+    token: &Token,
+) -> Result<Option<Object>, LoxError> {
+    let arguments = match &callback {
+        Object::Function(function) if function.arity().max == Some(0) => vec![],
+        _ => vec![argument],
+    };
+    interpreter.call_value(Some(callback), arguments, vec![], token)
+}
+
+/// errors a callback can catch reject its promise, others such as a return outside of a function are raised
+fn reject_or_raise(
+    interpreter: &mut Interpreter,
+    promise: &Rc<LoxPromise>,
+    error: LoxError,
+) -> Result<(), LoxError> {
+    match error {
+        LoxError::RuntimeError(_) | LoxError::ThrowError(_) => {
+            promise.reject(interpreter, error);
+            Ok(())
+        }
+        error => Err(error),
+    }
+}
+
+impl Managed for All {
+    fn trace(&self, tracer: &mut Tracer) -> bool {
+        let Ok(values) = self.values.try_borrow() else {
+            return false;
+        };
+        values.iter().for_each(|value| tracer.optional(value));
+        tracer.edge(&self.promise);
+        true
+    }
+
+    fn clear(&self) {
+        let values = self
+            .values
+            .try_borrow_mut()
+            .map(|mut v| std::mem::take(&mut *v));
+        drop(values);
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.values.try_borrow().map_or(0, |v| v.capacity())
+                * std::mem::size_of::<Option<Object>>()
+    }
+}
+
+/// a running call of an async function, it settles `promise` with the returned value or the raised error
+pub(crate) struct LoxAsync {
+    /// `None` while the body is running and once it has finished
+    body: RefCell<Option<Resumable>>,
+    promise: Rc<LoxPromise>,
+    /// name of the function, use to report errors
+    name: Token,
+}
+
+impl LoxAsync {
+    /// run the body up to its first await, the returned promise settles once the body finishes
+    pub fn start(
+        interpreter: &mut Interpreter,
+        name: Token,
+        body: Resumable,
+    ) -> Result<Rc<LoxPromise>, LoxError> {
+        let task = heap::track(LoxAsync {
+            body: RefCell::new(Some(body)),
+            promise: LoxPromise::pending(),
+            name,
+        });
+        task.run(interpreter, Ok(None))?;
+        Ok(task.promise.clone())
+    }
+
+    /// resume the body with the value of the promise it awaits, or raise the promise's error in it
+    fn run(
+        self: &Rc<Self>,
+        interpreter: &mut Interpreter,
+        outcome: Result<Option<Object>, LoxError>,
+    ) -> Result<(), LoxError> {
+        let Some(mut body) = self.body.take() else {
+            unreachable!("an async function is only resumed by the promise it awaits")
+        };
+        let resumed = match outcome {
+            Ok(value) => body.resume(interpreter, value),
+            Err(error) => body.throw(interpreter, error),
+        };
+        match resumed {
+            Ok(Resumed::Awaiting(promise)) => {
+                *self.body.borrow_mut() = Some(body);
+                promise.react(interpreter, Reaction::Await(self.clone()));
+                Ok(())
+            }
+            Ok(Resumed::Returned(value)) => {
+                self.promise.resolve(interpreter, value, &self.name);
+                Ok(())
+            }
+            Ok(Resumed::Yielded(_) | Resumed::Waiting(_)) => {
+                unreachable!("async functions only suspend in awaits")
+            }
+            Err(error) => reject_or_raise(interpreter, &self.promise, error),
+        }
+    }
+}
+
+/// the body is away while it runs, like a generator's
+impl Managed for LoxAsync {
+    fn trace(&self, tracer: &mut Tracer) -> bool {
+        let Ok(body) = self.body.try_borrow() else {
+            return false;
+        };
+        if let Some(body) = body.as_ref() {
+            body.trace(tracer);
+        }
+        tracer.edge(&self.promise);
+        true
+    }
+
+    fn clear(&self) {
+        let body = self
+            .body
+            .try_borrow_mut()
+            .ok()
+            .and_then(|mut body| body.take());
+        drop(body);
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self
+                .body
+                .try_borrow()
+                .map_or(0, |body| body.as_ref().map_or(0, Resumable::size))
+    }
+}
+
+/// natives of promises, bound to their promise where they have one
+#[derive(Debug, Clone)]
+pub enum PromiseFunction {
+    /// `Promise(executor)`, `Promise.all`, `Promise.resolve` and `Promise.reject` are read from it
+    Promise,
+    All,
+    /// `Promise.resolve(value)`
+    Resolved,
+    /// `Promise.reject(value)`
+    Rejected,
+    /// `resolve` passed to an executor
+    Resolve(Rc<LoxPromise>),
+    /// `reject` passed to an executor
+    Reject(Rc<LoxPromise>),
+    Then(Rc<LoxPromise>),
+}
+
+impl PromiseFunction {
+    /// the promise the function is bound to
+    pub fn trace(&self, tracer: &mut Tracer) {
+        match self {
+            PromiseFunction::Resolve(promise)
+            | PromiseFunction::Reject(promise)
+            | PromiseFunction::Then(promise) => tracer.edge(promise),
+            _ => {}
+        }
+    }
+
+    pub fn object(self) -> Object {
+        Object::Function(Box::new(LoxCallable::NativeFunction(
+            NativeFunction::Promise(self),
+        )))
+    }
+
+    /// property access on a native, only `Promise` has some
+    pub fn property(&self, name: &Token) -> Result<Object, LoxError> {
+        let function = match (self, name.lexeme.as_str()) {
+            (PromiseFunction::Promise, "all") => PromiseFunction::All,
+            (PromiseFunction::Promise, "resolve") => PromiseFunction::Resolved,
+            (PromiseFunction::Promise, "reject") => PromiseFunction::Rejected,
+            _ => {
+                return Err(LoxError::new_runtime_error(
+                    name.clone(),
+                    ErrorKind::PropertyError,
+                    format!("Undefined property '{}'.", name.lexeme),
+                ))
+            }
+        };
+        Ok(function.object())
+    }
+
+    pub fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Option<Object>>,
+        token: &Token,
+    ) -> Result<Option<Object>, LoxError> {
+        let mut arguments = arguments.into_iter();
+        let argument = arguments.next().flatten();
+        // a value rejected by Lox code is raised again as if it was thrown at the call
+        let thrown = |value: Option<Object>| {
+            LoxError::ThrowError(Throw {
+                keyword: token.clone(),
+                value,
+                trace: None,
+            })
+        };
+        match self {
+            PromiseFunction::Promise => {
+                let promise = LoxPromise::pending();
+                let resolve = PromiseFunction::Resolve(promise.clone()).object();
+                let reject = PromiseFunction::Reject(promise.clone()).object();
+                let executor = vec![Some(resolve), Some(reject)];
+                if let Err(error) = interpreter.call_value(argument, executor, vec![], token) {
+                    reject_or_raise(interpreter, &promise, error)?;
+                }
+                Ok(Some(Object::Promise(promise)))
+            }
+            PromiseFunction::All => {
+                let mut elements = vec![];
+                let mut iterator = LoxIterator::new(interpreter, argument, token)?;
+                while let Some(element) = iterator.next(interpreter)? {
+                    elements.push(LoxPromise::of(element));
+                }
+                let promise = LoxPromise::pending();
+                if elements.is_empty() {
+                    promise.resolve(interpreter, Some(LoxList::object(vec![])), token);
+                    return Ok(Some(Object::Promise(promise)));
+                }
+                let all = heap::track(All {
+                    values: RefCell::new(vec![None; elements.len()]),
+                    remaining: Cell::new(elements.len()),
+                    promise: promise.clone(),
+                });
+                for (index, element) in elements.iter().enumerate() {
+                    element.react(interpreter, Reaction::All(all.clone(), index));
+                }
+                Ok(Some(Object::Promise(promise)))
+            }
+            PromiseFunction::Resolved => {
+                let promise = LoxPromise::pending();
+                promise.resolve(interpreter, argument, token);
+                Ok(Some(Object::Promise(promise)))
+            }
+            PromiseFunction::Rejected => {
+                let promise = LoxPromise::pending();
+                promise.reject(interpreter, thrown(argument));
+                Ok(Some(Object::Promise(promise)))
+            }
+            PromiseFunction::Resolve(promise) => {
+                promise.resolve(interpreter, argument, token);
+                Ok(None)
+            }
+            PromiseFunction::Reject(promise) => {
+                promise.reject(interpreter, thrown(argument));
+                Ok(None)
+            }
+            PromiseFunction::Then(promise) => {
+                let derived = LoxPromise::pending();
+                let reaction = Reaction::Then(Box::new(Then {
+                    on_fulfilled: argument,
+                    on_rejected: arguments.next().flatten(),
+                    promise: derived.clone(),
+                    token: token.clone(),
+                }));
+                promise.react(interpreter, reaction);
+                Ok(Some(Object::Promise(derived)))
+            }
+        }
+    }
+
+    pub fn arity(&self) -> Arity {
+        match self {
+            PromiseFunction::Promise | PromiseFunction::All => Arity::exact(1),
+            PromiseFunction::Resolved
+            | PromiseFunction::Rejected
+            | PromiseFunction::Resolve(_)
+            | PromiseFunction::Reject(_) => Arity {
+                min: 0,
+                max: Some(1),
+            },
+            PromiseFunction::Then(_) => Arity {
+                min: 1,
+                max: Some(2),
+            },
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        let name = match self {
+            PromiseFunction::Promise => "Promise",
+            PromiseFunction::All => "Promise.all",
+            PromiseFunction::Resolved => "Promise.resolve",
+            PromiseFunction::Rejected => "Promise.reject",
+            PromiseFunction::Resolve(_) => "resolve",
+            PromiseFunction::Reject(_) => "reject",
+            PromiseFunction::Then(_) => "promise.then",
+        };
+        format!("<native fn {}>", name)
+    }
+}
@@ -0,0 +1,53 @@
+use crate::number;
+use crate::object::Object;
+use std::fmt::{Display, Formatter};
+
+/// numeric range such as 0..10 or 0..=10, numbers are produced lazily when iterated
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct LoxRange {
+    pub start: f64,
+    pub end: f64,
+    pub inclusive: bool,
+    /// both bounds are ints, so are the numbers the range produces
+    pub integral: bool,
+}
+
+impl LoxRange {
+    /// the bounds must be numbers
+    pub fn new(start: &Object, end: &Object, inclusive: bool) -> Self {
+        LoxRange {
+            start: number::as_f64(start).unwrap_or(f64::NAN),
+            end: number::as_f64(end).unwrap_or(f64::NAN),
+            inclusive,
+            integral: matches!((start, end), (Object::Int(_), Object::Int(_))),
+        }
+    }
+
+    pub fn contains(&self, n: f64) -> bool {
+        if self.inclusive {
+            n >= self.start && n <= self.end
+        } else {
+            n >= self.start && n < self.end
+        }
+    }
+
+    /// the number at `n` as a value of the range
+    pub fn value(&self, n: f64) -> Object {
+        if self.integral {
+            Object::Int(n as i64)
+        } else {
+            Object::Number(n)
+        }
+    }
+}
+
+impl Display for LoxRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let operator = if self.inclusive { "..=" } else { ".." };
+        let bound = |n: f64| match self.value(n) {
+            Object::Number(n) => number::float_to_string(n),
+            int => int.to_string(),
+        };
+        write!(f, "{}{}{}", bound(self.start), operator, bound(self.end))
+    }
+}
@@ -1,51 +1,99 @@
 use crate::class::ClassType;
+use crate::environment::Slot;
 use crate::error::LoxError;
 use crate::expr::assign::Assign;
+use crate::expr::assign_destructure::AssignDestructure;
+use crate::expr::await_::Await;
 use crate::expr::binary::Binary;
 use crate::expr::call::Call;
 use crate::expr::get::Get;
 use crate::expr::grouping::Grouping;
+use crate::expr::index::Index;
+use crate::expr::list::List;
 use crate::expr::literal::Literal;
 use crate::expr::logical::Logical;
+use crate::expr::map::Map;
+use crate::expr::match_::Match;
 use crate::expr::set::Set;
+use crate::expr::set_index::SetIndex;
 use crate::expr::super_::Super;
 use crate::expr::this::This;
 use crate::expr::unary::Unary;
 use crate::expr::variable::Variable;
-use crate::expr::Expr;
+use crate::expr::yield_::Yield;
+use crate::expr::{Expr, NodeId};
 use crate::function::FunctionType;
 use crate::function::FunctionType::NONE;
 use crate::interpreter::Interpreter;
 use crate::lox::Lox;
 use crate::object::Object;
+use crate::pattern::Pattern;
+use crate::resumable::{expression_suspension_point, is_suspension};
 use crate::stmt::block::Block;
+use crate::stmt::export::Export;
 use crate::stmt::expression::Expression;
+use crate::stmt::for_in::ForIn;
 use crate::stmt::function::Function;
+use crate::stmt::import::Import;
 use crate::stmt::print::Print;
 use crate::stmt::r#if::If;
 use crate::stmt::r#return::Return;
+use crate::stmt::r#try::Try;
 use crate::stmt::r#while::While;
+use crate::stmt::throw::Throw;
 use crate::stmt::var::Var;
-use crate::stmt::{class, Stmt};
+use crate::stmt::var_destructure::VarDestructure;
+use crate::stmt::{class, r#enum, r#trait, Stmt};
+use crate::symbol::Symbol;
 use crate::token::Token;
 use crate::{expr, function, stmt};
-use std::collections::HashMap;
-use std::iter::Map;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+/// a name declared in a local scope
+#[derive(Clone, Copy, Debug)]
+struct Local {
+    /// index of the name's value in the scope's environment, names get slots in declaration order
+    index: usize,
+    /// false while the name's initializer is being resolved
+    defined: bool,
+}
 
-pub(crate) struct Resolver {
-    pub interpreter: Interpreter,
-    scopes: Vec<HashMap<String, bool>>,
+pub(crate) struct Resolver<'a> {
+    pub interpreter: &'a mut Interpreter,
+    scopes: Vec<HashMap<Symbol, Local>>,
+    /// names declared with const in each scope, parallel to `scopes`
+    constants: Vec<HashSet<Symbol>>,
+    /// const names declared at the top level so far
+    global_constants: HashSet<Symbol>,
     current_function: FunctionType,
     current_class: ClassType,
+    /// whether the function being resolved is a generator
+    in_generator: bool,
+    /// whether the function being resolved is async
+    in_async: bool,
+    /// whether a call returned here can be a tail call, not in generators, async functions or try statements
+    tail_calls: bool,
+    /// set by a statement that may suspend, consumed by the yield or await expression it contains
+    suspension_allowed: bool,
+    /// method names of the traits declared so far, used to report conflicts at compile time
+    traits: HashMap<Symbol, Vec<Symbol>>,
 }
 
-impl Resolver {
-    pub fn new(interpreter: Interpreter) -> Self {
+impl<'a> Resolver<'a> {
+    pub fn new(interpreter: &'a mut Interpreter) -> Self {
         Self {
             current_function: NONE,
             current_class: ClassType::NONE,
+            in_generator: false,
+            in_async: false,
+            tail_calls: false,
+            suspension_allowed: false,
+            traits: HashMap::new(),
             interpreter,
             scopes: vec![],
+            constants: vec![],
+            global_constants: HashSet::new(),
         }
     }
 
@@ -64,10 +112,39 @@ impl Resolver {
 
     fn begin_scope(&mut self) {
         self.scopes.push(HashMap::new());
+        self.constants.push(HashSet::new());
     }
 
     fn end_scope(&mut self) {
         self.scopes.pop();
+        self.constants.pop();
+    }
+
+    /// record whether a declared name is a constant, no other declaration in the same scope can reuse a constant's name
+    fn declare_constant(&mut self, name: &Token, is_const: bool) {
+        let constants = match self.constants.last_mut() {
+            Some(constants) => constants,
+            None => &mut self.global_constants,
+        };
+        if is_const {
+            constants.insert(name.lexeme.clone());
+        } else if constants.contains(&name.lexeme) {
+            Lox::error_(name, "Can't redeclare a constant.");
+        }
+    }
+
+    /// assignments resolve to the innermost declaration of the name, it must not be a constant
+    fn check_assignable(&mut self, name: &Token) {
+        let scope = (0..self.scopes.len())
+            .rev()
+            .find(|i| self.scopes[*i].contains_key(&name.lexeme));
+        let is_const = match scope {
+            Some(i) => self.constants[i].contains(&name.lexeme),
+            None => self.global_constants.contains(&name.lexeme),
+        };
+        if is_const {
+            Lox::error_(name, "Can't assign to a constant.");
+        }
     }
 
     fn declare(&mut self, name: &Token) {
@@ -79,25 +156,85 @@ impl Resolver {
         };
         if (scope.contains_key(&name.lexeme)) {
             Lox::error_(name, "Already variable with this name in this scope.");
+            return;
         }
-        scope.insert(name.lexeme.clone(), false);
+        let index = scope.len();
+        scope.insert(
+            name.lexeme.clone(),
+            Local {
+                index,
+                defined: false,
+            },
+        );
     }
 
     fn define(&mut self, name: &Token) {
         if self.scopes.is_empty() {
             return;
         }
+        self.define_name(&name.lexeme);
+    }
+
+    /// mark a name of the innermost scope as defined, declaring it first if needed, such as `this` and `super`
+    fn define_name(&mut self, name: &str) {
         if let Some(scope) = self.scopes.last_mut() {
-            scope.insert(name.lexeme.clone(), true);
+            let index = scope.len();
+            scope
+                .entry(name.into())
+                .or_insert(Local {
+                    index,
+                    defined: false,
+                })
+                .defined = true;
         }
     }
 
-    fn resolve_local(&mut self, expr: &mut Expr, name: &Token) {
-        for i in (0..self.scopes.len()).rev() {
-            if let Some(scope) = self.scopes.get(i) {
-                if scope.contains_key(&name.lexeme) {
-                    self.interpreter.resolve(expr, self.scopes.len() - 1 - i);
-                    return;
+    fn resolve_local(&mut self, id: NodeId, name: &Token) {
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if let Some(local) = scope.get(&name.lexeme) {
+                let slot = Slot {
+                    depth,
+                    index: local.index,
+                };
+                self.interpreter.resolve(id, slot);
+                return;
+            }
+        }
+    }
+
+    fn resolve_pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Wildcard | Pattern::Literal(_) => {}
+            Pattern::Binding(name) => {
+                self.declare(name);
+                self.define(name);
+            }
+            Pattern::Value(value) => self.resolve_expr(value),
+            Pattern::Class {
+                constructor,
+                fields,
+                ..
+            } => {
+                self.resolve_expr(constructor);
+                for field in fields {
+                    self.resolve_pattern(field);
+                }
+            }
+            Pattern::List { elements, rest, .. } => {
+                for element in elements {
+                    self.resolve_pattern(element);
+                }
+                if let Some(rest) = rest {
+                    self.declare(rest);
+                    self.define(rest);
+                }
+            }
+            Pattern::Or(alternatives) => {
+                for alternative in alternatives {
+                    if let Some(name) = alternative.bindings().first() {
+                        Lox::error_(name, "Alternatives in an or-pattern can't bind names.");
+                    }
+                    self.resolve_pattern(alternative);
                 }
             }
         }
@@ -106,109 +243,182 @@ impl Resolver {
     fn resolve_function(&mut self, function: &Function, function_type: FunctionType) {
         let enclosing_function = self.current_function;
         self.current_function = function_type;
+        let enclosing_generator = std::mem::replace(&mut self.in_generator, function.is_generator);
+        let enclosing_async = std::mem::replace(&mut self.in_async, function.is_async);
+        let enclosing_tail_calls = std::mem::replace(
+            &mut self.tail_calls,
+            !function.is_generator && !function.is_async,
+        );
+        if function.is_generator && function_type == FunctionType::INITIALIZER {
+            Lox::error_(&function.name, "Can't make an initializer a generator.");
+        }
+        if function.is_async && function_type == FunctionType::INITIALIZER {
+            Lox::error_(&function.name, "Can't make an initializer async.");
+        }
         self.begin_scope();
-        for param in &function.params {
+        for (param, default) in function.params.iter().zip(&function.defaults) {
+            if let Some(default) = default {
+                self.resolve_expr(default);
+            }
             self.declare(param);
             self.define(param);
         }
+        if let Some(rest) = &function.rest {
+            self.declare(rest);
+            self.define(rest);
+        }
         self.resolve(&function.body);
         self.end_scope();
         self.current_function = enclosing_function;
+        self.in_generator = enclosing_generator;
+        self.in_async = enclosing_async;
+        self.tail_calls = enclosing_tail_calls;
     }
 }
 
-impl stmt::Visitor for Resolver {
-    fn visit_expression_stmt(&mut self, stmt: Expression) -> Result<(), LoxError> {
+impl stmt::Visitor for Resolver<'_> {
+    fn visit_expression_stmt(&mut self, stmt: &Expression) -> Result<(), LoxError> {
+        self.suspension_allowed = is_suspension(expression_suspension_point(&stmt.expression));
         self.resolve_expr(&stmt.expression);
         Ok(())
     }
 
-    fn visit_print_stmt(&mut self, stmt: Print) -> Result<(), LoxError> {
+    fn visit_print_stmt(&mut self, stmt: &Print) -> Result<(), LoxError> {
         self.resolve_expr(&stmt.expression);
         Ok(())
     }
 
-    fn visit_var_stmt(&mut self, stmt: Var) -> Result<(), LoxError> {
+    fn visit_var_stmt(&mut self, stmt: &Var) -> Result<(), LoxError> {
         self.declare(&stmt.name);
-        if let Some(initializer) = stmt.initializer {
+        self.declare_constant(&stmt.name, stmt.is_const);
+        self.suspension_allowed = stmt.initializer.as_ref().is_some_and(is_suspension);
+        if let Some(initializer) = &stmt.initializer {
             self.resolve_expr(&initializer);
         }
         self.define(&stmt.name);
         Ok(())
     }
 
-    fn visit_block_stmt(&mut self, stmt: Block) -> Result<(), LoxError> {
+    fn visit_var_destructure_stmt(&mut self, stmt: &VarDestructure) -> Result<(), LoxError> {
+        let names = stmt.target.names();
+        for name in &names {
+            self.declare(&name.name);
+            self.declare_constant(&name.name, false);
+        }
+        self.resolve_expr(&stmt.initializer);
+        for name in &names {
+            self.define(&name.name);
+        }
+        Ok(())
+    }
+
+    fn visit_block_stmt(&mut self, stmt: &Block) -> Result<(), LoxError> {
         self.begin_scope();
         self.resolve(&stmt.statements);
         self.end_scope();
         Ok(())
     }
 
-    fn visit_if_stmt(&mut self, stmt: If) -> Result<(), LoxError> {
+    fn visit_if_stmt(&mut self, stmt: &If) -> Result<(), LoxError> {
         self.resolve_expr(&stmt.condition);
         self.resolve_stmt(&stmt.then_branch);
-        if let Some(else_branch) = stmt.else_branch {
+        if let Some(else_branch) = &stmt.else_branch {
             self.resolve_stmt(&else_branch);
         }
         Ok(())
     }
 
-    fn visit_while_stmt(&mut self, stmt: While) -> Result<(), LoxError> {
+    fn visit_while_stmt(&mut self, stmt: &While) -> Result<(), LoxError> {
         self.resolve_expr(&stmt.condition);
         self.resolve_stmt(&stmt.body);
         Ok(())
     }
 
-    fn visit_function_stmt(&mut self, stmt: Function) -> Result<(), LoxError> {
+    fn visit_function_stmt(&mut self, stmt: &Rc<Function>) -> Result<(), LoxError> {
         self.declare(&stmt.name);
+        self.declare_constant(&stmt.name, false);
         self.define(&stmt.name);
 
         self.resolve_function(&stmt, FunctionType::FUNCTION);
         Ok(())
     }
 
-    fn visit_return_stmt(&mut self, stmt: Return) -> Result<(), LoxError> {
+    fn visit_return_stmt(&mut self, stmt: &Return) -> Result<(), LoxError> {
         if (self.current_function == NONE) {
             Lox::error_(&stmt.keyword, "Can't return from top-level code.");
         }
-        if let Some(expr) = stmt.value {
+        if let Some(expr) = &stmt.value {
             if self.current_function == FunctionType::INITIALIZER {
                 Lox::error_(&stmt.keyword, "Can't return a value from an initializer.");
             }
+            if let Expr::Call(call) = expr {
+                if self.tail_calls {
+                    self.interpreter.tail_call(call.id);
+                }
+            }
             self.resolve_expr(&expr);
         }
         Ok(())
     }
 
-    fn visit_class_stmt(&mut self, stmt: class::Class) -> Result<(), LoxError> {
+    fn visit_class_stmt(&mut self, stmt: &class::Class) -> Result<(), LoxError> {
         let enclosing_class = self.current_class;
         self.current_class = ClassType::CLASS;
 
         self.declare(&stmt.name);
+        self.declare_constant(&stmt.name, false);
         self.define(&stmt.name);
         if let Some(superclass) = &stmt.superclass {
             if stmt.name.lexeme == superclass.name.lexeme {
                 Lox::error_(&superclass.name, "A class can't inherit from itself.");
             }
             self.resolve_expr(&Expr::Variable(superclass.clone()));
-
+            self.current_class = ClassType::SUBCLASS;
+        }
+        let mut owners: HashMap<Symbol, Symbol> = HashMap::new();
+        for r#trait in &stmt.traits {
+            self.resolve_expr(&Expr::Variable(r#trait.clone()));
+            let Some(names) = self.traits.get(&r#trait.name.lexeme).cloned() else {
+                continue;
+            };
+            for name in names {
+                let overridden = stmt.methods.iter().any(|m| m.name.lexeme == name);
+                if let Some(owner) = owners.get(&name) {
+                    if !overridden {
+                        Lox::error_(
+                            &r#trait.name,
+                            &format!(
+                                "Method '{}' is provided by both '{}' and '{}', override it in the class.",
+                                name, owner, r#trait.name.lexeme
+                            ),
+                        );
+                    }
+                }
+                owners.insert(name, r#trait.name.lexeme.clone());
+            }
+        }
+        for field in &stmt.fields {
+            if let Some(initializer) = &field.initializer {
+                self.resolve_expr(initializer);
+            }
+        }
+        if stmt.superclass.is_some() {
             self.begin_scope();
-            self.scopes
-                .last_mut()
-                .map(|map| map.insert("super".into(), true));
+            self.define_name("super");
         }
 
         self.begin_scope();
-        self.scopes
-            .last_mut()
-            .map(|map| map.insert("this".into(), true));
-        for method in stmt.methods {
+        self.define_name("this");
+        for method in &stmt.methods {
             let mut declaration = FunctionType::METHOD;
             if method.name.lexeme == "init" {
                 declaration = FunctionType::INITIALIZER;
             }
             self.resolve_function(&method, declaration);
         }
+        for method in &stmt.class_methods {
+            self.resolve_function(&method, FunctionType::METHOD);
+        }
         self.end_scope();
 
         if stmt.superclass.is_some() {
@@ -217,98 +427,286 @@ impl stmt::Visitor for Resolver {
         self.current_class = enclosing_class;
         Ok(())
     }
+
+    fn visit_enum_stmt(&mut self, stmt: &r#enum::Enum) -> Result<(), LoxError> {
+        self.declare(&stmt.name);
+        self.declare_constant(&stmt.name, false);
+        self.define(&stmt.name);
+        Ok(())
+    }
+
+    fn visit_trait_stmt(&mut self, stmt: &r#trait::Trait) -> Result<(), LoxError> {
+        let enclosing_class = self.current_class;
+        self.current_class = ClassType::TRAIT;
+
+        self.declare(&stmt.name);
+        self.declare_constant(&stmt.name, false);
+        self.define(&stmt.name);
+        let names = stmt.methods.iter().map(|m| m.name.lexeme.clone()).collect();
+        self.traits.insert(stmt.name.lexeme.clone(), names);
+
+        self.begin_scope();
+        self.define_name("this");
+        for method in &stmt.methods {
+            self.resolve_function(method, FunctionType::METHOD);
+        }
+        self.end_scope();
+
+        self.current_class = enclosing_class;
+        Ok(())
+    }
+
+    fn visit_for_in_stmt(&mut self, stmt: &ForIn) -> Result<(), LoxError> {
+        self.resolve_expr(&stmt.iterable);
+        self.begin_scope();
+        self.declare(&stmt.name);
+        self.define(&stmt.name);
+        self.resolve_stmt(&stmt.body);
+        self.end_scope();
+        Ok(())
+    }
+
+    fn visit_throw_stmt(&mut self, stmt: &Throw) -> Result<(), LoxError> {
+        self.resolve_expr(&stmt.value);
+        Ok(())
+    }
+
+    fn visit_try_stmt(&mut self, stmt: &Try) -> Result<(), LoxError> {
+        // a deferred call would escape the catch clause and run after the finally block
+        let enclosing_tail_calls = std::mem::replace(&mut self.tail_calls, false);
+        self.begin_scope();
+        self.resolve(&stmt.try_block);
+        self.end_scope();
+        if let Some(catch) = &stmt.catch {
+            self.begin_scope();
+            self.declare(&catch.name);
+            self.define(&catch.name);
+            self.resolve(&catch.body);
+            self.end_scope();
+        }
+        self.tail_calls = enclosing_tail_calls;
+        if let Some(finally_block) = &stmt.finally_block {
+            self.begin_scope();
+            self.resolve(finally_block);
+            self.end_scope();
+        }
+        Ok(())
+    }
+
+    fn visit_import_stmt(&mut self, stmt: &Import) -> Result<(), LoxError> {
+        for (i, name) in stmt.names.iter().enumerate() {
+            if stmt.names[..i].iter().any(|n| n.lexeme == name.lexeme) {
+                Lox::error_(name, "Name is imported more than once.");
+            }
+            self.declare(name);
+            self.declare_constant(name, false);
+            self.define(name);
+        }
+        if let Some(alias) = &stmt.alias {
+            self.declare(alias);
+            self.declare_constant(alias, false);
+            self.define(alias);
+        }
+        Ok(())
+    }
+
+    fn visit_export_stmt(&mut self, stmt: &Export) -> Result<(), LoxError> {
+        if !self.scopes.is_empty() {
+            Lox::error_(&stmt.keyword, "Can only export top-level declarations.");
+        }
+        self.resolve_stmt(&stmt.declaration);
+        Ok(())
+    }
 }
 
-impl expr::Visitor for Resolver {
-    fn visit_literal_expr(&self, expr: Literal) -> Result<Option<Object>, LoxError> {
+impl expr::Visitor for Resolver<'_> {
+    fn visit_literal_expr(&self, expr: &Literal) -> Result<Option<Object>, LoxError> {
         Ok(None)
     }
 
-    fn visit_grouping_expr(&mut self, expr: Grouping) -> Result<Option<Object>, LoxError> {
+    fn visit_grouping_expr(&mut self, expr: &Grouping) -> Result<Option<Object>, LoxError> {
         self.resolve_expr(&expr.expression);
         Ok(None)
     }
 
-    fn visit_unary_expr(&mut self, expr: Unary) -> Result<Option<Object>, LoxError> {
+    fn visit_unary_expr(&mut self, expr: &Unary) -> Result<Option<Object>, LoxError> {
         self.resolve_expr(&expr.right);
         Ok(None)
     }
 
-    fn visit_binary_expr(&mut self, expr: Binary) -> Result<Option<Object>, LoxError> {
+    fn visit_binary_expr(&mut self, expr: &Binary) -> Result<Option<Object>, LoxError> {
         self.resolve_expr(&expr.left);
         self.resolve_expr(&expr.right);
         Ok(None)
     }
 
-    fn visit_variable_expr(&mut self, expr: Variable) -> Result<Option<Object>, LoxError> {
-        println!("visit_variable_expr scope: {:?}", self.scopes);
+    fn visit_variable_expr(&mut self, expr: &Variable) -> Result<Option<Object>, LoxError> {
         if !self.scopes.is_empty() {
             let exist = self.scopes.last().map(|last| last.get(&expr.name.lexeme));
-            if let Some(Some(&false)) = exist {
+            if let Some(Some(Local { defined: false, .. })) = exist {
                 Lox::error_(
                     &expr.name.clone(),
                     "Can't read local variable in its own initializer.",
                 );
             }
         }
-        self.resolve_local(&mut Expr::variable(expr.name.clone()), &expr.name);
+        self.resolve_local(expr.id, &expr.name);
         Ok(Some(Object::Void))
     }
 
-    fn visit_assign_expr(&mut self, expr: Assign) -> Result<Option<Object>, LoxError> {
+    fn visit_assign_expr(&mut self, expr: &Assign) -> Result<Option<Object>, LoxError> {
         self.resolve_expr(&expr.value);
-        let name = expr.name.clone();
-        self.resolve_local(&mut Expr::Assign(Box::new(expr)), &name);
+        self.check_assignable(&expr.name);
+        self.resolve_local(expr.id, &expr.name);
+        Ok(Some(Object::Void))
+    }
+
+    fn visit_assign_destructure_expr(
+        &mut self,
+        expr: &AssignDestructure,
+    ) -> Result<Option<Object>, LoxError> {
+        self.resolve_expr(&expr.value);
+        for name in expr.target.names() {
+            self.check_assignable(&name.name);
+            self.resolve_local(name.id, &name.name);
+        }
+        Ok(Some(Object::Void))
+    }
+
+    fn visit_match_expr(&mut self, expr: &Match) -> Result<Option<Object>, LoxError> {
+        self.resolve_expr(&expr.subject);
+        let mut exhaustive = false;
+        for arm in &expr.arms {
+            if exhaustive {
+                Lox::warning(&arm.token, "Unreachable match arm.");
+            }
+            self.begin_scope();
+            self.resolve_pattern(&arm.pattern);
+            if let Some(guard) = &arm.guard {
+                self.resolve_expr(guard);
+            }
+            self.resolve_expr(&arm.body);
+            self.end_scope();
+            if arm.guard.is_none() && arm.pattern.is_irrefutable() {
+                exhaustive = true;
+            }
+        }
+        if !exhaustive {
+            Lox::warning(
+                &expr.keyword,
+                "Match has no wildcard arm, it fails when no arm matches.",
+            );
+        }
         Ok(Some(Object::Void))
     }
 
-    fn visit_logical_expr(&mut self, expr: Logical) -> Result<Option<Object>, LoxError> {
+    fn visit_yield_expr(&mut self, expr: &Yield) -> Result<Option<Object>, LoxError> {
+        let allowed = std::mem::replace(&mut self.suspension_allowed, false);
+        if !self.in_generator {
+            Lox::error_(&expr.keyword, "Can't yield outside a generator.");
+        } else if !allowed {
+            Lox::error_(
+                &expr.keyword,
+                "Can only yield in a statement, a variable initializer or an assignment statement.",
+            );
+        }
+        if let Some(value) = &expr.value {
+            self.resolve_expr(value);
+        }
+        Ok(None)
+    }
+
+    fn visit_await_expr(&mut self, expr: &Await) -> Result<Option<Object>, LoxError> {
+        let allowed = std::mem::replace(&mut self.suspension_allowed, false);
+        if !self.in_async {
+            Lox::error_(&expr.keyword, "Can't await outside an async function.");
+        } else if !allowed {
+            Lox::error_(
+                &expr.keyword,
+                "Can only await in a statement, a variable initializer or an assignment statement.",
+            );
+        }
+        self.resolve_expr(&expr.value);
+        Ok(None)
+    }
+
+    fn visit_logical_expr(&mut self, expr: &Logical) -> Result<Option<Object>, LoxError> {
         self.resolve_expr(&expr.left);
         self.resolve_expr(&expr.right);
         Ok(None)
     }
 
-    fn visit_call_expr(&mut self, expr: Call) -> Result<Option<Object>, LoxError> {
+    fn visit_call_expr(&mut self, expr: &Call) -> Result<Option<Object>, LoxError> {
         self.resolve_expr(&expr.callee);
         for argument in &expr.arguments {
             self.resolve_expr(argument);
         }
+        for (_, argument) in &expr.named {
+            self.resolve_expr(argument);
+        }
         Ok(Some(Object::Void))
     }
 
-    fn visit_get_expr(&mut self, expr: Get) -> Result<Option<Object>, LoxError> {
+    fn visit_get_expr(&mut self, expr: &Get) -> Result<Option<Object>, LoxError> {
         self.resolve_expr(&expr.object);
         Ok(Some(Object::Void))
     }
 
-    fn visit_set_expr(&mut self, expr: Set) -> Result<Option<Object>, LoxError> {
+    fn visit_set_expr(&mut self, expr: &Set) -> Result<Option<Object>, LoxError> {
         self.resolve_expr(&expr.value);
         self.resolve_expr(&expr.object);
         Ok(Some(Object::Void))
     }
 
-    fn visit_this_expr(&mut self, mut expr: This) -> Result<Option<Object>, LoxError> {
+    fn visit_this_expr(&mut self, expr: &This) -> Result<Option<Object>, LoxError> {
         if ClassType::NONE == self.current_class {
             Lox::error_(&expr.keyword, "Can't use 'this' outside of a class.");
             return Ok(Some(Object::Void));
         }
-        self.resolve_local(&mut Expr::this(expr.keyword.clone()), &expr.keyword);
+        self.resolve_local(expr.id, &expr.keyword);
         Ok(Some(Object::Void))
     }
 
-    fn visit_super_expr(&mut self, expr: Super) -> Result<Option<Object>, LoxError> {
+    fn visit_super_expr(&mut self, expr: &Super) -> Result<Option<Object>, LoxError> {
         if self.current_class == ClassType::NONE {
             Lox::error_(&expr.keyword, "Can't use 'super' outside of a class.");
+        } else if self.current_class == ClassType::TRAIT {
+            Lox::error_(&expr.keyword, "Can't use 'super' in a trait.");
         } else if self.current_class != ClassType::SUBCLASS {
             Lox::error_(
                 &expr.keyword,
                 "Can't use 'super' in a class with no superclass.",
             );
         }
-        self.resolve_local(
-            &mut Expr::super_(expr.keyword.clone(), expr.method),
-            &expr.keyword,
-        );
+        self.resolve_local(expr.id, &expr.keyword);
+        Ok(Some(Object::Void))
+    }
+
+    fn visit_list_expr(&mut self, expr: &List) -> Result<Option<Object>, LoxError> {
+        for element in &expr.elements {
+            self.resolve_expr(element);
+        }
+        Ok(Some(Object::Void))
+    }
+
+    fn visit_map_expr(&mut self, expr: &Map) -> Result<Option<Object>, LoxError> {
+        for (key, value) in &expr.entries {
+            self.resolve_expr(key);
+            self.resolve_expr(value);
+        }
+        Ok(Some(Object::Void))
+    }
+
+    fn visit_index_expr(&mut self, expr: &Index) -> Result<Option<Object>, LoxError> {
+        self.resolve_expr(&expr.object);
+        self.resolve_expr(&expr.index);
+        Ok(Some(Object::Void))
+    }
+
+    fn visit_set_index_expr(&mut self, expr: &SetIndex) -> Result<Option<Object>, LoxError> {
+        self.resolve_expr(&expr.value);
+        self.resolve_expr(&expr.object);
+        self.resolve_expr(&expr.index);
         Ok(Some(Object::Void))
     }
 }
@@ -0,0 +1,628 @@
+use crate::environment::Environment;
+use crate::error::LoxError;
+use crate::expr::assign::Assign;
+use crate::expr::call::Call;
+use crate::expr::{Expr, NodeId};
+use crate::fiber::Wait;
+use crate::function::lox_function::LoxFunction;
+use crate::function::native_function::NativeFunction;
+use crate::function::LoxCallable;
+use crate::heap::{self, Tracer};
+use crate::interpreter::Interpreter;
+use crate::iterator::LoxIterator;
+use crate::object::Object;
+use crate::promise::LoxPromise;
+use crate::stmt::for_in::ForIn;
+use crate::stmt::r#try::Try;
+use crate::stmt::r#while::While;
+use crate::stmt::Stmt;
+use crate::symbol::Symbol;
+use crate::token::Token;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// what a resumed body did before handing control back
+pub(crate) enum Resumed {
+    Yielded(Option<Object>),
+    /// a fiber called `sleep` or blocked on a channel, it is resumed with the result of the call
+    Waiting(Wait),
+    /// an async function awaits a promise, it is resumed with its value or thrown its error once it settles
+    Awaiting(Rc<LoxPromise>),
+    /// the body ran to its end or returned, it can't be resumed again
+    Returned(Option<Object>),
+}
+
+/// a function body that can stop in the middle and continue later, such as a generator, a fiber or an async function
+///
+/// the recursive interpreter can't suspend, so statements that contain a `yield` are run here instead,
+/// one frame per block, loop or try statement being executed. Statements without a `yield`
+/// are handed to the interpreter as a whole.
+///
+/// A fiber suspends in calls instead: calls used as a statement, a variable initializer or the value
+/// of an assignment statement are run here, Lox functions get frames of their own so that the functions
+/// they call can suspend the fiber too. An async function suspends in await expressions, like a generator in yields
+pub(crate) struct Resumable {
+    /// statements being executed, innermost last
+    frames: Vec<Frame>,
+    /// what the statement the body is suspended at does with the value it is resumed with
+    pending: Option<Pending>,
+    mode: Mode,
+}
+
+/// where a body suspends
+#[derive(Copy, Clone, PartialEq)]
+enum Mode {
+    /// in yield expressions
+    Generator,
+    /// in calls
+    Fiber,
+    /// in await expressions
+    Async,
+}
+
+enum Frame {
+    Block {
+        statements: Vec<Stmt>,
+        next: usize,
+        environment: Rc<RefCell<Environment>>,
+    },
+    While {
+        stmt: Rc<While>,
+        environment: Rc<RefCell<Environment>>,
+    },
+    ForIn {
+        stmt: Rc<ForIn>,
+        iterator: LoxIterator,
+        environment: Rc<RefCell<Environment>>,
+    },
+    /// the block of the current stage is the frame above it
+    Try {
+        stmt: Rc<Try>,
+        stage: Stage,
+        environment: Rc<RefCell<Environment>>,
+    },
+    /// a function called by a fiber, its body is the frame above it
+    Call {
+        function: LoxFunction,
+        pending: Pending,
+    },
+}
+
+enum Stage {
+    Body,
+    Catch,
+    /// holds the error or return that is raised again once the finally block completes
+    Finally(Option<Box<LoxError>>),
+}
+
+enum Pending {
+    /// yield used as a statement
+    Discard,
+    /// var x = yield ...;
+    Define {
+        name: Symbol,
+        is_const: bool,
+        environment: Rc<RefCell<Environment>>,
+    },
+    /// x = yield ...;
+    Assign {
+        name: Token,
+        id: NodeId,
+        environment: Rc<RefCell<Environment>>,
+    },
+}
+
+impl Resumable {
+    /// body of a generator, `environment` holds the parameters, the body runs directly in it
+    pub fn new(body: Vec<Stmt>, environment: Rc<RefCell<Environment>>) -> Self {
+        Resumable {
+            frames: vec![Frame::block(body, environment)],
+            pending: None,
+            mode: Mode::Generator,
+        }
+    }
+
+    /// body of a fiber's function
+    pub fn fiber(body: Vec<Stmt>, environment: Rc<RefCell<Environment>>) -> Self {
+        Resumable {
+            mode: Mode::Fiber,
+            ..Resumable::new(body, environment)
+        }
+    }
+
+    /// body of an async function
+    pub fn asynchronous(body: Vec<Stmt>, environment: Rc<RefCell<Environment>>) -> Self {
+        Resumable {
+            mode: Mode::Async,
+            ..Resumable::new(body, environment)
+        }
+    }
+
+    /// the environments of the frames and what they hold on to, for the heap
+    pub fn trace(&self, tracer: &mut Tracer) {
+        self.frames.iter().for_each(|frame| frame.trace(tracer));
+        if let Some(pending) = &self.pending {
+            pending.trace(tracer);
+        }
+    }
+
+    /// approximate number of bytes of the frames
+    pub fn size(&self) -> usize {
+        std::mem::size_of::<Self>() + self.frames.capacity() * std::mem::size_of::<Frame>()
+    }
+
+    /// run until the next yield or the end of the body, `sent` becomes the value of the yield it was suspended at
+    pub fn resume(
+        &mut self,
+        interpreter: &mut Interpreter,
+        sent: Option<Object>,
+    ) -> Result<Resumed, LoxError> {
+        let result = match self.pending.take() {
+            Some(pending) => Resumable::finish(interpreter, pending, sent).map(|_| None),
+            None => Ok(None),
+        };
+        self.run(interpreter, result)
+    }
+
+    /// raise `error` at the statement the body is suspended at, such as the rejection of an awaited promise
+    pub fn throw(
+        &mut self,
+        interpreter: &mut Interpreter,
+        error: LoxError,
+    ) -> Result<Resumed, LoxError> {
+        self.pending = None;
+        self.run(interpreter, Err(error))
+    }
+
+    fn run(
+        &mut self,
+        interpreter: &mut Interpreter,
+        mut result: Result<Option<Resumed>, LoxError>,
+    ) -> Result<Resumed, LoxError> {
+        loop {
+            match result {
+                Ok(Some(resumed)) => return Ok(resumed),
+                Ok(None) => {}
+                Err(error) => match self.unwind(interpreter, error) {
+                    Ok(()) => {}
+                    Err(LoxError::ReturnError(r)) => return Ok(Resumed::Returned(r.value)),
+                    Err(LoxError::TailCall(call)) => {
+                        return call.call(interpreter).map(Resumed::Returned)
+                    }
+                    Err(error) => return Err(error),
+                },
+            }
+            result = self.step(interpreter);
+        }
+    }
+
+    /// finish a suspended statement, `value` is the result of its yield or call
+    fn finish(
+        interpreter: &mut Interpreter,
+        pending: Pending,
+        value: Option<Object>,
+    ) -> Result<(), LoxError> {
+        match pending {
+            Pending::Discard => Ok(()),
+            Pending::Define {
+                name,
+                is_const,
+                environment,
+            } => {
+                if is_const {
+                    environment.borrow_mut().define_constant(name, value);
+                } else {
+                    environment.borrow_mut().define(name, value);
+                }
+                Ok(())
+            }
+            Pending::Assign {
+                name,
+                id,
+                environment,
+            } => {
+                let assign = Expr::Assign(Box::new(Assign {
+                    name,
+                    value: Expr::literal(value),
+                    id,
+                }));
+                interpreter.evaluate_in(&assign, environment).map(|_| ())
+            }
+        }
+    }
+
+    /// advance the innermost frame by one statement or iteration, `Some` when the body yields
+    fn step(&mut self, interpreter: &mut Interpreter) -> Result<Option<Resumed>, LoxError> {
+        let Some(frame) = self.frames.last_mut() else {
+            return Ok(Some(Resumed::Returned(None)));
+        };
+        match frame {
+            Frame::Block {
+                statements,
+                next,
+                environment,
+            } => {
+                let Some(stmt) = statements.get(*next).cloned() else {
+                    self.frames.pop();
+                    return Ok(None);
+                };
+                *next += 1;
+                let environment = environment.clone();
+                self.start(interpreter, stmt, environment)
+            }
+            Frame::While { stmt, environment } => {
+                let condition = interpreter.evaluate_in(&stmt.condition, environment.clone())?;
+                if interpreter.is_truthy(&condition) {
+                    let body = Frame::block(vec![stmt.body.clone()], environment.clone());
+                    self.frames.push(body);
+                } else {
+                    self.frames.pop();
+                }
+                Ok(None)
+            }
+            Frame::ForIn {
+                stmt,
+                iterator,
+                environment,
+            } => {
+                match iterator.next(interpreter)? {
+                    Some(value) => {
+                        let mut scope = Environment::new_from_enclosing(environment.clone());
+                        scope.define(stmt.name.lexeme.clone(), value);
+                        let body = Frame::block(vec![stmt.body.clone()], heap::environment(scope));
+                        self.frames.push(body);
+                    }
+                    None => {
+                        self.frames.pop();
+                    }
+                }
+                Ok(None)
+            }
+            // the block of the current stage completed normally
+            Frame::Try {
+                stmt,
+                stage,
+                environment,
+            } => {
+                if let Stage::Finally(pending) = stage {
+                    let pending = pending.take();
+                    self.frames.pop();
+                    return match pending {
+                        Some(error) => Err(*error),
+                        None => Ok(None),
+                    };
+                }
+                match stmt.finally_block.clone() {
+                    Some(finally_block) => {
+                        *stage = Stage::Finally(None);
+                        let block = Frame::scope(finally_block, environment);
+                        self.frames.push(block);
+                    }
+                    None => {
+                        self.frames.pop();
+                    }
+                }
+                Ok(None)
+            }
+            // the function's body ran to its end without returning
+            Frame::Call { .. } => {
+                let Some(Frame::Call { function, pending }) = self.frames.pop() else {
+                    unreachable!()
+                };
+                Resumable::finish(interpreter, pending, function.result(None)).map(|_| None)
+            }
+        }
+    }
+
+    /// begin executing a statement of a block
+    fn start(
+        &mut self,
+        interpreter: &mut Interpreter,
+        stmt: Stmt,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<Option<Resumed>, LoxError> {
+        if !suspends(&stmt, self.mode) {
+            interpreter.execute_in(&stmt, environment)?;
+            return Ok(None);
+        }
+        match suspension_point(&stmt) {
+            Some(Expr::Yield(operand)) => {
+                let value = match &operand.value {
+                    Some(value) => interpreter.evaluate_in(value, environment.clone())?,
+                    None => None,
+                };
+                self.pending = Some(Pending::of(&stmt, environment));
+                return Ok(Some(Resumed::Yielded(value)));
+            }
+            Some(Expr::Await(operand)) => {
+                let value = interpreter.evaluate_in(&operand.value, environment.clone())?;
+                self.pending = Some(Pending::of(&stmt, environment));
+                return Ok(Some(Resumed::Awaiting(LoxPromise::of(value))));
+            }
+            Some(Expr::Call(call)) => {
+                let pending = Pending::of(&stmt, environment.clone());
+                return self.call(interpreter, call, pending, environment);
+            }
+            _ => {}
+        }
+        match stmt {
+            Stmt::Block(block) => {
+                self.frames
+                    .push(Frame::scope(block.statements.clone(), &environment));
+                Ok(None)
+            }
+            Stmt::If(stmt) => {
+                let condition = interpreter.evaluate_in(&stmt.condition, environment.clone())?;
+                let branch = if interpreter.is_truthy(&condition) {
+                    Some(stmt.then_branch.clone())
+                } else {
+                    stmt.else_branch.clone()
+                };
+                if let Some(branch) = branch {
+                    self.frames.push(Frame::block(vec![branch], environment));
+                }
+                Ok(None)
+            }
+            Stmt::While(stmt) => {
+                self.frames.push(Frame::While { stmt, environment });
+                Ok(None)
+            }
+            Stmt::ForIn(stmt) => {
+                let iterable = interpreter.evaluate_in(&stmt.iterable, environment.clone())?;
+                let iterator = LoxIterator::new(interpreter, iterable, &stmt.name)?;
+                self.frames.push(Frame::ForIn {
+                    stmt,
+                    iterator,
+                    environment,
+                });
+                Ok(None)
+            }
+            Stmt::Try(stmt) => {
+                let block = Frame::scope(stmt.try_block.clone(), &environment);
+                self.frames.push(Frame::Try {
+                    stmt,
+                    stage: Stage::Body,
+                    environment,
+                });
+                self.frames.push(block);
+                Ok(None)
+            }
+            stmt => interpreter.execute_in(&stmt, environment).map(|_| None),
+        }
+    }
+
+    /// a call made by a fiber, the fiber suspends in `Fiber.yield`, `sleep` and channel operations,
+    /// Lox functions run on frames of their own and other callables are called as usual
+    fn call(
+        &mut self,
+        interpreter: &mut Interpreter,
+        call: &Call,
+        pending: Pending,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<Option<Resumed>, LoxError> {
+        let callee = interpreter.evaluate_in(&call.callee, environment.clone())?;
+        let mut arguments = vec![];
+        for argument in &call.arguments {
+            arguments.push(interpreter.evaluate_in(argument, environment.clone())?);
+        }
+        let mut named = vec![];
+        for (name, argument) in &call.named {
+            named.push((
+                name.clone(),
+                interpreter.evaluate_in(argument, environment.clone())?,
+            ));
+        }
+        if let Some(Object::Function(callable)) = &callee {
+            match callable.as_ref() {
+                LoxCallable::NativeFunction(NativeFunction::Fiber(function)) => {
+                    if let Some(resumed) = function.suspend(&arguments, &call.paren)? {
+                        self.pending = Some(pending);
+                        return Ok(Some(resumed));
+                    }
+                }
+                LoxCallable::LoxFunction(function)
+                    if !function.declaration.is_generator && !function.declaration.is_async =>
+                {
+                    let count = arguments.len() + named.len();
+                    Interpreter::check_arity(callable, count, &call.paren)?;
+                    let environment =
+                        function.bind_arguments(interpreter, arguments, named, &call.paren)?;
+                    let body = Frame::block(function.declaration.body.clone(), environment);
+                    self.frames.push(Frame::Call {
+                        function: function.clone(),
+                        pending,
+                    });
+                    self.frames.push(body);
+                    return Ok(None);
+                }
+                _ => {}
+            }
+        }
+        let value = interpreter.call_value(callee, arguments, named, &call.paren)?;
+        Resumable::finish(interpreter, pending, value).map(|_| None)
+    }
+
+    /// pop frames up to the innermost try statement that handles the error, or up to the end of the body
+    fn unwind(&mut self, interpreter: &mut Interpreter, error: LoxError) -> Result<(), LoxError> {
+        while let Some(frame) = self.frames.pop() {
+            let (stmt, stage, environment) = match frame {
+                Frame::Try {
+                    stmt,
+                    stage,
+                    environment,
+                } => (stmt, stage, environment),
+                Frame::Call { function, pending } => {
+                    let value = match error {
+                        LoxError::ReturnError(r) => Ok(r.value),
+                        // the body runs on frames of their own, the call is made like any other
+                        LoxError::TailCall(call) => call.call(interpreter),
+                        _ => continue,
+                    };
+                    let result = value.and_then(|value| {
+                        Resumable::finish(interpreter, pending, function.result(value))
+                    });
+                    return match result {
+                        Ok(()) => Ok(()),
+                        Err(error) => self.unwind(interpreter, error),
+                    };
+                }
+                _ => continue,
+            };
+            let catchable = matches!(error, LoxError::RuntimeError(_) | LoxError::ThrowError(_));
+            match (stage, &stmt.catch, &stmt.finally_block) {
+                (Stage::Body, Some(catch), _) if catchable => {
+                    let mut scope = Environment::new_from_enclosing(environment.clone());
+                    scope.define(catch.name.lexeme.clone(), interpreter.caught_value(error)?);
+                    let block = Frame::block(catch.body.clone(), heap::environment(scope));
+                    self.frames.push(Frame::Try {
+                        stmt,
+                        stage: Stage::Catch,
+                        environment,
+                    });
+                    self.frames.push(block);
+                    return Ok(());
+                }
+                (Stage::Body | Stage::Catch, _, Some(finally_block)) => {
+                    let block = Frame::scope(finally_block.clone(), &environment);
+                    self.frames.push(Frame::Try {
+                        stmt,
+                        stage: Stage::Finally(Some(Box::new(error))),
+                        environment,
+                    });
+                    self.frames.push(block);
+                    return Ok(());
+                }
+                // an error raised by the finally block replaces the pending one
+                _ => {}
+            }
+        }
+        Err(error)
+    }
+}
+
+impl Frame {
+    fn trace(&self, tracer: &mut Tracer) {
+        match self {
+            Frame::Block { environment, .. } | Frame::While { environment, .. } => {
+                tracer.edge(environment)
+            }
+            Frame::ForIn {
+                iterator,
+                environment,
+                ..
+            } => {
+                iterator.trace(tracer);
+                tracer.edge(environment);
+            }
+            Frame::Try {
+                stage, environment, ..
+            } => {
+                if let Stage::Finally(Some(error)) = stage {
+                    tracer.error(error);
+                }
+                tracer.edge(environment);
+            }
+            Frame::Call { function, pending } => {
+                tracer.edge(&function.closure);
+                pending.trace(tracer);
+            }
+        }
+    }
+
+    fn block(statements: Vec<Stmt>, environment: Rc<RefCell<Environment>>) -> Self {
+        Frame::Block {
+            statements,
+            next: 0,
+            environment,
+        }
+    }
+
+    /// block running in a new scope nested in `enclosing`
+    fn scope(statements: Vec<Stmt>, enclosing: &Rc<RefCell<Environment>>) -> Self {
+        let environment = Environment::new_from_enclosing(enclosing.clone());
+        Frame::block(statements, heap::environment(environment))
+    }
+}
+
+impl Pending {
+    fn trace(&self, tracer: &mut Tracer) {
+        match self {
+            Pending::Discard => {}
+            Pending::Define { environment, .. } | Pending::Assign { environment, .. } => {
+                tracer.edge(environment)
+            }
+        }
+    }
+
+    /// what to do with the result of the yield or call a statement suspends at
+    fn of(stmt: &Stmt, environment: Rc<RefCell<Environment>>) -> Self {
+        match stmt {
+            Stmt::Var(stmt) => Pending::Define {
+                name: stmt.name.lexeme.clone(),
+                is_const: stmt.is_const,
+                environment,
+            },
+            Stmt::Expression(stmt) => match &stmt.expression {
+                Expr::Assign(assign) => Pending::Assign {
+                    name: assign.name.clone(),
+                    id: assign.id,
+                    environment,
+                },
+                _ => Pending::Discard,
+            },
+            _ => Pending::Discard,
+        }
+    }
+}
+
+/// the expression a statement can suspend at, the whole expression of an expression statement,
+/// a variable initializer or the value of an assignment statement
+fn suspension_point(stmt: &Stmt) -> Option<&Expr> {
+    match stmt {
+        Stmt::Expression(stmt) => Some(expression_suspension_point(&stmt.expression)),
+        Stmt::Var(stmt) => stmt.initializer.as_ref(),
+        _ => None,
+    }
+}
+
+/// the expression an expression statement can suspend at, the value of an assignment or the whole expression
+pub(crate) fn expression_suspension_point(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Assign(assign) => &assign.value,
+        expr => expr,
+    }
+}
+
+/// whether an expression is a yield or an await, they are only allowed where a statement can suspend
+pub(crate) fn is_suspension(expr: &Expr) -> bool {
+    matches!(expr, Expr::Yield(_) | Expr::Await(_))
+}
+
+/// whether executing a statement may suspend, a generator suspends in yields, a fiber in calls
+/// and an async function in awaits, nested functions are not part of the body
+fn suspends(stmt: &Stmt, mode: Mode) -> bool {
+    let any = |statements: &[Stmt]| statements.iter().any(|stmt| suspends(stmt, mode));
+    match stmt {
+        Stmt::Block(block) => any(&block.statements),
+        Stmt::If(stmt) => {
+            suspends(&stmt.then_branch, mode)
+                || stmt
+                    .else_branch
+                    .as_ref()
+                    .is_some_and(|branch| suspends(branch, mode))
+        }
+        Stmt::While(stmt) => suspends(&stmt.body, mode),
+        Stmt::ForIn(stmt) => suspends(&stmt.body, mode),
+        Stmt::Try(stmt) => {
+            any(&stmt.try_block)
+                || stmt.catch.as_ref().is_some_and(|catch| any(&catch.body))
+                || stmt.finally_block.as_deref().is_some_and(any)
+        }
+        stmt => match suspension_point(stmt) {
+            Some(Expr::Yield(_)) => mode == Mode::Generator,
+            Some(Expr::Call(_)) => mode == Mode::Fiber,
+            Some(Expr::Await(_)) => mode == Mode::Async,
+            _ => false,
+        },
+    }
+}
@@ -1,5 +1,7 @@
 use crate::lox::Lox;
+use crate::number::{self, Overflow};
 use crate::object::Object;
+use crate::symbol::Symbol;
 use crate::token::token_type::TokenType;
 use crate::token::token_type::TokenType::*;
 use crate::token::Token;
@@ -10,21 +12,36 @@ use std::collections::HashMap;
 static KEY_WORDS: Lazy<HashMap<&'static str, TokenType>> = Lazy::new(|| {
     hash_map! {
         "and" => AND,
+        "catch" => CATCH,
         "class" => CLASS,
+        "async" => ASYNC,
+        "await" => AWAIT,
+        "const" => CONST,
         "else" => ELSE,
+        "enum" => ENUM,
+        "export" => EXPORT,
         "false" => FALSE,
+        "finally" => FINALLY,
         "for" => FOR,
         "fun" => FUN,
         "if" => IF,
+        "import" => IMPORT,
+        "in" => IN,
+        "let" => LET,
+        "match" => MATCH,
         "nil" => NIL,
         "or" => OR,
         "print" => PRINT,
         "return" => RETURN,
         "super" => SUPER,
         "this" => THIS,
+        "throw" => THROW,
+        "trait" => TRAIT,
         "true" => TRUE,
+        "try" => TRY,
         "var" => VAR,
         "while" => WHILE,
+        "yield" => YIELD,
     }
 });
 
@@ -34,6 +51,10 @@ pub(crate) struct Scanner {
     start: usize,
     current: usize,
     line: usize,
+    /// for each open paren, whether it holds a condition or parameter list rather than an operand
+    parens: Vec<bool>,
+    /// whether the last `)` closed a condition or parameter list
+    closed_condition: bool,
 }
 
 impl Scanner {
@@ -44,6 +65,8 @@ impl Scanner {
             start: 0,
             current: 0,
             line: 1,
+            parens: vec![],
+            closed_condition: false,
         }
     }
 
@@ -65,34 +88,73 @@ impl Scanner {
         };
 
         match c {
-            '(' => self.add_token(LEFT_PAREN),
-            ')' => self.add_token(RIGHT_PAREN),
+            '(' => {
+                let condition = self.opens_condition();
+                self.parens.push(condition);
+                self.add_token(LEFT_PAREN);
+            }
+            ')' => {
+                self.closed_condition = self.parens.pop().unwrap_or(false);
+                self.add_token(RIGHT_PAREN);
+            }
             '{' => self.add_token(LEFT_BRACE),
 
             '}' => self.add_token(RIGHT_BRACE),
+            '[' => self.add_token(LEFT_BRACKET),
+            ']' => self.add_token(RIGHT_BRACKET),
             ',' => self.add_token(COMMA),
-            '.' => self.add_token(DOT),
+            '.' => {
+                let token_type = if !self.match_('.') {
+                    DOT
+                } else if self.match_('.') {
+                    DOT_DOT_DOT
+                } else if self.match_('=') {
+                    DOT_DOT_EQUAL
+                } else {
+                    DOT_DOT
+                };
+                self.add_token(token_type);
+            }
 
             '-' => self.add_token(MINUS),
             '+' => self.add_token(PLUS),
             ';' => self.add_token(SEMICOLON),
+            ':' => self.add_token(COLON),
+            '|' => self.add_token(PIPE),
             '*' => self.add_token(STAR),
+            '&' => self.add_token(AMPERSAND),
+            '^' => self.add_token(CARET),
+            '~' => self.add_token(TILDE),
 
             '!' => {
                 let token_type = if self.match_('=') { BANG_EQUAL } else { BANG };
                 self.add_token(token_type);
             }
             '=' => {
-                let token_type = if self.match_('=') { EQUAL_EQUAL } else { EQUAL };
+                let token_type = if self.match_('=') {
+                    EQUAL_EQUAL
+                } else if self.match_('>') {
+                    EQUAL_GREATER
+                } else {
+                    EQUAL
+                };
                 self.add_token(token_type);
             }
             '<' => {
-                let token_type = if self.match_('=') { LESS_EQUAL } else { LESS };
+                let token_type = if self.match_('=') {
+                    LESS_EQUAL
+                } else if self.match_('<') {
+                    LESS_LESS
+                } else {
+                    LESS
+                };
                 self.add_token(token_type);
             }
             '>' => {
                 let token_type = if self.match_('=') {
                     GREATER_EQUAL
+                } else if self.match_('>') {
+                    GREATER_GREATER
                 } else {
                     GREATER
                 };
@@ -100,7 +162,9 @@ impl Scanner {
             }
 
             '/' => {
-                if self.match_('/') {
+                if self.ends_operand() && self.match_('/') {
+                    self.add_token(SLASH_SLASH);
+                } else if self.match_('/') {
                     // A comment goes until the end of the line.
                     while self.peek() != Some('\n') && !self.is_at_end() {
                         self.advance();
@@ -126,6 +190,30 @@ impl Scanner {
         }
     }
 
+    /// `//` right after a value on the same line is floor division, anywhere else it starts a comment
+    fn ends_operand(&self) -> bool {
+        let Some(previous) = self.tokens.last() else {
+            return false;
+        };
+        previous.line == self.line
+            && match previous.r#type {
+                NUMBER | STRING | IDENTIFIER | RIGHT_BRACKET | THIS | TRUE | FALSE | NIL => true,
+                RIGHT_PAREN => !self.closed_condition,
+                _ => false,
+            }
+    }
+
+    /// whether the paren about to open follows `if`, `while`, `for`, `catch`, `match` or a function's name
+    fn opens_condition(&self) -> bool {
+        let types: Vec<TokenType> = self.tokens.iter().rev().take(3).map(|t| t.r#type).collect();
+        matches!(
+            types.as_slice(),
+            [IF | WHILE | FOR | CATCH | MATCH, ..]
+                | [IDENTIFIER, FUN, ..]
+                | [IDENTIFIER, STAR, FUN]
+        )
+    }
+
     fn identifier(&mut self) {
         while self.is_alpha_numeric(self.peek()) {
             self.advance();
@@ -160,14 +248,47 @@ impl Scanner {
             }
         }
 
-        self.add_token2(
-            NUMBER,
-            Some(Object::number(
-                self.source[self.start..self.current]
-                    .parse::<f64>()
-                    .unwrap_or(f64::NAN),
-            )),
-        );
+        // `n` makes a bigint, `r` a rational and `d` a decimal
+        let text = self.source[self.start..self.current].to_string();
+        let suffix = self
+            .peek()
+            .filter(|c| matches!(c, 'n' | 'r' | 'd'))
+            .filter(|_| !self.is_alpha_numeric(self.peek_next()));
+        if let Some(suffix) = suffix {
+            self.advance();
+            let value = number::parse_literal(&text, suffix);
+            if value.is_none() {
+                Lox::error(
+                    self.line,
+                    &format!("Invalid number literal '{}{}'.", text, suffix),
+                );
+            }
+            self.add_token2(NUMBER, value);
+            return;
+        }
+
+        // literals without a fractional part are ints, one too large for an int follows the overflow policy
+        let value = match text.parse::<i64>() {
+            Ok(n) => Object::Int(n),
+            Err(_) if text.contains('.') => Object::number(text.parse::<f64>().unwrap_or(f64::NAN)),
+            Err(_) => match Overflow::from_env() {
+                Overflow::Error => {
+                    Lox::error(
+                        self.line,
+                        &format!(
+                            "Integer literal '{}' is too large, write '{}n' for a bigint.",
+                            text, text
+                        ),
+                    );
+                    Object::Int(0)
+                }
+                Overflow::Promote => Object::number(text.parse::<f64>().unwrap_or(f64::NAN)),
+                Overflow::BigInt => {
+                    number::parse_literal(&text, 'n').unwrap_or(Object::number(f64::NAN))
+                }
+            },
+        };
+        self.add_token2(NUMBER, Some(value));
     }
     fn peek_next(&self) -> Option<char> {
         if self.current + 1 >= self.source.len() {
@@ -193,7 +314,7 @@ impl Scanner {
         self.advance();
         // Trim the surrounding quotes.
         let value = &self.source[self.start + 1..self.current - 1];
-        self.add_token2(STRING, Some(Object::string(value.into())));
+        self.add_token2(STRING, Some(Object::Str(Symbol::intern(value).text())));
     }
     fn match_(&mut self, expected: char) -> bool {
         if self.is_at_end() {
@@ -1,10 +1,18 @@
 use crate::expr::variable::Variable;
 use crate::stmt::function::Function;
+use crate::stmt::var::Var;
 use crate::token::Token;
+use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub(crate) struct Class {
     pub name: Token,
     pub superclass: Option<Variable>,
-    pub methods: Vec<Function>,
+    /// traits after `with`, their methods are copied into the class
+    pub traits: Vec<Variable>,
+    pub methods: Vec<Rc<Function>>,
+    /// methods declared with the class keyword, called on the class object
+    pub class_methods: Vec<Rc<Function>>,
+    /// class-level constants, evaluated once when the class is declared
+    pub fields: Vec<Var>,
 }
@@ -0,0 +1,9 @@
+use crate::token::Token;
+
+/// such as enum Shape { Circle(radius), Rect(w, h), Empty }
+#[derive(Debug, Clone)]
+pub(crate) struct Enum {
+    pub name: Token,
+    /// name and field names of every variant
+    pub variants: Vec<(Token, Vec<Token>)>,
+}
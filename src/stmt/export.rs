@@ -0,0 +1,9 @@
+use crate::stmt::Stmt;
+use crate::token::Token;
+
+/// such as export fun sqrt(n) { ... }, only top level function, class and var declarations can be exported
+#[derive(Debug, Clone)]
+pub(crate) struct Export {
+    pub keyword: Token,
+    pub declaration: Stmt,
+}
@@ -0,0 +1,12 @@
+use crate::expr::Expr;
+use crate::stmt::Stmt;
+use crate::token::Token;
+
+/// such as for (x in list) print x;
+#[derive(Debug, Clone)]
+pub(crate) struct ForIn {
+    /// loop variable, rebound on every iteration
+    pub name: Token,
+    pub iterable: Expr,
+    pub body: Stmt,
+}
@@ -1,3 +1,4 @@
+use crate::expr::Expr;
 use crate::stmt::Stmt;
 use crate::token::Token;
 
@@ -5,5 +6,15 @@ use crate::token::Token;
 pub(crate) struct Function {
     pub name: Token,
     pub params: Vec<Token>,
+    /// default value of each parameter, evaluated at call time when the argument is missing
+    pub defaults: Vec<Option<Expr>>,
+    /// rest parameter, such as args in fun log(fmt, ...args)
+    pub rest: Option<Token>,
     pub body: Vec<Stmt>,
+    /// method declared without a parameter list, such as area { return this.w * this.h; }
+    pub is_getter: bool,
+    /// declared with fun* or *name(), calling it returns a generator instead of running the body
+    pub is_generator: bool,
+    /// declared with async, calling it returns a promise of the result instead of running the body to its end
+    pub is_async: bool,
 }
@@ -0,0 +1,12 @@
+use crate::token::Token;
+
+/// such as import "lib/math.lox" as math; or import { sqrt, PI } from "lib/math.lox";
+#[derive(Debug, Clone)]
+pub(crate) struct Import {
+    /// string token of the module path, relative to the importing file
+    pub path: Token,
+    /// name bound to the whole module
+    pub alias: Option<Token>,
+    /// exported names copied into the current scope
+    pub names: Vec<Token>,
+}
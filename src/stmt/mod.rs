@@ -1,137 +1,309 @@
 pub(crate) mod block;
 pub(crate) mod class;
+pub(crate) mod r#enum;
+pub(crate) mod export;
 pub(crate) mod expression;
+pub(crate) mod for_in;
 pub mod function;
 pub mod r#if;
+pub(crate) mod import;
 pub(crate) mod print;
 pub(crate) mod r#return;
+pub(crate) mod throw;
+pub(crate) mod r#trait;
+pub(crate) mod r#try;
 pub(crate) mod var;
+pub(crate) mod var_destructure;
 pub(crate) mod r#while;
 
+use crate::destructure::Target;
 use crate::error::{LoxError, ParseError};
 use crate::expr::variable::Variable;
 use crate::expr::Expr;
 use crate::object::Object;
 use crate::stmt::function::Function;
 use crate::token::Token;
+use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub(crate) enum Stmt {
     /// such as a+1;
-    Expression(expression::Expression),
+    Expression(Rc<expression::Expression>),
     /// such as print 123;
-    Print(print::Print),
+    Print(Rc<print::Print>),
     /// such as var a = 3; var b;
-    Var(var::Var),
-    Block(block::Block),
-    If(Box<r#if::If>),
-    While(Box<r#while::While>),
-    Function(Box<Function>),
-    Return(r#return::Return),
-    Class(class::Class),
+    Var(Rc<var::Var>),
+    Block(Rc<block::Block>),
+    If(Rc<r#if::If>),
+    While(Rc<r#while::While>),
+    Function(Rc<Function>),
+    Return(Rc<r#return::Return>),
+    Class(Rc<class::Class>),
+    ForIn(Rc<for_in::ForIn>),
+    Throw(Rc<throw::Throw>),
+    Try(Rc<r#try::Try>),
+    Import(Rc<import::Import>),
+    Export(Rc<export::Export>),
+    Trait(Rc<r#trait::Trait>),
+    VarDestructure(Rc<var_destructure::VarDestructure>),
+    Enum(Rc<r#enum::Enum>),
 }
 
 impl Stmt {
+    /// line of the statement's first token that has one
+    pub(crate) fn line(&self) -> Option<usize> {
+        match self {
+            Stmt::Expression(v) => v.expression.line(),
+            Stmt::Print(v) => v.expression.line(),
+            Stmt::Var(v) => Some(v.name.line),
+            Stmt::Block(v) => v.statements.iter().find_map(Stmt::line),
+            Stmt::If(v) => v.condition.line(),
+            Stmt::While(v) => v.condition.line(),
+            Stmt::Function(f) => Some(f.name.line),
+            Stmt::Return(v) => Some(v.keyword.line),
+            Stmt::Class(v) => Some(v.name.line),
+            Stmt::ForIn(v) => Some(v.name.line),
+            Stmt::Throw(v) => Some(v.keyword.line),
+            Stmt::Try(v) => v.try_block.iter().find_map(Stmt::line),
+            Stmt::Import(v) => Some(v.path.line),
+            Stmt::Export(v) => Some(v.keyword.line),
+            Stmt::Trait(v) => Some(v.name.line),
+            Stmt::Enum(v) => Some(v.name.line),
+            Stmt::VarDestructure(v) => v.initializer.line(),
+        }
+    }
+
     pub fn accept<V: Visitor>(&self, visitor: &mut V) -> Result<Option<Object>, LoxError> {
         match self {
-            Stmt::Expression(v) => visitor
-                .visit_expression_stmt(v.clone())
-                .map(|_| Some(Object::Void)),
-            Stmt::Print(v) => visitor
-                .visit_print_stmt(v.clone())
-                .map(|_| Some(Object::Void)),
-            Stmt::Var(v) => visitor
-                .visit_var_stmt(v.clone())
-                .map(|_| Some(Object::Void)),
-            Stmt::Block(v) => visitor
-                .visit_block_stmt(v.clone())
-                .map(|_| Some(Object::Void)),
-            Stmt::If(v) => visitor
-                .visit_if_stmt(*v.clone())
+            Stmt::Expression(v) => visitor.visit_expression_stmt(v).map(|_| Some(Object::Void)),
+            Stmt::Print(v) => visitor.visit_print_stmt(v).map(|_| Some(Object::Void)),
+            Stmt::Var(v) => visitor.visit_var_stmt(v).map(|_| Some(Object::Void)),
+            Stmt::Block(v) => visitor.visit_block_stmt(v).map(|_| Some(Object::Void)),
+            Stmt::If(v) => visitor.visit_if_stmt(v).map(|_| Some(Object::Void)),
+            Stmt::While(v) => visitor.visit_while_stmt(v).map(|_| Some(Object::Void)),
+            Stmt::Function(f) => visitor.visit_function_stmt(f).map(|_| Some(Object::Void)),
+            Stmt::Return(v) => visitor.visit_return_stmt(v).map(|_| Some(Object::Void)),
+            Stmt::Class(v) => visitor.visit_class_stmt(v).map(|_| Some(Object::Void)),
+            Stmt::ForIn(v) => visitor.visit_for_in_stmt(v).map(|_| Some(Object::Void)),
+            Stmt::Throw(v) => visitor.visit_throw_stmt(v).map(|_| Some(Object::Void)),
+            Stmt::Try(v) => visitor.visit_try_stmt(v).map(|_| Some(Object::Void)),
+            Stmt::Import(v) => visitor.visit_import_stmt(v).map(|_| Some(Object::Void)),
+            Stmt::Export(v) => visitor.visit_export_stmt(v).map(|_| Some(Object::Void)),
+            Stmt::Trait(v) => visitor.visit_trait_stmt(v).map(|_| Some(Object::Void)),
+            Stmt::Enum(v) => visitor.visit_enum_stmt(v).map(|_| Some(Object::Void)),
+            Stmt::VarDestructure(v) => visitor
+                .visit_var_destructure_stmt(v)
                 .map(|_| Some(Object::Void)),
-            Stmt::While(v) => visitor
-                .visit_while_stmt(*v.clone())
-                .map(|_| Some(Object::Void)),
-            Stmt::Function(f) => visitor
-                .visit_function_stmt(*f.clone())
-                .map(|_| Some(Object::Void)),
-            Stmt::Return(v) => visitor
-                .visit_return_stmt(v.clone())
-                .map(|_| Some(Object::Void)),
-            Stmt::Class(_) => todo!(),
         }
     }
 
     pub fn print(expression: Expr) -> Self {
-        Stmt::Print(print::Print { expression })
+        Stmt::Print(Rc::new(print::Print { expression }))
     }
     pub fn expression(expression: Expr) -> Self {
-        Stmt::Expression(expression::Expression { expression })
+        Stmt::Expression(Rc::new(expression::Expression { expression }))
     }
 
     pub fn var(token: Token, initializer: Option<Expr>) -> Self {
-        Stmt::Var(var::Var {
+        Stmt::Var(Rc::new(var::Var {
             name: token,
             initializer,
-        })
+            is_const: false,
+        }))
+    }
+
+    pub fn constant(name: Token, initializer: Expr) -> Self {
+        Stmt::Var(Rc::new(var::Var {
+            name,
+            initializer: Some(initializer),
+            is_const: true,
+        }))
+    }
+    pub fn var_destructure(target: Target, initializer: Expr) -> Self {
+        Stmt::VarDestructure(Rc::new(var_destructure::VarDestructure {
+            target,
+            initializer,
+        }))
     }
     pub fn block(statements: Vec<Stmt>) -> Self {
-        Stmt::Block(block::Block { statements })
+        Stmt::Block(Rc::new(block::Block { statements }))
     }
     pub fn r#if(condition: Expr, thenBranch: Stmt, elseBranch: Option<Stmt>) -> Self {
-        Stmt::If(Box::new(r#if::If {
+        Stmt::If(Rc::new(r#if::If {
             condition,
             then_branch: thenBranch,
             else_branch: elseBranch,
         }))
     }
     pub fn r#while(condition: Expr, body: Stmt) -> Self {
-        Stmt::While(Box::new(r#while::While { condition, body }))
+        Stmt::While(Rc::new(r#while::While { condition, body }))
+    }
+
+    pub fn function(
+        name: Token,
+        params: Vec<Token>,
+        defaults: Vec<Option<Expr>>,
+        rest: Option<Token>,
+        body: Vec<Stmt>,
+        is_generator: bool,
+        is_async: bool,
+    ) -> Self {
+        Stmt::Function(Rc::new(function::Function {
+            name,
+            params,
+            defaults,
+            rest,
+            body,
+            is_getter: false,
+            is_generator,
+            is_async,
+        }))
     }
 
-    pub fn function(name: Token, params: Vec<Token>, body: Vec<Stmt>) -> Self {
-        Stmt::Function(Box::new(function::Function { name, params, body }))
+    pub fn getter(name: Token, body: Vec<Stmt>) -> Self {
+        Stmt::Function(Rc::new(function::Function {
+            name,
+            params: vec![],
+            defaults: vec![],
+            rest: None,
+            body,
+            is_getter: true,
+            is_generator: false,
+            is_async: false,
+        }))
     }
 
     pub fn r#return(keyword: Token, value: Expr) -> Self {
-        Stmt::Return(r#return::Return {
+        Stmt::Return(Rc::new(r#return::Return {
             keyword,
             value: Some(value),
-        })
+        }))
+    }
+
+    pub fn for_in(name: Token, iterable: Expr, body: Stmt) -> Self {
+        Stmt::ForIn(Rc::new(for_in::ForIn {
+            name,
+            iterable,
+            body,
+        }))
+    }
+
+    pub fn throw(keyword: Token, value: Expr) -> Self {
+        Stmt::Throw(Rc::new(throw::Throw { keyword, value }))
+    }
+
+    pub fn r#try(
+        try_block: Vec<Stmt>,
+        catch: Option<r#try::Catch>,
+        finally_block: Option<Vec<Stmt>>,
+    ) -> Self {
+        Stmt::Try(Rc::new(r#try::Try {
+            try_block,
+            catch,
+            finally_block,
+        }))
+    }
+
+    pub fn import(path: Token, alias: Option<Token>, names: Vec<Token>) -> Self {
+        Stmt::Import(Rc::new(import::Import { path, alias, names }))
     }
 
-    pub fn class(name: Token, superclass: Option<Variable>, methods: Vec<Function>) -> Self {
-        Stmt::Class(class::Class {
+    pub fn export(keyword: Token, declaration: Stmt) -> Self {
+        Stmt::Export(Rc::new(export::Export {
+            keyword,
+            declaration,
+        }))
+    }
+
+    pub fn r#trait(name: Token, methods: Vec<Rc<Function>>) -> Self {
+        Stmt::Trait(Rc::new(r#trait::Trait { name, methods }))
+    }
+
+    pub fn r#enum(name: Token, variants: Vec<(Token, Vec<Token>)>) -> Self {
+        Stmt::Enum(Rc::new(r#enum::Enum { name, variants }))
+    }
+
+    /// name introduced by a function, class, trait, enum or var declaration
+    pub fn declared_name(&self) -> Option<&Token> {
+        match self {
+            Stmt::Var(v) => Some(&v.name),
+            Stmt::Function(f) => Some(&f.name),
+            Stmt::Class(c) => Some(&c.name),
+            Stmt::Trait(t) => Some(&t.name),
+            Stmt::Enum(e) => Some(&e.name),
+            _ => None,
+        }
+    }
+
+    pub fn class(
+        name: Token,
+        superclass: Option<Variable>,
+        traits: Vec<Variable>,
+        methods: Vec<Rc<Function>>,
+        class_methods: Vec<Rc<Function>>,
+        fields: Vec<var::Var>,
+    ) -> Self {
+        Stmt::Class(Rc::new(class::Class {
             name,
             superclass,
+            traits,
             methods,
-        })
+            class_methods,
+            fields,
+        }))
     }
 }
 
 pub(crate) trait Visitor {
     /// execute expression, ignore result
-    fn visit_expression_stmt(&mut self, stmt: expression::Expression) -> Result<(), LoxError>;
+    fn visit_expression_stmt(&mut self, stmt: &expression::Expression) -> Result<(), LoxError>;
 
     /// print statement
-    fn visit_print_stmt(&mut self, stmt: print::Print) -> Result<(), LoxError>;
+    fn visit_print_stmt(&mut self, stmt: &print::Print) -> Result<(), LoxError>;
 
     /// define var
-    fn visit_var_stmt(&mut self, stmt: var::Var) -> Result<(), LoxError>;
+    fn visit_var_stmt(&mut self, stmt: &var::Var) -> Result<(), LoxError>;
+
+    /// define every name of a destructuring target
+    fn visit_var_destructure_stmt(
+        &mut self,
+        stmt: &var_destructure::VarDestructure,
+    ) -> Result<(), LoxError>;
 
     /// execute block
-    fn visit_block_stmt(&mut self, stmt: block::Block) -> Result<(), LoxError>;
+    fn visit_block_stmt(&mut self, stmt: &block::Block) -> Result<(), LoxError>;
 
     /// execute if statement
-    fn visit_if_stmt(&mut self, stmt: r#if::If) -> Result<(), LoxError>;
+    fn visit_if_stmt(&mut self, stmt: &r#if::If) -> Result<(), LoxError>;
 
     /// execute while statement
-    fn visit_while_stmt(&mut self, stmt: r#while::While) -> Result<(), LoxError>;
+    fn visit_while_stmt(&mut self, stmt: &r#while::While) -> Result<(), LoxError>;
 
     /// define function
-    fn visit_function_stmt(&mut self, stmt: function::Function) -> Result<(), LoxError>;
+    fn visit_function_stmt(&mut self, stmt: &Rc<function::Function>) -> Result<(), LoxError>;
+
+    fn visit_return_stmt(&mut self, stmt: &r#return::Return) -> Result<(), LoxError>;
+
+    fn visit_class_stmt(&mut self, stmt: &class::Class) -> Result<(), LoxError>;
+
+    /// execute for-in loop
+    fn visit_for_in_stmt(&mut self, stmt: &for_in::ForIn) -> Result<(), LoxError>;
+
+    /// throw a value, unwinding until a catch clause
+    fn visit_throw_stmt(&mut self, stmt: &throw::Throw) -> Result<(), LoxError>;
+
+    /// execute try statement
+    fn visit_try_stmt(&mut self, stmt: &r#try::Try) -> Result<(), LoxError>;
+
+    /// load a module and bind it or its exported names
+    fn visit_import_stmt(&mut self, stmt: &import::Import) -> Result<(), LoxError>;
+
+    /// execute a declaration and make its name visible to importers
+    fn visit_export_stmt(&mut self, stmt: &export::Export) -> Result<(), LoxError>;
 
-    fn visit_return_stmt(&mut self, stmt: r#return::Return) -> Result<(), LoxError>;
+    /// define an enum and its variants
+    fn visit_enum_stmt(&mut self, stmt: &r#enum::Enum) -> Result<(), LoxError>;
 
-    fn visit_class_stmt(&mut self, stmt: class::Class) -> Result<(), LoxError>;
+    /// define a trait
+    fn visit_trait_stmt(&mut self, stmt: &r#trait::Trait) -> Result<(), LoxError>;
 }
@@ -0,0 +1,9 @@
+use crate::expr::Expr;
+use crate::token::Token;
+
+/// such as throw "boom";
+#[derive(Debug, Clone)]
+pub(crate) struct Throw {
+    pub keyword: Token,
+    pub value: Expr,
+}
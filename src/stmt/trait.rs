@@ -0,0 +1,10 @@
+use crate::stmt::function::Function;
+use crate::token::Token;
+use std::rc::Rc;
+
+/// such as trait Comparable { compare(other) { ... } }
+#[derive(Debug, Clone)]
+pub(crate) struct Trait {
+    pub name: Token,
+    pub methods: Vec<Rc<Function>>,
+}
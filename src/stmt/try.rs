@@ -0,0 +1,18 @@
+use crate::stmt::Stmt;
+use crate::token::Token;
+
+/// such as try { ... } catch (e) { ... } finally { ... }, at least one of catch and finally is present
+#[derive(Debug, Clone)]
+pub(crate) struct Try {
+    pub try_block: Vec<Stmt>,
+    pub catch: Option<Catch>,
+    /// always executed, even when the try or catch block returns or throws
+    pub finally_block: Option<Vec<Stmt>>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Catch {
+    /// name bound to the caught value
+    pub name: Token,
+    pub body: Vec<Stmt>,
+}
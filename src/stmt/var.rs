@@ -5,4 +5,6 @@ use crate::token::Token;
 pub(crate) struct Var {
     pub name: Token,
     pub initializer: Option<Expr>,
+    /// declared with const, the variable can't be assigned again
+    pub is_const: bool,
 }
@@ -0,0 +1,9 @@
+use crate::destructure::Target;
+use crate::expr::Expr;
+
+/// such as var [a, b] = pair; or var {x, y} = point;
+#[derive(Debug, Clone)]
+pub(crate) struct VarDestructure {
+    pub target: Target,
+    pub initializer: Expr,
+}
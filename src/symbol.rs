@@ -0,0 +1,112 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::rc::Rc;
+
+thread_local! {
+    static SYMBOLS: RefCell<HashSet<Rc<str>>> = RefCell::new(HashSet::new());
+
+    // names looked up on every instance creation, bound method call or operator, interned once
+    pub(crate) static INIT: Symbol = Symbol::intern("init");
+    pub(crate) static THIS: Symbol = Symbol::intern("this");
+    pub(crate) static NEG: Symbol = Symbol::intern("__neg__");
+    pub(crate) static ADD: Symbol = Symbol::intern("__add__");
+    pub(crate) static SUB: Symbol = Symbol::intern("__sub__");
+    pub(crate) static MUL: Symbol = Symbol::intern("__mul__");
+    pub(crate) static DIV: Symbol = Symbol::intern("__div__");
+    pub(crate) static LT: Symbol = Symbol::intern("__lt__");
+    pub(crate) static LE: Symbol = Symbol::intern("__le__");
+    pub(crate) static GT: Symbol = Symbol::intern("__gt__");
+    pub(crate) static GE: Symbol = Symbol::intern("__ge__");
+    pub(crate) static EQ: Symbol = Symbol::intern("__eq__");
+    pub(crate) static INDEX: Symbol = Symbol::intern("__index__");
+}
+
+/// interned string, every symbol with the same text shares one allocation, so cloning one only counts a reference
+/// and comparing or hashing one only looks at the pointer
+///
+/// identifiers and string literals are interned by the scanner, symbols live as long as the interpreter
+#[derive(Clone)]
+pub(crate) struct Symbol(Rc<str>);
+
+impl Symbol {
+    pub fn intern(text: &str) -> Symbol {
+        SYMBOLS.with(|symbols| {
+            if let Some(symbol) = symbols.borrow().get(text) {
+                return Symbol(symbol.clone());
+            }
+            let symbol: Rc<str> = Rc::from(text);
+            symbols.borrow_mut().insert(symbol.clone());
+            Symbol(symbol)
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// the shared text, for a string value
+    pub fn text(&self) -> Rc<str> {
+        self.0.clone()
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Rc::as_ptr(&self.0) as *const u8 as usize).hash(state);
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(text: &str) -> Self {
+        Symbol::intern(text)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(text: String) -> Self {
+        Symbol::intern(&text)
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Debug for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&*self.0, f)
+    }
+}
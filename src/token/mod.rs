@@ -1,4 +1,5 @@
 use crate::object::Object;
+use crate::symbol::Symbol;
 use crate::token::token_type::TokenType;
 use std::fmt::{Display, Formatter, Write};
 
@@ -7,13 +8,13 @@ pub(crate) mod token_type;
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token {
     pub(crate) r#type: TokenType,
-    pub(crate) lexeme: String,
+    pub(crate) lexeme: Symbol,
     pub(crate) literal: Option<Object>,
     pub(crate) line: usize,
 }
 
 impl Token {
-    pub fn new(r#type: TokenType, lexeme: String, literal: Option<Object>, line: usize) -> Self {
+    pub fn new(r#type: TokenType, lexeme: Symbol, literal: Option<Object>, line: usize) -> Self {
         Self {
             r#type,
             lexeme,
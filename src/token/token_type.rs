@@ -5,23 +5,37 @@ pub(crate) enum TokenType {
     RIGHT_PAREN,
     LEFT_BRACE,
     RIGHT_BRACE,
+    LEFT_BRACKET,
+    RIGHT_BRACKET,
     COMMA,
     DOT,
+    DOT_DOT,
+    DOT_DOT_DOT,
+    DOT_DOT_EQUAL,
     MINUS,
     PLUS,
     SEMICOLON,
+    COLON,
+    PIPE,
     SLASH,
     STAR,
+    AMPERSAND,
+    CARET,
 
     // One or two character tokens.
     BANG,
     BANG_EQUAL,
     EQUAL,
     EQUAL_EQUAL,
+    EQUAL_GREATER,
     GREATER,
     GREATER_EQUAL,
     LESS,
     LESS_EQUAL,
+    LESS_LESS,
+    GREATER_GREATER,
+    SLASH_SLASH,
+    TILDE,
 
     // Literals.
     IDENTIFIER,
@@ -30,20 +44,35 @@ pub(crate) enum TokenType {
 
     // Keywords.
     AND,
+    ASYNC,
+    AWAIT,
+    CATCH,
     CLASS,
+    CONST,
     ELSE,
+    ENUM,
+    EXPORT,
     FALSE,
+    FINALLY,
     FUN,
     FOR,
     IF,
+    IMPORT,
+    IN,
+    LET,
+    MATCH,
     NIL,
     OR,
     PRINT,
     RETURN,
     SUPER,
     THIS,
+    THROW,
+    TRAIT,
     TRUE,
+    TRY,
     VAR,
     WHILE,
+    YIELD,
     EOF,
 }
@@ -0,0 +1,48 @@
+use crate::function::lox_function::LoxFunction;
+use crate::heap::{Managed, Tracer};
+use crate::symbol::Symbol;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// a named set of methods, mixed into classes with `class A with T { }`
+#[derive(Clone, Debug)]
+pub(crate) struct LoxTrait {
+    pub name: Symbol,
+    pub methods: HashMap<Symbol, LoxFunction>,
+}
+
+impl LoxTrait {
+    pub fn new(name: Symbol, methods: HashMap<Symbol, LoxFunction>) -> Self {
+        LoxTrait { name, methods }
+    }
+}
+
+/// traits are immutable like classes, a cycle through one goes through the environment its methods close over
+impl Managed for LoxTrait {
+    fn trace(&self, tracer: &mut Tracer) -> bool {
+        self.methods
+            .values()
+            .for_each(|method| tracer.edge(&method.closure));
+        true
+    }
+
+    fn clear(&self) {}
+
+    fn size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.methods.capacity()
+                * (std::mem::size_of::<Symbol>() + std::mem::size_of::<LoxFunction>())
+    }
+}
+
+impl Display for LoxTrait {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<trait {}>", self.name)
+    }
+}
+
+impl PartialEq for LoxTrait {
+    fn eq(&self, other: &Self) -> bool {
+        self.name.eq(&other.name)
+    }
+}
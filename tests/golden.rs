@@ -0,0 +1,109 @@
+//! runs every script in `tests/lox` and compares what it prints with the comments in it:
+//!
+//! - `// expect: <line>` a line of standard output
+//! - `// expect error: <line>` a line of standard error
+//! - `// expect exit: <code>` the exit code, 0 when there is none
+//! - `// env: <NAME>=<value>` an environment variable the script runs with
+//!
+//! paths in the output are relative to `tests/lox`, so that they don't depend on where the repository is, and
+//! every script runs twice, the second time with `LOX_GC_STRESS` set so that the collector runs on every allocation
+use std::path::Path;
+use std::process::Command;
+
+#[derive(Debug, Default)]
+struct Expected {
+    stdout: Vec<String>,
+    stderr: Vec<String>,
+    exit: i32,
+    env: Vec<(String, String)>,
+}
+
+fn expected(source: &str) -> Expected {
+    let mut expected = Expected::default();
+    for line in source.lines() {
+        // `//` is also floor division, the directive is the first `// ` that starts one
+        for (index, _) in line.match_indices("// ") {
+            if directive(&mut expected, &line[index + 3..]) {
+                break;
+            }
+        }
+    }
+    expected
+}
+
+fn directive(expected: &mut Expected, comment: &str) -> bool {
+    if let Some(text) = comment.strip_prefix("expect: ") {
+        expected.stdout.push(text.to_string());
+    } else if let Some(text) = comment.strip_prefix("expect error: ") {
+        expected.stderr.push(text.to_string());
+    } else if let Some(code) = comment.strip_prefix("expect exit: ") {
+        expected.exit = code.trim().parse().expect("exit code");
+    } else if let Some((name, value)) = comment
+        .strip_prefix("env: ")
+        .and_then(|env| env.split_once('='))
+    {
+        expected
+            .env
+            .push((name.to_string(), value.trim().to_string()));
+    } else {
+        return false;
+    }
+    true
+}
+
+fn run(path: &Path, expected: &Expected, stress: bool) -> Result<(), String> {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_r-lox"));
+    command.arg(path).envs(expected.env.iter().cloned());
+    if stress {
+        command.env("LOX_GC_STRESS", "1");
+    }
+    let output = command.output().map_err(|e| e.to_string())?;
+    let directory = format!("{}/", path.parent().expect("directory").display());
+    let lines = |bytes: &[u8]| {
+        String::from_utf8_lossy(bytes)
+            .lines()
+            .map(|line| line.replace(&directory, ""))
+            .collect::<Vec<_>>()
+    };
+    let (stdout, stderr) = (lines(&output.stdout), lines(&output.stderr));
+    let exit = output.status.code().unwrap_or(-1);
+    if stdout == expected.stdout && stderr == expected.stderr && exit == expected.exit {
+        return Ok(());
+    }
+    Err(format!(
+        "{}{}:\nexpected stdout {:#?}\n     got stdout {:#?}\nexpected stderr {:#?}\n     got stderr {:#?}\n\
+         expected exit {}, got {}",
+        path.display(),
+        if stress { " with LOX_GC_STRESS" } else { "" },
+        expected.stdout,
+        stdout,
+        expected.stderr,
+        stderr,
+        expected.exit,
+        exit
+    ))
+}
+
+#[test]
+fn golden() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/lox");
+    let mut scripts: Vec<_> = std::fs::read_dir(&directory)
+        .expect("tests/lox")
+        .map(|entry| entry.expect("script").path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "lox"))
+        .collect();
+    scripts.sort();
+    assert!(!scripts.is_empty(), "no scripts in {}", directory.display());
+
+    let mut failures = vec![];
+    for script in &scripts {
+        let source = std::fs::read_to_string(script).expect("script");
+        let expected = expected(&source);
+        for stress in [false, true] {
+            if let Err(failure) = run(script, &expected, stress) {
+                failures.push(failure);
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}